@@ -1,18 +1,35 @@
 use std::{
     fs::File,
     io::{BufRead, BufReader, SeekFrom},
+    path::PathBuf,
     thread::yield_now,
     time::Instant,
 };
 
 use arc_reader::archive::Archive;
 use clap::Parser;
-use hash40::Hash40;
 
 #[derive(Parser)]
 pub enum Args {
     Load,
-    Inspect { offset: String, how_much: String },
+    Inspect {
+        offset: String,
+        how_much: String,
+    },
+    Whatis {
+        archive: PathBuf,
+        offset: String,
+        #[arg(long)]
+        hashes: Option<PathBuf>,
+    },
+}
+
+fn parse_number(number: &str) -> u64 {
+    if let Some(hex) = number.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).unwrap()
+    } else {
+        number.parse::<u64>().unwrap()
+    }
 }
 
 fn print_hex_values<R: std::io::Read + std::io::Seek>(
@@ -58,11 +75,7 @@ fn main() {
 
     match args {
         Args::Load => {
-            Hash40::label_map()
-                .lock()
-                .unwrap()
-                .add_labels_from_path("/Users/blujay/Downloads/Hashes_all")
-                .unwrap();
+            Archive::load_labels("/Users/blujay/Downloads/Hashes_all").unwrap();
 
             let mut file =
                 BufReader::new(File::open("/Users/blujay/Downloads/13.0.1.arc").unwrap());
@@ -75,22 +88,43 @@ fn main() {
             std::fs::write("./second.bin", second).unwrap();
         }
         Args::Inspect { offset, how_much } => {
-            let offset = if offset.starts_with("0x") {
-                u64::from_str_radix(offset.strip_prefix("0x").unwrap(), 16).unwrap()
-            } else {
-                offset.parse::<u64>().unwrap()
-            };
-
-            let how_much = if how_much.starts_with("0x") {
-                usize::from_str_radix(how_much.strip_prefix("0x").unwrap(), 16).unwrap()
-            } else {
-                how_much.parse::<usize>().unwrap()
-            };
+            let offset = parse_number(&offset);
+            let how_much = parse_number(&how_much) as usize;
 
             let mut file =
                 BufReader::new(File::open("/Users/blujay/Downloads/13.0.1.arc").unwrap());
 
             print_hex_values(&mut file, offset, how_much);
         }
+        Args::Whatis {
+            archive,
+            offset,
+            hashes,
+        } => {
+            if let Some(hashes) = hashes {
+                Archive::load_labels(hashes).unwrap();
+            }
+
+            let offset = parse_number(&offset);
+            let arc = Archive::read(&mut BufReader::new(File::open(archive).unwrap())).unwrap();
+
+            let Some((data, location)) = arc.file_data_at_offset(offset) else {
+                println!("{offset:#x} is not inside of any file");
+                return;
+            };
+
+            let path = arc
+                .file_data_owner(data)
+                .map_or_else(|| String::from("<no path>"), |path| path.path().to_string());
+
+            println!(
+                "{offset:#x} is {:#x} bytes into {path}",
+                offset - location.absolute_offset
+            );
+            println!("  file data:         {:#x}", data.get());
+            println!("  data offset:       {:#x}", location.absolute_offset);
+            println!("  compressed size:   {:#x}", location.compressed_size);
+            println!("  decompressed size: {:#x}", location.decompressed_size);
+        }
     }
 }
@@ -0,0 +1,9 @@
+//! The `arc_reader` Python extension module, see [`arc_reader::python`]
+
+use pyo3::prelude::*;
+
+#[pymodule]
+#[pyo3(name = "arc_reader")]
+fn arc_reader_py(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    arc_reader::python::register(m)
+}
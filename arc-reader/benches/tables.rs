@@ -0,0 +1,78 @@
+use arc_reader::archive::{resource::ResourceTables, Archive, ArchiveBuilder};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+/// The number of files in the synthetic archive
+const FILE_COUNT: usize = 0x4000;
+
+fn file_path(index: usize) -> String {
+    format!(
+        "fighter/bench/model/body/c{:02}/file_{index:05}.bin",
+        index % 8
+    )
+}
+
+/// Builds an archive with [`FILE_COUNT`] small files spread across 8 packages, so that every run
+/// measures the same tables
+fn synthetic_archive() -> Archive {
+    let mut builder = ArchiveBuilder::new();
+    builder.bucket_count(0x400);
+    for index in 0..FILE_COUNT {
+        builder.add_package_file(
+            format!("fighter/bench/c{:02}", index % 8),
+            file_path(index),
+            (index as u32).to_le_bytes(),
+        );
+    }
+
+    Archive::from_slice(&builder.build()).expect("synthetic archive should be readable")
+}
+
+fn tables(c: &mut Criterion) {
+    let archive = synthetic_archive();
+    let tables = archive.quick_serialize();
+
+    let mut group = c.benchmark_group("tables");
+    group.throughput(Throughput::Bytes(tables.len() as u64));
+
+    group.bench_function("from_bytes", |b| {
+        b.iter_batched(
+            || tables.clone().into_boxed_slice(),
+            |bytes| ResourceTables::from_bytes(bytes).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("quick_serialize", |b| b.iter(|| archive.quick_serialize()));
+
+    group.bench_function("serialize_tables", |b| {
+        b.iter(|| archive.serialize_tables().unwrap())
+    });
+
+    group.bench_function("serialized_tables_size", |b| {
+        b.iter(|| archive.serialized_tables_size())
+    });
+
+    group.finish();
+}
+
+fn lookups(c: &mut Criterion) {
+    let archive = synthetic_archive();
+    let paths = (0..FILE_COUNT).map(file_path).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("lookups");
+    group.throughput(Throughput::Elements(FILE_COUNT as u64));
+
+    group.bench_function("file_path", |b| {
+        b.iter(|| {
+            paths
+                .iter()
+                .filter(|path| archive.lookup_file_path(path.as_str()).is_some())
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, tables, lookups);
+criterion_main!(benches);
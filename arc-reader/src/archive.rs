@@ -1,24 +1,81 @@
+#[cfg(feature = "std")]
 use byteorder::{ByteOrder, LittleEndian};
+#[cfg(feature = "std")]
+use hash40::Hash40;
 
-use crate::{io::ReadBinExt, BinaryRepr, IntoHash};
-use std::io::{Read, Seek, SeekFrom};
+use crate::BinaryRepr;
+#[cfg(feature = "std")]
+use crate::{
+    index::{Index, INVALID_INDEX},
+    io::ReadBinExt,
+    ArchiveError, Decompressor, DuplicatePath, IntoHash, Locale, RoundtripMismatch,
+    ZstdDecompressor,
+};
+#[cfg(feature = "std")]
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
+    io::{Read, Seek, SeekFrom, Write},
+};
 
 mod data;
 pub use data::*;
 
+#[cfg(all(feature = "std", not(target_os = "switch")))]
+mod builder;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
 mod containers;
+#[cfg(feature = "std")]
+mod extract;
+#[cfg(feature = "std")]
+mod graph;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+mod options;
+#[cfg(feature = "std")]
 pub mod resource;
+#[cfg(feature = "std")]
+mod shared_reader;
+#[cfg(feature = "std")]
+mod source;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "std")]
+mod validate;
+#[cfg(all(feature = "std", not(target_os = "switch")))]
+mod write;
 
+#[cfg(all(feature = "std", not(target_os = "switch")))]
+pub use builder::ArchiveBuilder;
+#[cfg(feature = "std")]
+pub use containers::{BucketLookup, BucketStats, IndexLookup};
+#[cfg(feature = "std")]
+pub use extract::{ExtractProgress, ExtractReport};
+#[cfg(feature = "std")]
+pub use lazy::LazyArchive;
+#[cfg(feature = "std")]
+pub use options::ArchiveReadOptions;
+#[cfg(feature = "std")]
+pub use shared_reader::{SharedArchiveCursor, SharedArchiveReader};
+#[cfg(feature = "std")]
+pub use source::{FileSource, OverlaySource, PassthroughSource};
+
+#[cfg(feature = "std")]
 use self::{
-    containers::{BucketLookup, IndexLookup, Table, TableMut, TableRef, TableSliceRef},
-    file_data::FileData,
-    file_desc::FileDesc,
+    containers::{Table, TableMut, TableRef, TableSliceRef},
+    file_data::{Compression, DataLocation, DataRegion, FileData, FileDataSpec, FileLocation},
+    file_desc::{FileDesc, LoadMethod},
     file_entity::FileEntity,
-    file_group::FileGroup,
+    file_group::{FileGroup, GroupRegion},
     file_info::FileInfo,
-    file_package::{FilePackage, FilePackageChild},
+    file_package::{FilePackage, FilePackageChild, SubPackageRef},
     file_path::FilePath,
-    resource::ResourceTables,
+    resource::{
+        CompactionReport, LocaleRegionEntry, Reachable, ResourceTableHeader, ResourceTables,
+        TableSizes,
+    },
     stream_data::StreamData,
     stream_desc::StreamDesc,
     stream_folder::StreamFolder,
@@ -37,17 +94,101 @@ pub struct ArchiveMetadata {
     unknown_table_offset: u64,
 }
 
+#[cfg(feature = "std")]
 impl ArchiveMetadata {
     const MAGIC: u64 = 0xABCDEF9876543210;
+
+    /// Reads the metadata at the current location, checking its magic
+    fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, std::io::Error> {
+        // SAFETY: Confirms that the metadata is proper by checking the magic after reading it
+        let metadata = unsafe { reader.read_binary::<ArchiveMetadata>()? };
+        if metadata.magic != ArchiveMetadata::MAGIC {
+            return Err(ArchiveError::BadMagic {
+                expected: ArchiveMetadata::MAGIC,
+                found: metadata.magic,
+            }
+            .into());
+        }
+
+        Ok(metadata)
+    }
+
+    /// Reads the metadata at the start of the archive, followed by the decompressed resource section
+    fn read_with_resource<R: Read + Seek, D: Decompressor + ?Sized>(
+        reader: &mut R,
+        decompressor: &D,
+    ) -> Result<(Self, Box<[u8]>), std::io::Error> {
+        let metadata = Self::read(reader)?;
+
+        reader.seek(SeekFrom::Start(metadata.resource_table_offset))?;
+
+        Ok((metadata, reader.read_compressed_data(decompressor)?))
+    }
+
+    /// Like [`Self::read_with_resource`], but reads every section in the chain of compressed
+    /// sections that starts at the resource table offset and ends at the user table offset
+    fn read_with_chained_resource<R: Read + Seek, D: Decompressor + ?Sized>(
+        reader: &mut R,
+        decompressor: &D,
+    ) -> Result<(Self, Box<[u8]>), std::io::Error> {
+        let metadata = Self::read(reader)?;
+
+        reader.seek(SeekFrom::Start(metadata.resource_table_offset))?;
+
+        let resource =
+            reader.read_all_compressed_sections(decompressor, metadata.user_table_offset)?;
+        Ok((metadata, resource.into_boxed_slice()))
+    }
+}
+
+unsafe impl BinaryRepr for ArchiveMetadata {}
+
+/// Summary of how many files are in an archive and how many of them share data, see [`Archive::file_stats`]
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FileStats {
+    /// The number of file paths
+    pub file_paths: usize,
+
+    /// The number of distinct file entities pointed to by the file paths, which is the number of
+    /// physically unique files
+    pub unique_entities: usize,
+
+    /// The number of file infos that are marked as shared between packages
+    pub shared_infos: usize,
+}
+
+/// Rows that can not be reached from any file path or package, see [`Archive::orphans`]
+///
+/// Each list is sorted by index
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Orphans {
+    pub file_entity: Vec<Index<FileEntity>>,
+    pub file_info: Vec<Index<FileInfo>>,
+    pub file_desc: Vec<Index<FileDesc>>,
+    pub file_data: Vec<Index<FileData>>,
 }
 
-impl BinaryRepr for ArchiveMetadata {}
+#[cfg(feature = "std")]
+impl Orphans {
+    /// Checks if every row is reachable
+    pub fn is_empty(&self) -> bool {
+        self.file_entity.is_empty()
+            && self.file_info.is_empty()
+            && self.file_desc.is_empty()
+            && self.file_data.is_empty()
+    }
+}
 
+#[cfg(feature = "std")]
+#[derive(Clone)]
 pub struct Archive {
     metadata: ArchiveMetadata,
-    resource: ResourceTables,
+    pub(crate) resource: ResourceTables,
 }
 
+#[cfg(feature = "std")]
 macro_rules! decl_lookup {
     ($($name:ident => $t:ty),*) => {
         paste::paste! {
@@ -66,6 +207,7 @@ macro_rules! decl_lookup {
     }
 }
 
+#[cfg(feature = "std")]
 macro_rules! decl_access {
     ($($name:ident => $t:ty),*) => {
         paste::paste! {
@@ -74,6 +216,26 @@ macro_rules! decl_access {
                     self.resource.$name.len()
                 }
 
+                /// Number of rows that were read from the archive, the rest were added afterwards
+                pub fn [<num_fixed_ $name>](&self) -> usize {
+                    self.resource.$name.fixed_len()
+                }
+
+                /// Number of rows that were added after reading the archive
+                pub fn [<num_dynamic_ $name>](&self) -> usize {
+                    self.resource.$name.dynamic_len()
+                }
+
+                /// Checks if the row at `index` was read from the archive, rather than added afterwards
+                pub fn [<is_base_game_ $name>](&self, index: Index<$t>) -> bool {
+                    self.resource.$name.is_base_game(index.get())
+                }
+
+                /// Gets the row of the table at `index`
+                pub fn $name(&self, index: Index<$t>) -> Option<TableRef<'_, $t>> {
+                    self.[<get_ $name>](index.get())
+                }
+
                 pub(crate) fn [<get_ $name>](&self, index: u32) -> Option<TableRef<'_, $t>> {
                     TableRef::new(self, &self.resource.$name, index)
                 }
@@ -94,6 +256,52 @@ macro_rules! decl_access {
     }
 }
 
+#[cfg(feature = "std")]
+macro_rules! decl_remove {
+    ($($name:ident => $t:ty),*) => {
+        paste::paste! {
+            $(
+                /// Removes a row from the dynamic region of the table, shifting every row after it down by one
+                /// and updating all of the references to the rows that moved.
+                ///
+                /// Rows that were read from the archive cannot be removed, in which case this returns [`None`].
+                /// The caller must make sure that nothing references the removed row. The version history
+                /// is left as it is.
+                pub fn [<remove_dynamic_ $name>](&mut self, index: Index<$t>) -> Option<$t> {
+                    let index = index.get();
+                    let len = self.resource.$name.len() as u32;
+                    let package_len = self.resource.package_len();
+                    let value = self.resource.$name.remove_dynamic(index)?;
+
+                    let order = (0..len).filter(|row| *row != index).collect::<Vec<_>>();
+                    self.resource.remap_rows::<$t>(&order, package_len);
+                    Some(value)
+                }
+
+                /// Removes a row from the dynamic region of the table, moving the last row of the table into its place
+                /// and updating all of the references to the moved row.
+                ///
+                /// Rows that were read from the archive cannot be removed, in which case this returns [`None`].
+                /// The caller must make sure that nothing references the removed row. The version history
+                /// is left as it is.
+                pub fn [<swap_remove_dynamic_ $name>](&mut self, index: Index<$t>) -> Option<$t> {
+                    let index = index.get();
+                    let last = (self.resource.$name.len() as u32).checked_sub(1)?;
+                    let package_len = self.resource.package_len();
+                    let value = self.resource.$name.swap_remove_dynamic(index)?;
+
+                    let order = (0..last)
+                        .map(|row| if row == index { last } else { row })
+                        .collect::<Vec<_>>();
+                    self.resource.remap_rows::<$t>(&order, package_len);
+                    Some(value)
+                }
+            )*
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 impl Archive {
     decl_lookup! {
         file_path => FilePath,
@@ -116,39 +324,1520 @@ impl Archive {
         stream_data => StreamData
     }
 
+    /// Loads a newline separated list of labels from a file into the global [`Hash40::label_map`],
+    /// returning the number of labels that were read
+    ///
+    /// Labels are used when displaying hashes, and when resolving paths in [`Self::extract_all`]
+    pub fn load_labels(path: impl AsRef<std::path::Path>) -> std::io::Result<usize> {
+        Self::load_labels_from_reader(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    /// Loads a newline separated list of labels into the global [`Hash40::label_map`], returning the
+    /// number of labels that were read
+    ///
+    /// Empty lines are skipped. See [`Self::load_labels`]
+    pub fn load_labels_from_reader<R: std::io::BufRead>(reader: R) -> std::io::Result<usize> {
+        let labels = reader
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let count = labels.len();
+        Hash40::label_map().lock().unwrap().add_labels(labels);
+
+        Ok(count)
+    }
+
+    /// Collects every distinct hash in the file path, file package and stream tables that has no label
+    /// in the global [`Hash40::label_map`], sorted by value
+    ///
+    /// Empty hashes are not included
+    pub fn unknown_hashes(&self) -> Vec<Hash40> {
+        let mut hashes = BTreeSet::new();
+
+        for (_, path) in self.resource.file_path.iter() {
+            hashes.extend([
+                path.path(),
+                path.extension(),
+                path.parent(),
+                path.file_name(),
+            ]);
+        }
+
+        for (_, package) in self.resource.file_package.iter() {
+            hashes.extend([
+                package.path(),
+                package.name(),
+                package.parent(),
+                package.lifetime(),
+            ]);
+        }
+
+        for (_, child) in self.resource.file_package_child.iter() {
+            hashes.insert(child.path());
+        }
+
+        for (_, folder) in self.resource.stream_folder.iter() {
+            hashes.insert(folder.name());
+        }
+
+        for (_, path) in self.resource.stream_path.iter() {
+            hashes.insert(path.path());
+        }
+
+        let labels = Hash40::label_map();
+        let labels = labels.lock().unwrap();
+
+        hashes
+            .into_iter()
+            .filter(|hash| hash.0 != 0 && labels.label_of(*hash).is_none())
+            .collect()
+    }
+
+    /// Iterates over the hash of every file path in the archive, in table order, along with its
+    /// label in the global [`Hash40::label_map`]
+    ///
+    /// The label map is locked once and every label is resolved before this returns. The lock
+    /// is not held while iterating, since formatting a [`Hash40`] locks the label map as well.
+    pub fn file_paths_labeled(&self) -> impl Iterator<Item = (Hash40, Option<String>)> {
+        let labels = Hash40::label_map();
+        let labels = labels.lock().unwrap();
+
+        self.resource
+            .file_path
+            .iter()
+            .map(|(_, path)| (path.path(), labels.label_of(path.path())))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Resolves the label of `path` and splits it into its components, returning [`None`] if `path`
+    /// has no label in the global [`Hash40::label_map`]
+    ///
+    /// Empty components, such as the one after a trailing slash, are skipped. The path does not have to
+    /// be in the archive.
+    pub fn path_components(&self, path: impl IntoHash) -> Option<Vec<String>> {
+        let label = Hash40::label_map()
+            .lock()
+            .unwrap()
+            .label_of(path.into_hash())?;
+
+        Some(
+            label
+                .split('/')
+                .filter(|component| !component.is_empty())
+                .map(String::from)
+                .collect(),
+        )
+    }
+
+    /// The hash of the directory that contains the file or package at `path`, as it is stored in the archive
+    ///
+    /// This is the parent of the [`FilePath`] at `path`, or of the [`FilePackage`] if there is no file there.
+    /// Returns [`None`] if there is neither.
+    pub fn directory_of(&self, path: impl IntoHash) -> Option<Hash40> {
+        let path = path.into_hash();
+
+        self.lookup_file_path(path)
+            .map(|file_path| file_path.parent())
+            .or_else(|| {
+                self.lookup_file_package(path)
+                    .map(|package| package.parent())
+            })
+    }
+
+    /// Checks if the archive contains a file at `path`
+    pub fn contains_file(&self, path: impl IntoHash) -> bool {
+        self.lookup_file_path(path).is_some()
+    }
+
+    /// Checks if the archive contains a package at `path`
+    pub fn contains_package(&self, path: impl IntoHash) -> bool {
+        self.lookup_file_package(path).is_some()
+    }
+
+    /// Checks if the archive contains a stream file at `path`, which includes the `stream:` prefix
+    pub fn contains_stream(&self, path: impl IntoHash) -> bool {
+        self.lookup_stream_path(path).is_some()
+    }
+
+    /// Looks up a file path after normalizing it with [`normalize_and_hash`](crate::normalize_and_hash),
+    /// so that mixed case, backslashes and a leading slash don't cause the lookup to fail
+    pub fn lookup_file_path_normalized(&self, path: &str) -> Option<TableRef<'_, FilePath>> {
+        self.lookup_file_path(crate::normalize_and_hash(path))
+    }
+
+    /// Looks up a stream path after normalizing it with [`normalize_stream_and_hash`](crate::normalize_stream_and_hash),
+    /// so that the `stream:/` prefix can be left out
+    pub fn lookup_stream_path_normalized(&self, path: &str) -> Option<TableRef<'_, StreamPath>> {
+        self.lookup_stream_path(crate::normalize_stream_and_hash(path))
+    }
+
+    /// Gets the lookup that maps the hash of a file's path to its [`FilePath`]
+    pub fn file_path_lookup(&self) -> &BucketLookup {
+        &self.resource.file_path_lookup
+    }
+
+    /// Gets the lookup that maps the hash of a package's path to its [`FilePackage`]
+    pub fn file_package_lookup(&self) -> &IndexLookup {
+        &self.resource.file_package_lookup
+    }
+
+    /// Gets the lookup that maps the hash of a package's path to its [`FilePackage`], for
+    /// inserting or removing packages
+    pub fn file_package_lookup_mut(&mut self) -> &mut IndexLookup {
+        &mut self.resource.file_package_lookup
+    }
+
+    /// Gets the lookup that maps the hash of a stream file's path to its [`StreamPath`]
+    pub fn stream_path_lookup(&self) -> &IndexLookup {
+        &self.resource.stream_path_lookup
+    }
+
+    /// Gets the lookup that maps the hash of a stream file's path to its [`StreamPath`], for
+    /// inserting or removing stream files
+    pub fn stream_path_lookup_mut(&mut self) -> &mut IndexLookup {
+        &mut self.resource.stream_path_lookup
+    }
+
+    /// Rebuilds the file path lookup from the [`FilePath`] table, keeping the same number of buckets
+    ///
+    /// This is faster than inserting many new paths one at a time, see [`BucketLookup::rebuild`]
+    pub fn rebuild_file_path_lookup(&mut self) {
+        let lookup = BucketLookup::rebuild(
+            self.resource
+                .file_path
+                .iter()
+                .map(|(index, path)| (path.path(), index)),
+            self.resource.file_path_lookup.bucket_count(),
+        );
+
+        self.resource.file_path_lookup = lookup;
+    }
+
+    /// Renames a file path, re-keying the file path lookup so that the path can be found by its new hash
+    ///
+    /// Only the full path hash is changed, the parent, file name and extension hashes of the [`FilePath`]
+    /// are left untouched.
+    ///
+    /// Returns `false` if `old` is not present in the archive, or if `new` is already used by another path
+    pub fn rename_path(&mut self, old: impl IntoHash, new: impl IntoHash) -> bool {
+        let old = old.into_hash();
+        let new = new.into_hash();
+
+        let lookup = &mut self.resource.file_path_lookup;
+        if old == new {
+            return lookup.contains_key(old);
+        }
+
+        if lookup.contains_key(new) {
+            return false;
+        }
+
+        let Some(index) = lookup.remove(old) else {
+            return false;
+        };
+
+        lookup.insert(new, index);
+
+        self.resource
+            .file_path
+            .get_mut(index)
+            .expect("file path lookup should point to a valid file path")
+            .set_path(new);
+
+        true
+    }
+
+    /// The header of the resource tables, as it was read from the archive
+    ///
+    /// The counts do not include any rows that have been added since the archive was read.
+    pub fn header(&self) -> &ResourceTableHeader {
+        &self.resource.header
+    }
+
+    /// The index of the first [`FileGroup`] of shared data
+    ///
+    /// This is the [`file_data_group_count`](ResourceTableHeader::file_data_group_count) from the header:
+    /// the groups before it belong to packages, and the
+    /// [`file_info_group_count`](ResourceTableHeader::file_info_group_count) groups starting at it contain
+    /// shared [`FileInfo`]. Indices that can refer to either a package or a group, such as the owner of a
+    /// [`FileEntity`] or the redirection of a data group, refer to a group if they are at or past this boundary.
+    ///
+    /// Like [`Self::header`], this does not account for rows that have been added since the archive was read.
+    pub fn shared_group_start(&self) -> u32 {
+        self.resource.header.file_data_group_count()
+    }
+
+    /// Decodes the locale to region mapping in the header of the resource tables
+    ///
+    /// See [`LocaleRegionEntry`] for how each row is interpreted
+    pub fn locale_region_map(&self) -> [LocaleRegionEntry; Locale::COUNT] {
+        self.resource.header.locale_region_map()
+    }
+
+    /// Counts the file paths, the unique files they point to and the shared file infos
+    pub fn file_stats(&self) -> FileStats {
+        let entities = self
+            .resource
+            .file_path
+            .iter()
+            .map(|(_, path)| path.file_entity_index())
+            .collect::<BTreeSet<_>>();
+
+        FileStats {
+            file_paths: self.resource.file_path.len(),
+            unique_entities: entities.len(),
+            shared_infos: self
+                .resource
+                .file_info
+                .iter()
+                .filter(|(_, info)| info.is_shared())
+                .count(),
+        }
+    }
+
+    /// Collects the [`FileData`] that each file entity's data is read from, so that data shared
+    /// between file paths is only counted once
+    fn entity_file_data(&self) -> impl Iterator<Item = &FileData> {
+        let indices = self
+            .resource
+            .file_entity
+            .iter()
+            .filter_map(|(_, entity)| self.resource.file_info.get(entity.info_index()))
+            .filter_map(|info| self.resource.file_desc.get(info.descriptor_range().start))
+            .map(|desc| desc.file_data_index())
+            .collect::<BTreeSet<_>>();
+
+        indices
+            .into_iter()
+            .filter_map(|index| self.resource.file_data.get(index))
+    }
+
+    /// Sums the decompressed size of every physically unique file in the archive
+    ///
+    /// Only the first descriptor of regional and localized files is counted, matching [`Self::extract_all`]
+    pub fn total_decompressed_size(&self) -> u64 {
+        self.entity_file_data()
+            .map(|data| data.decompressed_size() as u64)
+            .sum()
+    }
+
+    /// Sums the on-disk size of every physically unique file in the archive
+    ///
+    /// See [`Self::total_decompressed_size`]
+    pub fn total_compressed_size(&self) -> u64 {
+        self.entity_file_data()
+            .map(|data| data.compressed_size() as u64)
+            .sum()
+    }
+
+    /// Finds the `n` files whose data compresses the worst, sorted from the highest
+    /// [`FileData::compression_ratio`] to the lowest
+    ///
+    /// Like [`Self::read_file`], regional and localized files use their first descriptor.
+    pub fn worst_compressing_files(&self, n: usize) -> Vec<(Hash40, f32)> {
+        let mut files = self
+            .resource
+            .file_path
+            .iter()
+            .filter_map(|(_, path)| {
+                let (_, data) = self.resolve_file_data(path.path()).ok()?;
+                let data = self.resource.file_data.get(data)?;
+                Some((path.path(), data.compression_ratio()))
+            })
+            .collect::<Vec<_>>();
+
+        files.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        files.truncate(n);
+        files
+    }
+
+    /// Finds the `n` largest files by [`FileData::decompressed_size`], sorted from the largest to the smallest
+    ///
+    /// Paths that share a [`FileEntity`] share their data, so only the first path of each entity is listed.
+    /// Like [`Self::read_file`], regional and localized files use their first descriptor.
+    pub fn largest_files(&self, n: usize) -> Vec<(Hash40, u32)> {
+        let mut entities = BTreeSet::new();
+        let mut files = self
+            .resource
+            .file_path
+            .iter()
+            .filter(|(_, path)| entities.insert(path.file_entity_index()))
+            .filter_map(|(index, path)| {
+                let (_, data) = self.resolve_file_path_data(index).ok()?;
+                let data = self.resource.file_data.get(data)?;
+                Some((path.path(), data.decompressed_size()))
+            })
+            .collect::<Vec<_>>();
+
+        let by_size = |(_, a): &(Hash40, u32), (_, b): &(Hash40, u32)| b.cmp(a);
+        if n < files.len() {
+            files.select_nth_unstable_by(n, by_size);
+            files.truncate(n);
+        }
+
+        files.sort_by(by_size);
+        files
+    }
+
+    /// Resolves the distinct files that are loaded along with the package at `package`, in the order
+    /// that they are reached
+    ///
+    /// The package is walked the way that the resource loaders walk it:
+    /// - a package that is a sym link loads the package that it links to instead of its own infos
+    /// - a package with a file info group as its sub package loads every info in that group
+    /// - child packages are walked as well, each package is only walked once
+    /// - infos whose first descriptor is [`LoadMethod::PackageSkip`] are skipped, since they are loaded from
+    ///   the group that they are shared through
+    /// - infos whose first descriptor is [`LoadMethod::Unowned`] resolve to the info of the entity that owns their data
+    ///
+    /// Every file is returned once, even if it is reached through multiple packages. Returns an empty list
+    /// if there is no package at `package`.
+    pub fn resolve_load_set(&self, package: impl IntoHash) -> Vec<TableRef<'_, FileInfo>> {
+        let Some(root) = self.lookup_file_package(package) else {
+            return Vec::new();
+        };
+
+        let mut packages = HashSet::from([root.index()]);
+        let mut queue = VecDeque::from([root.index()]);
+        let mut seen = HashSet::new();
+        let mut infos = Vec::new();
+        let mut push = |info: u32| {
+            if seen.insert(info) {
+                infos.push(info);
+            }
+        };
+
+        while let Some(index) = queue.pop_front() {
+            let package = self
+                .get_file_package(index)
+                .expect("queued package should exist");
+
+            let mut next = package
+                .child_package_range()
+                .filter_map(|child| self.resource.file_package_child.get(child))
+                .map(|child| child.inner().data())
+                .collect::<Vec<_>>();
+
+            match package.sub_package() {
+                Some(SubPackageRef::SymLink(target)) => next.push(target.index()),
+                Some(SubPackageRef::FileGroup(group)) => {
+                    group.file_info_range().for_each(&mut push);
+                }
+                None => {}
+            }
+
+            if package.get_sym_link().is_none() {
+                package
+                    .info_range()
+                    .filter_map(|info| self.resolve_package_info(info))
+                    .for_each(&mut push);
+            }
+
+            for next in next {
+                if self.get_file_package(next).is_some() && packages.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        infos
+            .into_iter()
+            .filter_map(|info| self.get_file_info(info))
+            .collect()
+    }
+
+    /// Resolves the info that is loaded for the info at `index` when it is loaded as part of a package,
+    /// following the load method of its first descriptor. See [`Self::resolve_load_set`]
+    fn resolve_package_info(&self, index: u32) -> Option<u32> {
+        let info = self.resource.file_info.get(index)?;
+        let desc = self.resource.file_desc.get(info.descriptor_range().start)?;
+
+        match desc.load_method() {
+            LoadMethod::PackageSkip(_) => None,
+            LoadMethod::Unowned(entity) => self
+                .resource
+                .file_entity
+                .get(entity)
+                .map(|entity| entity.info_index()),
+            _ => Some(index),
+        }
+    }
+
+    /// Finds the package that owns the [`FileEntity`] at `entity`
+    ///
+    /// Entities that are owned by a package return that package. Entities that are owned by a group
+    /// return the package that the group redirects to, if any. Groups of shared file infos redirect to
+    /// themselves and some data groups do not redirect at all, so their entities return [`None`], as do
+    /// entities that do not exist.
+    pub fn owning_package(&self, entity: Index<FileEntity>) -> Option<TableRef<'_, FilePackage>> {
+        let entity = self.resource.file_entity.get(entity.get())?;
+        let package_len = self.resource.package_len();
+        let owner = entity.package_or_group_index();
+
+        if !entity.is_owned_by_group(package_len) {
+            return self.get_file_package(owner);
+        }
+
+        let redirection = self.resource.file_group.get(owner)?.redirection_index();
+        if redirection == INVALID_INDEX || redirection >= package_len {
+            return None;
+        }
+
+        self.get_file_package(redirection)
+    }
+
+    /// Finds every package that loads the file at `path`, in table order
+    ///
+    /// A package loads a file if one of the file's infos is in the package's info range, or if one of
+    /// the package's data groups contains data from one of the file's descriptors. This scans every
+    /// info and package, so callers that query many files should cache the results.
+    pub fn packages_containing(&self, path: impl IntoHash) -> Vec<TableRef<'_, FilePackage>> {
+        let Some(file_path) = self.lookup_file_path(path) else {
+            return Vec::new();
+        };
+
+        let infos = self
+            .resource
+            .file_info
+            .iter()
+            .filter(|(_, info)| info.path_index() == file_path.index())
+            .map(|(index, _)| index)
+            .collect::<BTreeSet<_>>();
+
+        let data = infos
+            .iter()
+            .filter_map(|info| self.resource.file_info.get(*info))
+            .flat_map(|info| info.descriptor_range())
+            .filter_map(|desc| self.resource.file_desc.get(desc))
+            .map(|desc| desc.file_data_index())
+            .collect::<BTreeSet<_>>();
+
+        self.resource
+            .file_package
+            .iter()
+            .filter(|(_, package)| {
+                package.info_range().any(|info| infos.contains(&info))
+                    || package
+                        .data_group_range()
+                        .filter_map(|group| self.resource.file_group.get(group))
+                        .flat_map(|group| group.child_range())
+                        .any(|child| data.contains(&child))
+            })
+            .filter_map(|(index, _)| self.get_file_package(index))
+            .collect()
+    }
+
+    /// Finds the file entities, infos, descriptors and data that can not be reached from any file
+    /// path, package or group
+    ///
+    /// See [`ResourceTables::compact`] for how rows are reached
+    pub fn orphans(&self) -> Orphans {
+        let reachable = self.resource.reachable();
+
+        Orphans {
+            file_entity: Reachable::unmarked(&reachable.file_entity),
+            file_info: Reachable::unmarked(&reachable.file_info),
+            file_desc: Reachable::unmarked(&reachable.file_desc),
+            file_data: Reachable::unmarked(&reachable.file_data),
+        }
+    }
+
+    /// Iterates over every file path whose extension hash matches `ext`, in table order
+    ///
+    /// The extension is hashed without the leading `.`, e.g. `"nutexb"`. Files that were added with
+    /// [`Self::push_file`] do not have an extension, and are never returned.
+    pub fn files_with_extension(
+        &self,
+        ext: impl IntoHash,
+    ) -> impl Iterator<Item = TableRef<'_, FilePath>> {
+        let ext = ext.into_hash();
+        self.resource
+            .file_path
+            .iter()
+            .filter(move |(_, path)| path.extension() == ext)
+            .filter_map(|(index, _)| self.get_file_path(index))
+    }
+
+    /// Iterates over every stream folder in the archive, in table order
+    pub fn stream_folders(&self) -> impl Iterator<Item = TableRef<'_, StreamFolder>> {
+        (0..self.num_stream_folder() as u32).filter_map(|index| self.get_stream_folder(index))
+    }
+
+    /// Iterates over every file group in the archive, in table order, along with which
+    /// section of the group table it is in
+    ///
+    /// See [`GroupRegion`] for how the sections are determined
+    pub fn file_groups(&self) -> impl Iterator<Item = (TableRef<'_, FileGroup>, GroupRegion)> {
+        (0..self.num_file_group() as u32).filter_map(move |index| {
+            let group = self.get_file_group(index)?;
+            Some((group, self.resource.group_region(index)))
+        })
+    }
+
+    /// Appends a new file to the archive, returning the index of the new [`FilePath`]
+    ///
+    /// See [`ResourceTables::push_file`]
+    pub fn push_file(&mut self, path: impl IntoHash, data: FileDataSpec) -> Index<FilePath> {
+        self.resource.push_file(path.into_hash(), data)
+    }
+
+    /// Appends a new file to the archive, unless there is already a file at `path`
+    ///
+    /// See [`ResourceTables::try_push_file`]
+    pub fn try_push_file(
+        &mut self,
+        path: impl IntoHash,
+        data: FileDataSpec,
+    ) -> Result<Index<FilePath>, DuplicatePath> {
+        self.resource.try_push_file(path.into_hash(), data)
+    }
+
+    /// Groups together the [`FileData`] rows that describe the same bytes on disk
+    ///
+    /// Two rows describe the same bytes if they are used by descriptors in the same [`FileGroup`] and have
+    /// the same offset, compressed size and decompressed size. Only groups of two or more rows are returned,
+    /// each sorted by index, and the groups themselves are sorted by their location in the archive.
+    pub fn duplicate_data_extents(&self) -> Vec<Vec<u32>> {
+        let mut extents: BTreeMap<_, BTreeSet<u32>> = BTreeMap::new();
+
+        for (_, desc) in self.resource.file_desc.iter() {
+            let Some(data) = self.resource.file_data.get(desc.file_data_index()) else {
+                continue;
+            };
+
+            let key = (
+                desc.group_index(),
+                data.in_group_offset(),
+                data.compressed_size(),
+                data.decompressed_size(),
+            );
+
+            extents
+                .entry(key)
+                .or_default()
+                .insert(desc.file_data_index());
+        }
+
+        extents
+            .into_values()
+            .filter(|indices| indices.len() > 1)
+            .map(|indices| indices.into_iter().collect())
+            .collect()
+    }
+
+    decl_remove! {
+        file_path => FilePath,
+        file_entity => FileEntity,
+        file_info => FileInfo,
+        file_desc => FileDesc,
+        file_data => FileData,
+        file_package => FilePackage,
+        file_package_child => FilePackageChild,
+        file_group => FileGroup,
+        stream_folder => StreamFolder,
+        stream_path => StreamPath,
+        stream_desc => StreamDesc,
+        stream_data => StreamData
+    }
+
+    /// Follows a path through its entity, info and first descriptor, returning the indices of the
+    /// [`FileGroup`] and [`FileData`] that the descriptor points to
+    pub(crate) fn resolve_file_data(&self, path: Hash40) -> Result<(u32, u32), ArchiveError> {
+        let file_path = self
+            .lookup_file_path(path)
+            .ok_or(ArchiveError::MissingPath(path))?;
+
+        self.resolve_file_path_data(file_path.index())
+    }
+
+    /// Resolves the data of a file like [`Self::resolve_file_data`], starting from the index of its
+    /// [`FilePath`] instead of looking up its hash
+    fn resolve_file_path_data(&self, file_path_index: u32) -> Result<(u32, u32), ArchiveError> {
+        let file_path =
+            self.get_file_path(file_path_index)
+                .ok_or(ArchiveError::IndexOutOfRange {
+                    table: "file_path",
+                    index: file_path_index,
+                })?;
+
+        let entity_index = file_path.file_entity_index();
+        let entity = self
+            .get_file_entity(entity_index)
+            .ok_or(ArchiveError::IndexOutOfRange {
+                table: "file_entity",
+                index: entity_index,
+            })?;
+
+        let info =
+            self.get_file_info(entity.info_index())
+                .ok_or(ArchiveError::IndexOutOfRange {
+                    table: "file_info",
+                    index: entity.info_index(),
+                })?;
+
+        let desc_index = info.descriptor_range().start;
+        let desc = self
+            .get_file_desc(desc_index)
+            .ok_or(ArchiveError::IndexOutOfRange {
+                table: "file_desc",
+                index: desc_index,
+            })?;
+
+        Ok((desc.group_index(), desc.file_data_index()))
+    }
+
+    /// Reads the data of a file out of the archive, decompressing it if necessary
+    ///
+    /// `reader` must be reading from the same archive that this was read from. For regional and localized
+    /// files, the data of the first descriptor is read.
+    ///
+    /// Returns [`ArchiveError::UnsupportedCompression`] if the file's data is compressed with something
+    /// other than ZSTD
+    pub fn read_file<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        path: impl IntoHash,
+    ) -> Result<Vec<u8>, ArchiveError> {
+        self.read_file_with_decompressor(reader, path, &ZstdDecompressor)
+    }
+
+    /// Reads the data of a file out of the archive like [`Self::read_file`], decompressing it with
+    /// `decompressor` instead of the default [`ZstdDecompressor`]
+    pub fn read_file_with_decompressor<R: Read + Seek, D: Decompressor + ?Sized>(
+        &self,
+        reader: &mut R,
+        path: impl IntoHash,
+        decompressor: &D,
+    ) -> Result<Vec<u8>, ArchiveError> {
+        let (group_index, data_index) = self.resolve_file_data(path.into_hash())?;
+        self.read_data(reader, group_index, data_index, decompressor)
+    }
+
+    /// Reads the data of a file out of the archive like [`Self::read_file`], starting from the index of
+    /// its [`FilePath`]
+    ///
+    /// This skips looking up the hash of the path, which is useful when the index is already known from
+    /// iterating the table. Returns [`ArchiveError::IndexOutOfRange`] if there is no path at the index.
+    pub fn read_file_by_index<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        file_path_index: u32,
+    ) -> Result<Vec<u8>, ArchiveError> {
+        let (group_index, data_index) = self.resolve_file_path_data(file_path_index)?;
+        self.read_data(reader, group_index, data_index, &ZstdDecompressor)
+    }
+
+    /// Reads and decompresses the [`FileData`] at `data`, stored in the [`FileGroup`] at `group`
+    fn read_data<R: Read + Seek, D: Decompressor + ?Sized>(
+        &self,
+        reader: &mut R,
+        group: u32,
+        data: u32,
+        decompressor: &D,
+    ) -> Result<Vec<u8>, ArchiveError> {
+        let location = self.checked_data_location(group, data)?;
+
+        if location.compression == Compression::ProprietaryUnknown {
+            return Err(ArchiveError::UnsupportedCompression(data));
+        }
+
+        reader.seek(SeekFrom::Start(location.absolute_offset))?;
+
+        let bytes = match location.compression {
+            Compression::None => reader.read_count_vec(location.compressed_size as usize)?,
+            _ => reader.read_decompressed_data_vec(
+                decompressor,
+                location.compressed_size as u64,
+                location.decompressed_size as usize,
+            )?,
+        };
+
+        Ok(bytes)
+    }
+
+    /// Reads the data of a file out of the archive like [`Self::read_file`], but decompresses it
+    /// directly into `out` instead of collecting it into a buffer
+    ///
+    /// Returns the number of decompressed bytes that were written. If an error occurs, some of
+    /// the data may have already been written to `out`.
+    pub fn read_file_to<R: Read + Seek, W: Write>(
+        &self,
+        reader: &mut R,
+        path: impl IntoHash,
+        out: &mut W,
+    ) -> Result<u64, ArchiveError> {
+        let (group_index, data_index) = self.resolve_file_data(path.into_hash())?;
+
+        let location = self.checked_data_location(group_index, data_index)?;
+
+        if location.compression == Compression::ProprietaryUnknown {
+            return Err(ArchiveError::UnsupportedCompression(data_index));
+        }
+
+        reader.seek(SeekFrom::Start(location.absolute_offset))?;
+
+        let written = match location.compression {
+            Compression::None => {
+                let size = location.compressed_size as u64;
+                let written = std::io::copy(&mut reader.take(size), out)?;
+                if written != size {
+                    return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                }
+
+                written
+            }
+            _ => reader.copy_zstd_data(
+                location.compressed_size as u64,
+                location.decompressed_size as usize,
+                out,
+            )?,
+        };
+
+        Ok(written)
+    }
+
+    /// Reads the data of a file out of the archive like [`Self::read_file`], but decompresses it into
+    /// `scratch` so that the buffer can be reused between files
+    ///
+    /// `scratch` is cleared before reading and only grows when a file is larger than its capacity,
+    /// so reading many files with the same buffer does not allocate for every file. The returned
+    /// slice is the contents of `scratch`.
+    pub fn read_file_into<'a, R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        path: impl IntoHash,
+        scratch: &'a mut Vec<u8>,
+    ) -> Result<&'a [u8], ArchiveError> {
+        scratch.clear();
+        self.read_file_to(reader, path, scratch)?;
+        Ok(scratch.as_slice())
+    }
+
+    /// Resolves where the bytes of a [`FileData`] are stored in the archive
+    ///
+    /// The [`FileGroup`] that contains the data is found through the first [`FileDesc`] that points to it.
+    /// Returns [`None`] if the index is out of range or no descriptor points to the data.
+    pub fn file_data_location(&self, file_data_index: Index<FileData>) -> Option<DataLocation> {
+        let location = FileLocation::resolve(self, file_data_index)?;
+        self.describe_location(location, file_data_index.get())
+    }
+
+    /// Writes a CSV row for every file path describing where its data is stored in the archive
+    ///
+    /// The columns are `path_hash,resolved_path,absolute_offset,compressed_size,decompressed_size,compression`.
+    /// `resolved_path` is taken from the global label map and is empty if the hash has no label. Like
+    /// [`Self::read_file`], regional and localized files use their first descriptor. Rows are written in
+    /// table order as they are produced, so this never holds more than one row in memory.
+    pub fn dump_offset_map<W: Write>(&self, mut out: W) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "path_hash,resolved_path,absolute_offset,compressed_size,decompressed_size,compression"
+        )?;
+
+        let labels = Hash40::label_map();
+        for (_, path) in self.resource.file_path.iter() {
+            let hash = path.path();
+            let Some(location) = self
+                .resolve_file_data(hash)
+                .ok()
+                .and_then(|(group, data)| self.data_location(group, data))
+            else {
+                continue;
+            };
+
+            let label = labels.lock().unwrap().label_of(hash).unwrap_or_default();
+            let label = if label.contains([',', '"']) {
+                format!("\"{}\"", label.replace('"', "\"\""))
+            } else {
+                label
+            };
+
+            let compression = match location.compression {
+                Compression::None => "none",
+                Compression::Zstd => "zstd",
+                Compression::ProprietaryUnknown => "unknown",
+            };
+
+            writeln!(
+                out,
+                "{:#012x},{label},{:#x},{:#x},{:#x},{compression}",
+                hash.0,
+                location.absolute_offset,
+                location.compressed_size,
+                location.decompressed_size,
+            )?;
+        }
+
+        out.flush()
+    }
+
+    /// Gets the offset from the start of the archive and the size of a stream file's data, in bytes
+    ///
+    /// Stream data is always stored uncompressed, so these can be used to read the file directly.
+    pub fn stream_data_location(&self, stream_data_index: Index<StreamData>) -> Option<(u64, u64)> {
+        let data = self.resource.stream_data.get(stream_data_index.get())?;
+        Some((data.offset(), data.size()))
+    }
+
+    /// Finds the [`FileGroup`] that contains each row of [`FileData`], indexed by the row
+    ///
+    /// See [`ResourceTables::file_data_groups`]
+    pub(crate) fn file_data_groups(&self) -> Vec<u32> {
+        self.resource.file_data_groups()
+    }
+
+    /// Lists the indices of every [`FileData`] sorted by where its bytes are stored in the archive
+    ///
+    /// Rows that are stored at the same offset are sorted by index, and rows that no [`FileDesc`] points
+    /// to are left out since they have no location. Comparing the end of each row with the start of the
+    /// next is enough to find the padding and unused regions of the file data section.
+    pub fn file_data_by_offset(&self) -> Vec<Index<FileData>> {
+        let mut rows = self
+            .file_data_groups()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(data, group)| {
+                let location = self.data_location(group, data as u32)?;
+                Some((location.absolute_offset, data as u32))
+            })
+            .collect::<Vec<_>>();
+
+        rows.sort_unstable();
+        rows.into_iter().map(|(_, data)| Index::new(data)).collect()
+    }
+
+    /// Finds the file whose data contains `absolute_offset`, the inverse of [`Self::file_data_location`]
+    ///
+    /// The path of the first [`FileInfo`] that uses the data is returned, see [`Self::file_data_at_offset`]
+    /// and [`Self::file_data_owner`].
+    pub fn file_at_offset(&self, absolute_offset: u64) -> Option<TableRef<'_, FilePath>> {
+        let (data, _) = self.file_data_at_offset(absolute_offset)?;
+        self.file_data_owner(data)
+    }
+
+    /// Finds the [`FileData`] whose bytes contain `absolute_offset`, along with where it is stored
+    ///
+    /// The offset is counted from the start of the archive, and the data covers its compressed size
+    /// starting at its location. Returns [`None`] if the offset is not inside of any file's data, such
+    /// as padding between files.
+    ///
+    /// This sorts every [`FileData`] by offset on each call, like [`Self::file_data_by_offset`].
+    pub fn file_data_at_offset(
+        &self,
+        absolute_offset: u64,
+    ) -> Option<(Index<FileData>, DataLocation)> {
+        let mut rows = self
+            .file_data_groups()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(data, group)| {
+                let location = self.data_location(group, data as u32)?;
+                Some((
+                    location.absolute_offset,
+                    location.compressed_size,
+                    data as u32,
+                    location,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        rows.sort_unstable_by_key(|&(start, size, data, _)| (start, size, data));
+
+        let end = rows.partition_point(|(start, ..)| *start <= absolute_offset);
+        let (_, _, data, location) = rows[..end]
+            .iter()
+            .rev()
+            .take_while(|(start, ..)| *start == rows[end - 1].0)
+            .find(|(start, size, ..)| absolute_offset < start + *size as u64)?;
+
+        Some((Index::new(*data), *location))
+    }
+
+    /// Finds the path of the first [`FileInfo`] with a descriptor that uses the [`FileData`] at `index`
+    ///
+    /// Data can be shared between paths, so this is not necessarily the path that owns the data. This scans
+    /// every info.
+    pub fn file_data_owner(&self, index: Index<FileData>) -> Option<TableRef<'_, FilePath>> {
+        let resource = &self.resource;
+        let index = index.get();
+        let (_, info) = resource.file_info.iter().find(|(_, info)| {
+            info.descriptor_range().any(|desc| {
+                resource
+                    .file_desc
+                    .get(desc)
+                    .is_some_and(|desc| desc.file_data_index() == index)
+            })
+        })?;
+
+        self.get_file_path(info.path_index())
+    }
+
+    /// Lists the indices of every [`StreamData`] sorted by where its bytes are stored in the archive
+    ///
+    /// Rows that are stored at the same offset are sorted by index
+    pub fn stream_data_by_offset(&self) -> Vec<Index<StreamData>> {
+        let mut rows = self
+            .resource
+            .stream_data
+            .iter()
+            .map(|(index, data)| (data.offset(), index))
+            .collect::<Vec<_>>();
+
+        rows.sort_unstable();
+        rows.into_iter()
+            .map(|(_, index)| Index::new(index))
+            .collect()
+    }
+
+    /// Resolves where the bytes of a [`FileData`] are stored, when the [`FileGroup`] that contains it
+    /// is already known
+    pub(crate) fn data_location(&self, group: u32, data: u32) -> Option<DataLocation> {
+        let location = FileLocation::in_group(self, group, data)?;
+        self.describe_location(location, data)
+    }
+
+    /// Combines a resolved [`FileLocation`] with the sizes and compression of the data it locates
+    fn describe_location(&self, location: FileLocation, data: u32) -> Option<DataLocation> {
+        let data = self.get_file_data(data)?;
+
+        Some(DataLocation {
+            absolute_offset: location.absolute_offset,
+            compressed_size: data.compressed_size(),
+            decompressed_size: data.decompressed_size(),
+            compression: data.compression(),
+            region: location.region,
+        })
+    }
+
+    /// Locates the data like [`Self::data_location`], returning [`ArchiveError::IndexOutOfRange`] for the
+    /// row that does not exist and [`ArchiveError::DataOffsetOverflow`] if its offset can't be computed
+    fn checked_data_location(&self, group: u32, data: u32) -> Result<DataLocation, ArchiveError> {
+        if self.get_file_group(group).is_none() {
+            return Err(ArchiveError::IndexOutOfRange {
+                table: "file_group",
+                index: group,
+            });
+        }
+
+        if self.get_file_data(data).is_none() {
+            return Err(ArchiveError::IndexOutOfRange {
+                table: "file_data",
+                index: data,
+            });
+        }
+
+        self.data_location(group, data)
+            .ok_or(ArchiveError::DataOffsetOverflow { group, data })
+    }
+
+    /// Determines which region of the archive an absolute offset is in
+    ///
+    /// Both regions are addressed relative to the start of the file data section, the shared region
+    /// is just the part of that section that starts at the shared file data offset.
+    pub(crate) fn region_of(&self, absolute_offset: u64) -> DataRegion {
+        if absolute_offset >= self.metadata.shared_file_data_offset {
+            DataRegion::Shared
+        } else {
+            DataRegion::Main
+        }
+    }
+
     pub fn quick_serialize(&self) -> Vec<u8> {
         self.resource.quick_serialize()
     }
 
+    /// Computes the size of every table and lookup in the archive, in bytes
+    ///
+    /// See [`ResourceTables::table_sizes`]
+    pub fn table_sizes(&self) -> TableSizes {
+        self.resource.table_sizes()
+    }
+
+    /// Checks that the resource tables survive being serialized with [`Self::quick_serialize`]
+    /// and read back, reporting the first table and index that differs
+    ///
+    /// See [`ResourceTables::roundtrip_check`]
+    pub fn roundtrip_check(&self) -> Result<(), RoundtripMismatch> {
+        self.resource.roundtrip_check()
+    }
+
+    /// Removes the version history from the archive, returning the number of bytes saved in the
+    /// resource tables
+    ///
+    /// See [`ResourceTables::strip_versions`]
+    pub fn strip_versions(&mut self) -> usize {
+        self.resource.strip_versions()
+    }
+
+    /// Removes the file entities, infos, descriptors and data that can not be reached, returning
+    /// how many rows were removed from each table
+    ///
+    /// See [`ResourceTables::compact`]
+    pub fn compact(&mut self) -> CompactionReport {
+        self.resource.compact()
+    }
+
+    /// Hash of the decompressed resource tables as they were read from the archive
+    ///
+    /// This is intended to be used as a cache key. The hash is computed once when the tables
+    /// are read, so rows that have been added or modified since then are not taken into account.
+    #[cfg(feature = "xxhash")]
+    pub fn resource_table_hash(&self) -> u64 {
+        self.resource.raw_hash
+    }
+
+    /// The decompressed resource tables that were read from the archive
+    ///
+    /// The fixed rows of each table are views into these bytes, so edits to them are reflected
+    /// here, while rows that have been added since reading are not. This is the same blob that
+    /// [`Self::serialize_tables`] returns alongside the reserialized tables
+    pub fn raw_resource_bytes(&self) -> &[u8] {
+        &self.resource.raw_data
+    }
+
+    /// Computes the size of the tables that [`Self::serialize_tables`] would produce, without serializing them
+    ///
+    /// See [`ResourceTables::serialized_size`]
+    pub fn serialized_tables_size(&self) -> usize {
+        self.resource.serialized_size(self)
+    }
+
     pub fn serialize_tables(&self) -> Result<(&[u8], Box<[u8]>), std::io::Error> {
         self.resource
             .into_bytes(self)
-            .map(|bytes| (self.resource.raw_data.as_ref(), bytes))
+            .map(|bytes| (self.raw_resource_bytes(), bytes))
     }
 
-    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, std::io::Error> {
-        // SAFETY: Confirms that the metadata is proper by checking the magic after reading it
-        let metadata = unsafe {
-            let metadata = reader.read_binary::<ArchiveMetadata>()?;
-            if metadata.magic != ArchiveMetadata::MAGIC {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!(
-                        "Expected magic {:#x} found {:#x}",
-                        ArchiveMetadata::MAGIC,
-                        metadata.magic
-                    ),
-                ));
-            }
+    /// Serializes the tables like [`Self::serialize_tables`], but spreads the file path lookup across
+    /// `bucket_count` buckets
+    ///
+    /// See [`ResourceTables::into_bytes_with_bucket_count`]
+    pub fn serialize_tables_with_bucket_count(
+        &self,
+        bucket_count: usize,
+    ) -> Result<(&[u8], Box<[u8]>), std::io::Error> {
+        self.resource
+            .into_bytes_with_bucket_count(self, bucket_count)
+            .map(|bytes| (self.raw_resource_bytes(), bytes))
+    }
 
-            metadata
-        };
+    /// Serializes the tables like [`Self::serialize_tables`], but keeps the version history
+    ///
+    /// See [`ResourceTables::into_bytes_preserving_versions`]
+    pub fn serialize_tables_preserving_versions(
+        &self,
+    ) -> Result<(&[u8], Box<[u8]>), std::io::Error> {
+        self.resource
+            .into_bytes_preserving_versions(self)
+            .map(|bytes| (self.raw_resource_bytes(), bytes))
+    }
 
-        reader.seek(SeekFrom::Start(metadata.resource_table_offset))?;
+    /// Reads an archive that has already been loaded into memory
+    ///
+    /// This never touches the filesystem, so it is usable in environments without one. Only the
+    /// metadata and the resource tables are read out of `bytes`.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, ArchiveError> {
+        Self::read(&mut std::io::Cursor::new(bytes))
+    }
+
+    /// Creates an archive from its metadata and an already decompressed resource section
+    ///
+    /// Unlike [`Self::read`], this does not check the magic, seek or decompress anything. The
+    /// resource section is parsed as-is.
+    pub fn from_parts(
+        metadata: ArchiveMetadata,
+        resource_bytes: Box<[u8]>,
+    ) -> Result<Self, ArchiveError> {
+        let resource = ResourceTables::from_bytes(resource_bytes)?;
 
-        let decompressed_section = reader.read_compressed_data()?;
+        Ok(Self { metadata, resource })
+    }
+
+    /// Reads an archive whose resource tables are split across a chain of compressed sections
+    ///
+    /// See [`Self::read_with_decompressor`], the only difference is that every compressed section in
+    /// the chain is decompressed and concatenated before the tables are parsed. The chain ends at the
+    /// user table, after a section that has an offset to the next section of 0, or when the next section
+    /// has a table size of 0.
+    pub fn read_chained<R: Read + Seek, D: Decompressor + ?Sized>(
+        reader: &mut R,
+        decompressor: &D,
+    ) -> Result<Self, ArchiveError> {
+        let (metadata, decompressed_section) =
+            ArchiveMetadata::read_with_chained_resource(reader, decompressor)?;
         let resource = ResourceTables::from_bytes(decompressed_section)?;
 
         Ok(Self { metadata, resource })
     }
+
+    /// Reads the metadata and resource tables of an archive
+    ///
+    /// Returns [`ArchiveError::BadMagic`] if `reader` is not reading an archive, and [`ArchiveError::Decompress`]
+    /// if the resource section is corrupt
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, ArchiveError> {
+        Self::read_with(reader, &ArchiveReadOptions::default())
+    }
+
+    /// Reads an archive, following `options`
+    ///
+    /// See [`ArchiveReadOptions`]
+    pub fn read_with<R: Read + Seek>(
+        reader: &mut R,
+        options: &ArchiveReadOptions,
+    ) -> Result<Self, ArchiveError> {
+        let (metadata, decompressed_section) =
+            ArchiveMetadata::read_with_resource(reader, options.decompressor.as_ref())?;
+        let resource = ResourceTables::from_bytes_with(decompressed_section, options)?;
+
+        Ok(Self { metadata, resource })
+    }
+
+    /// Reads an archive like [`Self::read`], decompressing the resource section with `decompressor`
+    /// instead of the default [`ZstdDecompressor`]
+    pub fn read_with_decompressor<R: Read + Seek, D: Decompressor + ?Sized>(
+        reader: &mut R,
+        decompressor: &D,
+    ) -> Result<Self, ArchiveError> {
+        let (metadata, decompressed_section) =
+            ArchiveMetadata::read_with_resource(reader, decompressor)?;
+        let resource = ResourceTables::from_bytes(decompressed_section)?;
+
+        Ok(Self { metadata, resource })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn built() -> (Archive, Cursor<Vec<u8>>) {
+        let bytes = ArchiveBuilder::new()
+            .add_file("fighter/mario/a.bin", b"hello".as_slice())
+            .build();
+        let mut reader = Cursor::new(bytes);
+        let archive = Archive::read(&mut reader).unwrap();
+        (archive, reader)
+    }
+
+    #[test]
+    fn read_file_reports_malformed_offsets() {
+        let (mut archive, mut reader) = built();
+        assert_eq!(
+            archive
+                .read_file(&mut reader, "fighter/mario/a.bin")
+                .unwrap(),
+            b"hello"
+        );
+
+        archive
+            .resource
+            .file_group
+            .get_mut(0)
+            .unwrap()
+            .set_location(u64::MAX, 0, 0);
+
+        assert!(matches!(
+            archive.read_file(&mut reader, "fighter/mario/a.bin"),
+            Err(ArchiveError::DataOffsetOverflow { group: 0, data: 0 })
+        ));
+    }
+
+    #[cfg(feature = "xxhash")]
+    #[test]
+    fn resource_table_hash_ignores_edits() {
+        let (mut archive, _) = built();
+        let hash = archive.resource_table_hash();
+        assert_eq!(
+            hash,
+            xxhash_rust::xxh3::xxh3_64(archive.raw_resource_bytes())
+        );
+
+        archive
+            .resource
+            .file_group
+            .get_mut(0)
+            .unwrap()
+            .set_location(0x1234, 0, 0);
+
+        assert_ne!(
+            hash,
+            xxhash_rust::xxh3::xxh3_64(archive.raw_resource_bytes())
+        );
+        assert_eq!(archive.resource_table_hash(), hash);
+        assert_eq!(archive.clone().resource_table_hash(), hash);
+    }
+
+    #[test]
+    fn read_file_reports_truncated_data() {
+        let (archive, reader) = built();
+        let mut bytes = reader.into_inner();
+        let offset = archive
+            .file_data_location(Index::new(0))
+            .unwrap()
+            .absolute_offset as usize;
+        bytes.truncate(offset + 2);
+
+        assert!(matches!(
+            archive.read_file(&mut Cursor::new(bytes), "fighter/mario/a.bin"),
+            Err(ArchiveError::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    fn built_many() -> (Archive, Cursor<Vec<u8>>) {
+        let bytes = ArchiveBuilder::new()
+            .add_file(
+                "fighter/mario/model/body/c00/def_mario_001_col.nutexb",
+                b"texture".as_slice(),
+            )
+            .add_file(
+                "fighter/mario/motion/body/c00/a00wait1.nuanmb",
+                b"animation".as_slice(),
+            )
+            .add_file(
+                "fighter/mario/model/body/c00/def_mario_002_col.nutexb",
+                b"other texture".as_slice(),
+            )
+            .build();
+        let mut reader = Cursor::new(bytes);
+        let archive = Archive::read(&mut reader).unwrap();
+        (archive, reader)
+    }
+
+    #[test]
+    fn files_with_extension_matches_extension_hash() {
+        let (mut archive, _) = built_many();
+        for (index, extension) in ["nutexb", "nuanmb", "nutexb"].into_iter().enumerate() {
+            archive
+                .resource
+                .file_path
+                .get_mut(index as u32)
+                .unwrap()
+                .set_extension(extension.into_hash());
+        }
+
+        let paths = |extension: &str| {
+            archive
+                .files_with_extension(extension)
+                .map(|path| path.path())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            paths("nutexb"),
+            [
+                "fighter/mario/model/body/c00/def_mario_001_col.nutexb".into_hash(),
+                "fighter/mario/model/body/c00/def_mario_002_col.nutexb".into_hash(),
+            ]
+        );
+        assert_eq!(
+            paths("nuanmb"),
+            ["fighter/mario/motion/body/c00/a00wait1.nuanmb".into_hash()]
+        );
+        assert!(paths("bntx").is_empty());
+    }
+
+    #[test]
+    fn file_location_resolves_shared_region() {
+        let (mut archive, mut reader) = built_many();
+        let first = FileLocation::resolve(&archive, Index::new(0)).unwrap();
+        let last = FileLocation::resolve(&archive, Index::new(2)).unwrap();
+        assert_eq!(first.region, DataRegion::Main);
+        assert_eq!(last.region, DataRegion::Main);
+
+        // Move the start of the shared region to the last file, the offsets themselves don't change
+        archive.metadata.shared_file_data_offset = last.absolute_offset;
+
+        let first = FileLocation::resolve(&archive, Index::new(0)).unwrap();
+        let shared = FileLocation::resolve(&archive, Index::new(2)).unwrap();
+        assert_eq!(first.region, DataRegion::Main);
+        assert_eq!(shared.region, DataRegion::Shared);
+        assert_eq!(shared.group_offset, last.group_offset);
+        assert_eq!(shared.in_group_offset, last.in_group_offset);
+        assert_eq!(
+            shared.absolute_offset,
+            archive.metadata.file_data_offset + shared.group_offset + shared.in_group_offset
+        );
+
+        assert_eq!(
+            archive
+                .read_file(
+                    &mut reader,
+                    "fighter/mario/model/body/c00/def_mario_002_col.nutexb"
+                )
+                .unwrap(),
+            b"other texture"
+        );
+    }
+
+    #[test]
+    fn serialize_tables_is_deterministic() {
+        let mut builder = ArchiveBuilder::new();
+        for costume in 0..4 {
+            for file in ["model.numdlb", "model.numshb", "def_mario_001_col.nutexb"] {
+                builder.add_package_file(
+                    format!("fighter/mario/c{costume:02}"),
+                    format!("fighter/mario/model/body/c{costume:02}/{file}"),
+                    file.as_bytes(),
+                );
+            }
+        }
+        builder.add_stream_file(
+            "sound/bgm",
+            "stream:/sound/bgm/bgm_crs2_01_menu.nus3audio",
+            b"music".as_slice(),
+        );
+        let bytes = builder.build();
+
+        let archive = Archive::from_slice(&bytes).unwrap();
+        let (_, first) = archive.serialize_tables().unwrap();
+        let (_, second) = archive.serialize_tables().unwrap();
+        assert_eq!(first, second);
+
+        // Every read archive has its own hash map state
+        let other = Archive::from_slice(&bytes).unwrap();
+        let (_, third) = other.serialize_tables().unwrap();
+        assert_eq!(first, third);
+    }
+
+    #[test]
+    fn serialized_tables_size_matches_serialize_tables() {
+        let package = |builder: &mut ArchiveBuilder| {
+            builder.add_package_file(
+                "fighter/mario/c00",
+                "fighter/mario/model/body/c00/model.numdlb",
+                b"model".as_slice(),
+            );
+        };
+        let stream = |builder: &mut ArchiveBuilder| {
+            builder.add_stream_file(
+                "sound/bgm",
+                "stream:/sound/bgm/bgm_crs2_01_menu.nus3audio",
+                b"music".as_slice(),
+            );
+        };
+        let loose = |builder: &mut ArchiveBuilder| {
+            builder.add_file("ui/param/database/ui_chara_db.prc", b"param".as_slice());
+        };
+
+        let fixtures: [&[&dyn Fn(&mut ArchiveBuilder)]; 5] = [
+            &[&package],
+            &[&stream],
+            &[&package, &stream],
+            &[&loose],
+            &[&package, &stream, &loose],
+        ];
+
+        for fixture in fixtures {
+            let mut builder = ArchiveBuilder::new();
+            for add in fixture {
+                add(&mut builder);
+            }
+            let mut archive = Archive::from_slice(&builder.build()).unwrap();
+
+            let (_, bytes) = archive.serialize_tables().unwrap();
+            assert_eq!(archive.serialized_tables_size(), bytes.len());
+
+            // The files outside of the packages are in the last group
+            let group = archive.resource.file_group.len() as u32 - 1;
+            archive.push_file(
+                "ui/message/msg_menu.msbt",
+                FileDataSpec {
+                    group,
+                    in_group_offset: 0,
+                    compressed_size: 0,
+                    decompressed_size: 0,
+                    is_compressed: false,
+                },
+            );
+
+            let (_, bytes) = archive.serialize_tables().unwrap();
+            assert_eq!(archive.serialized_tables_size(), bytes.len());
+        }
+    }
+
+    #[test]
+    fn read_chained_stops_at_the_user_table() {
+        let (archive, reader) = built_many();
+        let mut bytes = reader.into_inner();
+        bytes.extend_from_slice(b"user table");
+
+        let chained = Archive::read_chained(&mut Cursor::new(&bytes), &ZstdDecompressor).unwrap();
+        assert_eq!(chained.quick_serialize(), archive.quick_serialize());
+    }
+
+    #[test]
+    fn read_chained_concatenates_sections() {
+        use crate::io::WriteBinExt;
+
+        let (archive, reader) = built_many();
+        let mut bytes = reader.into_inner();
+        let tables = archive.quick_serialize();
+
+        let mut metadata = archive.metadata;
+        bytes.truncate(metadata.resource_table_offset as usize);
+        let (first, second) = tables.split_at(tables.len() / 2);
+        bytes.write_compressed_section(first).unwrap();
+        bytes.write_compressed_section(second).unwrap();
+
+        metadata.user_table_offset = bytes.len() as u64;
+        metadata.unknown_table_offset = bytes.len() as u64;
+        bytes[..std::mem::size_of::<ArchiveMetadata>()].copy_from_slice(metadata.cast_bytes());
+        bytes.extend_from_slice(b"user table");
+
+        let chained = Archive::read_chained(&mut Cursor::new(&bytes), &ZstdDecompressor).unwrap();
+        assert_eq!(chained.quick_serialize(), tables);
+    }
+
+    #[test]
+    fn push_file_takes_the_offset_in_bytes() {
+        let (mut archive, mut reader) = built_many();
+        let animation = archive.resource.file_data.get(1).unwrap();
+        let spec = FileDataSpec {
+            group: 0,
+            in_group_offset: animation.in_group_offset() as u32,
+            compressed_size: animation.compressed_size(),
+            decompressed_size: animation.decompressed_size(),
+            is_compressed: false,
+        };
+
+        archive.push_file("fighter/mario/motion/body/c00/a00wait2.nuanmb", spec);
+        assert_eq!(
+            archive
+                .read_file(&mut reader, "fighter/mario/motion/body/c00/a00wait2.nuanmb")
+                .unwrap(),
+            b"animation"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "is not one")]
+    fn push_file_rejects_missing_groups() {
+        let (mut archive, _) = built_many();
+        let group = archive.resource.file_group.len() as u32;
+        archive.push_file(
+            "fighter/mario/motion/body/c00/a00wait2.nuanmb",
+            FileDataSpec {
+                group,
+                in_group_offset: 0,
+                compressed_size: 0,
+                decompressed_size: 0,
+                is_compressed: false,
+            },
+        );
+    }
+
+    #[test]
+    fn serialize_tables_keeps_loose_files() {
+        let (archive, _) = built_many();
+        let (_, bytes) = archive.serialize_tables().unwrap();
+
+        // Every file is reachable, so the file data is written as it is and ends the tables
+        let file_data = FileData::cast_slice_bytes(archive.resource.file_data.fixed());
+        assert_eq!(file_data.len(), 3 * std::mem::size_of::<FileData>());
+        assert!(bytes.ends_with(file_data));
+    }
 }
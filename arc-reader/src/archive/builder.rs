@@ -0,0 +1,295 @@
+use hash40::Hash40;
+use indexmap::IndexMap;
+
+use crate::{io::WriteBinExt, IntoHash};
+
+use super::{
+    file_data::FileDataSpec, file_group::FileGroup, resource::ResourceTables, ArchiveMetadata,
+};
+
+/// The number of buckets in the file path lookup if none is specified
+const DEFAULT_BUCKET_COUNT: u32 = 0x40;
+
+fn align_up(value: usize, alignment: usize) -> usize {
+    (value + alignment - 1) & !(alignment - 1)
+}
+
+/// Builds a minimal archive in memory, which can be read back with [`Archive::read`](super::Archive::read)
+///
+/// This is intended for tests and fixtures, so it is limited to what can be expressed simply:
+/// - Every file and stream file is non-localized and non-regional, and files are stored uncompressed
+/// - Every package gets a single data group of its own, and has no children or sub package. The name,
+///   parent and lifetime hashes of packages are left empty
+/// - Files that are added without a package are all owned by a single [`FileGroup`] after the groups of
+///   the packages
+/// - Paths are expected to be unique across the whole archive, adding the same path to two packages
+///   leaves two rows with the same hash in the file path lookup
+///
+/// The resource section is ZSTD compressed, just like it is in a real archive.
+pub struct ArchiveBuilder {
+    files: IndexMap<Hash40, Vec<u8>>,
+    packages: IndexMap<Hash40, IndexMap<Hash40, Vec<u8>>>,
+    stream_folders: IndexMap<Hash40, IndexMap<Hash40, Vec<u8>>>,
+    bucket_count: u32,
+}
+
+impl Default for ArchiveBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArchiveBuilder {
+    /// Creates a builder for an archive without any files
+    pub fn new() -> Self {
+        Self {
+            files: IndexMap::new(),
+            packages: IndexMap::new(),
+            stream_folders: IndexMap::new(),
+            bucket_count: DEFAULT_BUCKET_COUNT,
+        }
+    }
+
+    /// Sets the number of buckets used by the file path lookup
+    ///
+    /// # Panics
+    /// Panics if `bucket_count` is zero
+    pub fn bucket_count(&mut self, bucket_count: u32) -> &mut Self {
+        assert!(
+            bucket_count > 0,
+            "the file path lookup needs at least one bucket"
+        );
+        self.bucket_count = bucket_count;
+        self
+    }
+
+    /// Adds a file to the archive, replacing the data of any file that was already added at `path`
+    ///
+    /// The file does not belong to any package. Files are stored in the order that they were first added.
+    pub fn add_file(&mut self, path: impl IntoHash, data: impl Into<Vec<u8>>) -> &mut Self {
+        self.files.insert(path.into_hash(), data.into());
+        self
+    }
+
+    /// Adds a file to `package`, replacing the data of any file that was already added to it at `path`
+    ///
+    /// The package is created when its first file is added. Packages, and the files in each of them, are
+    /// stored in the order that they were first added.
+    pub fn add_package_file(
+        &mut self,
+        package: impl IntoHash,
+        path: impl IntoHash,
+        data: impl Into<Vec<u8>>,
+    ) -> &mut Self {
+        self.packages
+            .entry(package.into_hash())
+            .or_default()
+            .insert(path.into_hash(), data.into());
+        self
+    }
+
+    /// Adds a stream file to `folder`, replacing the data of any stream file that was already added to
+    /// it at `path`
+    ///
+    /// The folder name is given without the `stream:` prefix, but the path includes it. The folder is
+    /// created when its first file is added.
+    pub fn add_stream_file(
+        &mut self,
+        folder: impl IntoHash,
+        path: impl IntoHash,
+        data: impl Into<Vec<u8>>,
+    ) -> &mut Self {
+        self.stream_folders
+            .entry(folder.into_hash())
+            .or_default()
+            .insert(path.into_hash(), data.into());
+        self
+    }
+
+    /// Lays out the stream data, file data and resource tables, returning the bytes of the archive
+    pub fn build(&self) -> Vec<u8> {
+        // Stream data offsets are relative to the start of the archive
+        let stream_data_offset = align_up(std::mem::size_of::<ArchiveMetadata>(), 0x10);
+        let mut stream_data = Vec::new();
+        let mut stream_folders = Vec::with_capacity(self.stream_folders.len());
+        for (folder, files) in self.stream_folders.iter() {
+            let files = files
+                .iter()
+                .map(|(path, data)| {
+                    stream_data.resize(align_up(stream_data.len(), 0x10), 0);
+                    let offset = (stream_data_offset + stream_data.len()) as u64;
+                    stream_data.extend_from_slice(data);
+                    (*path, offset, data.len() as u64)
+                })
+                .collect::<Vec<_>>();
+            stream_folders.push((*folder, files));
+        }
+
+        let file_data_offset = align_up(stream_data_offset + stream_data.len(), 0x10);
+        let mut file_data = Vec::new();
+        let mut resource = ResourceTables::empty(self.bucket_count);
+
+        for (path, files) in self.packages.iter() {
+            let group = resource.file_group.len() as u32;
+            let (offset, specs) = layout_group(&mut file_data, files, group);
+            let size = (file_data.len() - offset) as u32;
+            resource.push_package(*path, offset as u64, size, &specs);
+        }
+
+        // The number of packages is also the index of the first group that can own an entity, so the
+        // group of the files outside of the packages has to come after the groups of the packages
+        let group = resource.file_group.len() as u32;
+        let (offset, specs) = layout_group(&mut file_data, &self.files, group);
+        resource.file_group.push(FileGroup::new_data(
+            offset as u64,
+            (file_data.len() - offset) as u32,
+            resource.file_data.len() as u32,
+            specs.len() as u32,
+        ));
+
+        for (path, spec) in specs {
+            resource.push_file(path, spec);
+        }
+
+        for (folder, files) in stream_folders {
+            resource.push_stream_folder(folder, &files);
+        }
+
+        let mut section = Vec::new();
+        section
+            .write_compressed_section(&resource.quick_serialize())
+            .expect("compressing into memory should not fail");
+
+        let shared_file_data_offset = file_data_offset + file_data.len();
+        let resource_table_offset = align_up(shared_file_data_offset, 0x10);
+        let end = resource_table_offset + section.len();
+
+        let metadata = ArchiveMetadata {
+            magic: ArchiveMetadata::MAGIC,
+            stream_data_offset: stream_data_offset as u64,
+            file_data_offset: file_data_offset as u64,
+            shared_file_data_offset: shared_file_data_offset as u64,
+            resource_table_offset: resource_table_offset as u64,
+            user_table_offset: end as u64,
+            unknown_table_offset: end as u64,
+        };
+
+        let mut archive = Vec::with_capacity(end);
+        let _ = archive.write_binary(&metadata);
+        archive.resize(stream_data_offset, 0);
+        archive.extend_from_slice(&stream_data);
+        archive.resize(file_data_offset, 0);
+        archive.extend_from_slice(&file_data);
+        archive.resize(resource_table_offset, 0);
+        archive.extend_from_slice(&section);
+
+        archive
+    }
+}
+
+/// Appends the data of `files` to `file_data` as the group at index `group`, returning the offset of the
+/// group in the file data section along with the path and data spec of each file
+fn layout_group(
+    file_data: &mut Vec<u8>,
+    files: &IndexMap<Hash40, Vec<u8>>,
+    group: u32,
+) -> (usize, Vec<(Hash40, FileDataSpec)>) {
+    file_data.resize(align_up(file_data.len(), 0x10), 0);
+    let start = file_data.len();
+
+    // In-group offsets are stored divided by 4, so all data is aligned to a 0x4 boundary
+    let specs = files
+        .iter()
+        .map(|(path, data)| {
+            file_data.resize(align_up(file_data.len(), 0x4), 0);
+            let spec = FileDataSpec {
+                group,
+                in_group_offset: (file_data.len() - start) as u32,
+                compressed_size: data.len() as u32,
+                decompressed_size: data.len() as u32,
+                is_compressed: false,
+            };
+            file_data.extend_from_slice(data);
+            (*path, spec)
+        })
+        .collect();
+
+    (start, specs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{archive::Archive, index::Index, Locale};
+
+    #[test]
+    fn build_packages_and_stream_folders() {
+        let bytes = ArchiveBuilder::new()
+            .add_package_file(
+                "fighter/mario/c00",
+                "fighter/mario/model/body/c00/model.numdlb",
+                b"model".as_slice(),
+            )
+            .add_package_file(
+                "fighter/mario/c00",
+                "fighter/mario/model/body/c00/def_mario_001_col.nutexb",
+                b"texture".as_slice(),
+            )
+            .add_file("ui/param/database/ui_chara_db.prc", b"param".as_slice())
+            .add_stream_file(
+                "sound/bgm",
+                "stream:/sound/bgm/bgm_crs2_01_menu.nus3audio",
+                b"music".as_slice(),
+            )
+            .build();
+        let mut reader = Cursor::new(bytes);
+        let archive = Archive::read(&mut reader).unwrap();
+        assert!(archive.validate().is_empty());
+
+        let package = archive.lookup_file_package("fighter/mario/c00").unwrap();
+        for path in [
+            "fighter/mario/model/body/c00/model.numdlb",
+            "fighter/mario/model/body/c00/def_mario_001_col.nutexb",
+        ] {
+            let entity = archive.lookup_file_path(path).unwrap().file_entity_index();
+            let owner = archive.owning_package(Index::new(entity)).unwrap();
+            assert_eq!(owner.index(), package.index());
+        }
+
+        let loose = archive
+            .lookup_file_path("ui/param/database/ui_chara_db.prc")
+            .unwrap();
+        assert!(archive
+            .owning_package(Index::new(loose.file_entity_index()))
+            .is_none());
+
+        assert_eq!(
+            archive
+                .read_file(
+                    &mut reader,
+                    "fighter/mario/model/body/c00/def_mario_001_col.nutexb"
+                )
+                .unwrap(),
+            b"texture"
+        );
+        assert_eq!(
+            archive
+                .read_file(&mut reader, "ui/param/database/ui_chara_db.prc")
+                .unwrap(),
+            b"param"
+        );
+
+        let stream_path = archive
+            .lookup_stream_path("stream:/sound/bgm/bgm_crs2_01_menu.nus3audio")
+            .unwrap();
+        let stream_desc = stream_path.descriptor(Locale::Japanese).unwrap();
+        let stream_data = stream_desc.stream_data();
+        let offset = stream_data.offset() as usize;
+        assert_eq!(
+            &reader.get_ref()[offset..offset + stream_data.size() as usize],
+            b"music"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::io::{ReadBinExt, WriteBinExt};
+
+use super::{resource::ResourceTables, Archive, ArchiveMetadata};
+
+/// Magic at the start of every table cache file
+const CACHE_MAGIC: u64 = u64::from_le_bytes(*b"ARCTBLC\0");
+
+/// Version of the table cache layout
+///
+/// This must be bumped whenever the cache layout or the layout produced by
+/// [`ResourceTables::quick_serialize`] changes, so that stale caches are rejected
+///
+/// - 1: Initial layout
+/// - 2: Rows that were pushed after reading are written at the end of the section they belong
+///   to, and the header counts are taken from the table lengths
+/// - 3: The file path lookup is written with its own bucket count, which is no longer always `0x400`
+const CACHE_VERSION: u32 = 3;
+
+impl Archive {
+    /// Saves the archive metadata and the [`Self::quick_serialize`]d resource tables to `path`
+    ///
+    /// The cache can be read back with [`Self::load_table_cache`], which skips decompressing
+    /// the resource section of the archive.
+    pub fn save_table_cache(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let tables = self.quick_serialize();
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_u64::<LittleEndian>(CACHE_MAGIC)?;
+        writer.write_u32::<LittleEndian>(CACHE_VERSION)?;
+        writer.write_u32::<LittleEndian>(0)?;
+        writer.write_binary(&self.metadata)?;
+        writer.write_u64::<LittleEndian>(tables.len() as u64)?;
+        writer.write_all(&tables)?;
+        writer.flush()
+    }
+
+    /// Loads the resource tables from a cache that was written with [`Self::save_table_cache`]
+    ///
+    /// Caches with an unknown magic or written by a different version of the cache layout
+    /// are rejected with [`io::ErrorKind::InvalidData`], as are caches whose saved archive
+    /// metadata does not have the archive magic.
+    pub fn load_table_cache(path: impl AsRef<Path>) -> io::Result<ResourceTables> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let magic = reader.read_u64::<LittleEndian>()?;
+        if magic != CACHE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected table cache magic {CACHE_MAGIC:#x}, found {magic:#x}"),
+            ));
+        }
+
+        let version = reader.read_u32::<LittleEndian>()?;
+        if version != CACHE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected table cache version {CACHE_VERSION}, found {version}"),
+            ));
+        }
+
+        let _padding = reader.read_u32::<LittleEndian>()?;
+
+        // SAFETY: Confirms that the metadata is proper by checking the magic after reading it
+        let metadata = unsafe { reader.read_binary::<ArchiveMetadata>()? };
+        if metadata.magic != ArchiveMetadata::MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Expected magic {:#x} found {:#x}",
+                    ArchiveMetadata::MAGIC,
+                    metadata.magic
+                ),
+            ));
+        }
+
+        let len = reader.read_u64::<LittleEndian>()?;
+        let mut tables = Vec::new();
+        reader.take(len).read_to_end(&mut tables)?;
+        if tables.len() as u64 != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "Expected {len:#x} bytes of resource tables, found {:#x}",
+                    tables.len()
+                ),
+            ));
+        }
+
+        ResourceTables::from_bytes(tables.into_boxed_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::archive::ArchiveBuilder;
+
+    #[test]
+    fn table_cache_roundtrips() {
+        let bytes = ArchiveBuilder::new()
+            .add_package_file(
+                "fighter/mario/c00",
+                "fighter/mario/a.bin",
+                b"hello".as_slice(),
+            )
+            .add_stream_file(
+                "sound/bgm",
+                "stream:/sound/bgm/a.nus3audio",
+                b"bgm".as_slice(),
+            )
+            .build();
+        let archive = Archive::read(&mut Cursor::new(bytes)).unwrap();
+
+        let path = std::env::temp_dir().join(format!("arc-table-cache-{}", std::process::id()));
+        archive.save_table_cache(&path).unwrap();
+        let tables = Archive::load_table_cache(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(tables.unwrap().quick_serialize(), archive.quick_serialize());
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, ops::Range};
 
 use hash40::Hash40;
 
@@ -6,6 +6,28 @@ use crate::{hash::HashWithData, index::checked_range, BinaryRepr};
 
 use super::Archive;
 
+/// Moves a pointer to a slice inside of `from` to the same offset inside of `to`
+///
+/// Slices that do not point inside of `from`, such as the empty slices of rebuilt lookups, are returned as-is.
+///
+/// # Safety
+/// - Caller must ensure that `slice` is valid to read the length of
+/// - Caller must ensure that `to` holds the same bytes as `from`, so the rebased slice holds valid values
+unsafe fn rebase<T>(slice: *mut [T], from: &[u8], to: &mut [u8]) -> *mut [T] {
+    let start = slice.cast::<u8>() as usize;
+    let base = from.as_ptr() as usize;
+    if start < base || start > base + from.len() {
+        return slice;
+    }
+
+    let len = (&*slice).len();
+    let offset = start - base;
+    let bytes = &mut to[offset..offset + len * std::mem::size_of::<T>()];
+    crate::slice_sanity::<T>(bytes);
+
+    std::ptr::slice_from_raw_parts_mut(bytes.as_mut_ptr().cast::<T>(), len)
+}
+
 /// Table that represents a growable region of data
 ///
 /// Tables consist of two parts: a fixed-length array and a dynamic region. The fixed-length array
@@ -20,15 +42,22 @@ pub(crate) struct Table<T> {
     dynamic: Vec<T>,
 }
 
+// SAFETY: The fixed-length array is only accessed through `&self` or `&mut self`, and the creator of the table
+//      guarantees that nothing else references it, so the table behaves as if it owns the data
+unsafe impl<T: Send> Send for Table<T> {}
+unsafe impl<T: Sync> Sync for Table<T> {}
+
 impl<T: BinaryRepr> Table<T> {
-    /// SAFETY:
+    /// Creates a table whose fixed-length array is the first `count` values of `T` in `slice`
+    ///
+    /// # Safety
     /// - Caller must ensure that the data contained within the first
-    ///     `count * std::mem::size_of::<T>()` bytes of `slice` are valid
-    ///     values for `T`
+    ///   `count * std::mem::size_of::<T>()` bytes of `slice` are valid
+    ///   values for `T`
     /// - Caller must ensure that the returned table does not outlive `slice`
     /// - Caller must ensure that the range of data pointed to in the first
-    ///     `count * std::mem::size_of::<T>()` has no other exclusive references
-    ///     before or after creation of this table
+    ///   `count * std::mem::size_of::<T>()` has no other exclusive references
+    ///   before or after creation of this table
     pub unsafe fn new(slice: &mut [u8], count: usize) -> Self {
         let slice = &mut slice[..count * std::mem::size_of::<T>()];
         crate::slice_sanity::<T>(slice);
@@ -40,13 +69,6 @@ impl<T: BinaryRepr> Table<T> {
             dynamic: vec![],
         }
     }
-
-    /// Returns the length of the fixed array, in bytes
-    pub fn fixed_byte_len(&self) -> usize {
-        // SAFETY: Caller guarantees in constructor that there are no other mutable references
-        //      to this data
-        unsafe { (*self.fixed).len() * std::mem::size_of::<T>() }
-    }
 }
 
 impl<T> Table<T> {
@@ -58,7 +80,7 @@ impl<T> Table<T> {
         //      to this data, also they provide a reference so the pointer is non-null
         let fixed_len = self.fixed_len() as u32;
         if index < fixed_len {
-            unsafe { Some((*self.fixed).get_unchecked(index as usize)) }
+            unsafe { Some((&*self.fixed).get_unchecked(index as usize)) }
         } else {
             self.dynamic.get((index - fixed_len) as usize)
         }
@@ -71,7 +93,7 @@ impl<T> Table<T> {
         let fixed_len = self.fixed_len() as u32;
         if index < fixed_len {
             // SAFETY: See above
-            unsafe { Some((*self.fixed).get_unchecked_mut(index as usize)) }
+            unsafe { Some((&mut *self.fixed).get_unchecked_mut(index as usize)) }
         } else {
             self.dynamic.get_mut((index - fixed_len) as usize)
         }
@@ -89,13 +111,13 @@ impl<T> Table<T> {
     /// Checks if a table contains the provided index
     pub fn contains(&self, index: u32) -> bool {
         // SAFETY: See above
-        (self.dynamic.len() + unsafe { (*self.fixed).len() }) as u32 > index
+        (self.dynamic.len() + unsafe { (&*self.fixed).len() }) as u32 > index
     }
 
     /// Gets the length of the fixed-size array
     pub fn fixed_len(&self) -> usize {
         // SAFETY: See above
-        unsafe { (*self.fixed).len() }
+        unsafe { (&*self.fixed).len() }
     }
 
     /// Gets the length of the dynamic array
@@ -103,6 +125,12 @@ impl<T> Table<T> {
         self.dynamic.len()
     }
 
+    /// Checks if the provided index is in the fixed-size array, meaning that the row was
+    /// read from the archive rather than added afterwards
+    pub fn is_base_game(&self, index: u32) -> bool {
+        (index as usize) < self.fixed_len()
+    }
+
     /// Gets the total length of the table
     pub fn len(&self) -> usize {
         self.fixed_len() + self.dynamic_len()
@@ -125,14 +153,16 @@ impl<T> Table<T> {
 
     /// Gets a value from the table without bounds checking
     ///
-    /// SAFETY: Caller guarantees that the index provided is contained within this table
+    /// # Safety
+    /// Caller guarantees that the index provided is contained within this table
     pub unsafe fn get_unchecked(&self, index: u32) -> &T {
         self.get(index).unwrap_unchecked()
     }
 
     /// Gets a mutable reference to a value from the table without bounds checking
     ///
-    /// SAFETY: Caller guarantees that the index provided is contained within this table
+    /// # Safety
+    /// Caller guarantees that the index provided is contained within this table
     pub unsafe fn get_unchecked_mut(&mut self, index: u32) -> &mut T {
         self.get_mut(index).unwrap_unchecked()
     }
@@ -145,6 +175,26 @@ impl<T> Table<T> {
         (self.fixed_len() + length) as u32
     }
 
+    /// Removes a value from the dynamic region of this table, shifting all of the values after it
+    /// down by one
+    ///
+    /// Values in the fixed-length array cannot be removed, this method returns [`None`] if the index
+    /// is not in the dynamic region
+    pub fn remove_dynamic(&mut self, index: u32) -> Option<T> {
+        let index = (index as usize).checked_sub(self.fixed_len())?;
+        (index < self.dynamic.len()).then(|| self.dynamic.remove(index))
+    }
+
+    /// Removes a value from the dynamic region of this table, replacing it with the last value
+    /// of the table
+    ///
+    /// Values in the fixed-length array cannot be removed, this method returns [`None`] if the index
+    /// is not in the dynamic region
+    pub fn swap_remove_dynamic(&mut self, index: u32) -> Option<T> {
+        let index = (index as usize).checked_sub(self.fixed_len())?;
+        (index < self.dynamic.len()).then(|| self.dynamic.swap_remove(index))
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (u32, &T)> {
         // SAFETY: See above
         unsafe {
@@ -160,6 +210,111 @@ impl<T> Table<T> {
                 )
         }
     }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (u32, &mut T)> {
+        // SAFETY: See above
+        unsafe { (*self.fixed).iter_mut() }
+            .chain(self.dynamic.iter_mut())
+            .enumerate()
+            .map(|(index, data)| (index as u32, data))
+    }
+}
+
+impl<T: Copy> Table<T> {
+    /// Copies this table, pointing the fixed-length array at the same offset in `to` that it has in `from`
+    ///
+    /// # Safety
+    /// - Caller must ensure that the fixed-length array of this table points into `from`, and that `to`
+    ///   is a copy of `from`
+    /// - Caller must ensure that the returned table does not outlive `to`, and that no other table
+    ///   references the same range of `to`
+    pub(crate) unsafe fn rebased(&self, from: &[u8], to: &mut [u8]) -> Self {
+        Self {
+            fixed: rebase(self.fixed, from, to),
+            dynamic: self.dynamic.clone(),
+        }
+    }
+
+    /// Removes the rows in `range` from the fixed-size array, shifting every fixed row after
+    /// them down to fill the gap
+    ///
+    /// The range is clamped to the fixed-size array, and the clamped range is returned. The indices
+    /// of the rows after the range, including the whole dynamic region, shift down by its length.
+    pub fn remove_fixed_range(&mut self, range: Range<u32>) -> Range<u32> {
+        let len = self.fixed_len() as u32;
+        let range = range.start.min(len)..range.end.min(len);
+
+        // SAFETY: See above
+        unsafe {
+            let fixed = &mut *self.fixed;
+            fixed.copy_within(range.end as usize.., range.start as usize);
+            self.fixed = &mut fixed[..(len - range.len() as u32) as usize];
+        }
+
+        range
+    }
+
+    /// Rearranges the rows so that the row at each position is the row that was at `order[position]`
+    ///
+    /// Rows that keep their position at the start of the fixed-size array stay in it, every row after
+    /// the first one that moves is copied to the dynamic region. Rows that are not in `order` are removed.
+    ///
+    /// # Panics
+    /// Panics if `order` contains an index that is not a row of this table
+    pub fn reorder(&mut self, order: &[u32]) {
+        let rows = order
+            .iter()
+            .map(|index| {
+                *self
+                    .get(*index)
+                    .unwrap_or_else(|| panic!("Index {index} is not a row of the table"))
+            })
+            .collect::<Vec<_>>();
+
+        let fixed_len = self.fixed_len();
+        let kept = order
+            .iter()
+            .enumerate()
+            .take_while(|(position, index)| *position < fixed_len && **index == *position as u32)
+            .count();
+
+        // SAFETY: See above
+        unsafe {
+            let fixed = &mut *self.fixed;
+            self.fixed = &mut fixed[..kept];
+        }
+
+        self.dynamic = rows[kept..].to_vec();
+    }
+
+    /// Removes every row whose index is not marked in `keep`, shifting the remaining rows down
+    /// to fill the gaps
+    ///
+    /// Rows past the end of `keep` are removed as well. The remaining rows keep their order.
+    pub fn retain_rows(&mut self, keep: &[bool]) {
+        let fixed_len = self.fixed_len();
+        let kept = |index: usize| keep.get(index).copied().unwrap_or(false);
+
+        // SAFETY: See above
+        unsafe {
+            let fixed = &mut *self.fixed;
+            let mut len = 0;
+            for index in 0..fixed_len {
+                if kept(index) {
+                    fixed[len] = fixed[index];
+                    len += 1;
+                }
+            }
+
+            self.fixed = &mut fixed[..len];
+        }
+
+        let mut index = fixed_len;
+        self.dynamic.retain(|_| {
+            index += 1;
+            kept(index - 1)
+        });
+    }
 }
 
 /// Represents an immutable reference to a piece of data in a table
@@ -415,15 +570,21 @@ pub struct IndexLookup {
     dynamic: BTreeMap<Hash40, u32>,
 }
 
+// SAFETY: See the implementations for `Table`
+unsafe impl Send for IndexLookup {}
+unsafe impl Sync for IndexLookup {}
+
 impl IndexLookup {
-    /// SAFETY:
+    /// Creates a lookup whose fixed-length section is the first `count` hashes in `slice`
+    ///
+    /// # Safety
     /// - Caller must ensure that the data contained within the first
-    ///     `count * std::mem::size_of::<HashWithData>()` bytes of `slice` are valid
-    ///     values for `T`
+    ///   `count * std::mem::size_of::<HashWithData>()` bytes of `slice` are valid
+    ///   values for `T`
     /// - Caller must ensure that the returned table does not outlive `slice`
     /// - Caller must ensure that the range of data pointed to in the first
-    ///     `count * std::mem::size_of::<HashWithData>()` has no other exclusive references
-    ///     before or after creation of this table
+    ///   `count * std::mem::size_of::<HashWithData>()` has no other exclusive references
+    ///   before or after creation of this table
     pub unsafe fn new(slice: &mut [u8], count: usize) -> Self {
         let slice = &mut slice[..count * std::mem::size_of::<HashWithData>()];
         crate::slice_sanity::<HashWithData>(slice);
@@ -436,11 +597,26 @@ impl IndexLookup {
         }
     }
 
-    /// Returns the length of the fixed-size section, in bytes
-    pub fn fixed_byte_len(&self) -> usize {
-        // SAFETY: Caller guarantees in constructor that there are no other mutable references
-        //      to this data. They also provide a reference so the pointer is aligned and non-null
-        unsafe { (*self.fixed).len() * std::mem::size_of::<HashWithData>() }
+    /// Copies this lookup, pointing the fixed-length section at the same offset in `to` that it has in `from`
+    ///
+    /// # Safety
+    /// See [`Table::rebased`]
+    pub(crate) unsafe fn rebased(&self, from: &[u8], to: &mut [u8]) -> Self {
+        Self {
+            fixed: rebase(self.fixed, from, to),
+            dynamic: self.dynamic.clone(),
+        }
+    }
+
+    /// Calculates the total length of the index lookup
+    pub fn len(&self) -> usize {
+        // SAFETY: See above
+        unsafe { (&*self.fixed).len() + self.dynamic.len() }
+    }
+
+    /// Checks if the index lookup contains no hashes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /// Checks if the provided hash is contained within this lookup
@@ -492,8 +668,12 @@ impl IndexLookup {
 
     /// Inserts the index for the associated hash
     ///
+    /// Hashes that are already in the fixed-length section are updated in place, new hashes are added to the
+    /// dynamic region. Both regions stay sorted, so iterating the lookup still yields every hash in order.
+    ///
     /// This will return whatever the previous index was
     pub fn insert(&mut self, hash: Hash40, index: u32) -> Option<u32> {
+        // SAFETY: See above
         if let Ok(pos) = unsafe { (*self.fixed).binary_search_by_key(&hash, |key| key.hash40()) } {
             unsafe {
                 let prev = (*self.fixed)[pos].data();
@@ -505,6 +685,51 @@ impl IndexLookup {
         }
     }
 
+    /// Removes the provided hash from this lookup, returning the index that it pointed to
+    ///
+    /// Hashes removed from the fixed-length section are rotated to the end of the section,
+    /// which is then shrunk to exclude them
+    pub fn remove(&mut self, hash: Hash40) -> Option<u32> {
+        // SAFETY: See above
+        unsafe {
+            if let Ok(pos) = (*self.fixed).binary_search_by_key(&hash, |key| key.hash40()) {
+                let len = (&*self.fixed).len();
+                let index = (*self.fixed)[pos].data();
+                (&mut *self.fixed)[pos..].rotate_left(1);
+                self.fixed = &mut (&mut *self.fixed)[..len - 1];
+                return Some(index);
+            }
+        }
+
+        self.dynamic.remove(&hash)
+    }
+
+    /// Replaces every index in this lookup with the result of `f`, removing the hashes
+    /// that `f` returns [`None`] for
+    pub(crate) fn remap(&mut self, mut f: impl FnMut(u32) -> Option<u32>) {
+        let mut removed = vec![];
+
+        // SAFETY: See above
+        for hash in unsafe { (*self.fixed).iter_mut() } {
+            match f(hash.data()) {
+                Some(index) => hash.set_data(index),
+                None => removed.push(hash.hash40()),
+            }
+        }
+
+        self.dynamic.retain(|_, index| match f(*index) {
+            Some(new_index) => {
+                *index = new_index;
+                true
+            }
+            None => false,
+        });
+
+        for hash in removed {
+            self.remove(hash);
+        }
+    }
+
     pub(crate) fn iter(&self) -> IndexLookupIter<'_> {
         // SAFETY: See above
         let mut fixed = unsafe { (*self.fixed).iter() };
@@ -537,7 +762,7 @@ impl<'a> Iterator for IndexLookupIter<'a> {
 
                     Some((fixed_hash, fixed_index))
                 } else {
-                    self.current_fixed = self.dynamic_iter.next().map(|(h, i)| (*h, *i));
+                    self.current_dynamic = self.dynamic_iter.next().map(|(h, i)| (*h, *i));
                     Some((dyn_hash, dyn_index))
                 }
             }
@@ -561,7 +786,7 @@ pub(crate) struct Bucket {
     count: u32,
 }
 
-impl BinaryRepr for Bucket {}
+unsafe impl BinaryRepr for Bucket {}
 
 /// Container for looking up indexes from hashes, but with manual buckets for faster search times
 ///
@@ -574,18 +799,59 @@ impl BinaryRepr for Bucket {}
 /// The dynamic section is also a bucketed list of [`BTreeMap`] of [`Hash40`] -> [`Index`]
 pub struct BucketLookup {
     fixed_hashes: *mut [HashWithData],
-    fixed_buckets: *const [Bucket],
+    fixed_buckets: *mut [Bucket],
     dynamic: Box<[BTreeMap<Hash40, u32>]>,
+
+    /// Whether `fixed_buckets` was allocated by this lookup instead of borrowed, see [`Self::rebuild`]
+    owns_buckets: bool,
+}
+
+impl Drop for BucketLookup {
+    fn drop(&mut self) {
+        if self.owns_buckets {
+            // SAFETY: Owned buckets are only ever created from a leaked box in `Self::rebuild`
+            unsafe { drop(Box::from_raw(self.fixed_buckets)) }
+        }
+    }
+}
+
+// SAFETY: See the implementations for `Table`
+unsafe impl Send for BucketLookup {}
+unsafe impl Sync for BucketLookup {}
+
+/// Distribution of the hashes in a [`BucketLookup`] across its buckets
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BucketStats {
+    /// The number of buckets in the lookup
+    pub bucket_count: usize,
+
+    /// The number of hashes in the smallest bucket
+    pub min_chain: usize,
+
+    /// The number of hashes in the largest bucket
+    pub max_chain: usize,
+
+    /// The average number of hashes per bucket
+    pub avg_chain: f64,
+
+    /// The number of buckets that contain no hashes
+    pub empty_buckets: usize,
 }
 
 impl BucketLookup {
-    /// SAFETY:
-    /// - Caller must ensure that the data contained within the first [`Self::fixed_byte_len`]
-    ///     bytes of `slice` are valid values for `T`
+    /// The average number of hashes per bucket that [`Self::suggest_bucket_count`] aims for
+    pub const TARGET_LOAD_FACTOR: usize = 0x100;
+
+    /// Creates a lookup from the `bucket_count` buckets at the start of `slice`, followed by
+    /// `hash_count` hashes
+    ///
+    /// # Safety
+    /// - Caller must ensure that the data contained within the first
+    ///   `bucket_count * std::mem::size_of::<Bucket>() + hash_count * std::mem::size_of::<HashWithData>()`
+    ///   bytes of `slice` are valid buckets followed by valid hashes
     /// - Caller must ensure that the returned table does not outlive `slice`
-    /// - Caller must ensure that the range of data pointed to in the first
-    ///     `count * std::mem::size_of::<HashWithData>()` has no other exclusive references
-    ///     before or after creation of this table
+    /// - Caller must ensure that the same range of data has no other exclusive references
+    ///   before or after creation of this table
     pub unsafe fn new(slice: &mut [u8], hash_count: usize, bucket_count: usize) -> Self {
         let bucket_len = bucket_count * std::mem::size_of::<Bucket>();
         let bucket_slice = &mut slice[..bucket_len];
@@ -608,44 +874,120 @@ impl BucketLookup {
             fixed_hashes: hash_slice,
             fixed_buckets: bucket_slice,
             dynamic: buckets.into_boxed_slice(),
+            owns_buckets: false,
         }
     }
 
-    /// Returns the size of the fixed-length section, in bytes
-    pub fn fixed_byte_len(&self) -> usize {
-        // SAFETY: Caller guarantees in constructor that there are no other mutable references
-        //      to this data
-        unsafe {
-            (*self.fixed_hashes).len() * std::mem::size_of::<HashWithData>()
-                + (*self.fixed_buckets).len() * std::mem::size_of::<Bucket>()
+    /// Builds a new lookup from scratch, assigning every entry to its bucket with [`Self::bucket_index`]
+    ///
+    /// The new lookup does not borrow any data, all of the entries are stored in the dynamic region and
+    /// are sorted by hash within each bucket. If a hash shows up more than once, the last index is kept.
+    ///
+    /// # Panics
+    /// Panics if `bucket_count` is `0`
+    pub fn rebuild(entries: impl Iterator<Item = (Hash40, u32)>, bucket_count: usize) -> Self {
+        assert!(
+            bucket_count > 0,
+            "bucket lookup must have at least one bucket"
+        );
+
+        let mut dynamic = vec![BTreeMap::new(); bucket_count].into_boxed_slice();
+        for (hash, index) in entries {
+            dynamic[Self::bucket_index(hash, bucket_count)].insert(hash, index);
+        }
+
+        let fixed_buckets = vec![Bucket { start: 0, count: 0 }; bucket_count].into_boxed_slice();
+
+        Self {
+            fixed_hashes: std::ptr::slice_from_raw_parts_mut(
+                std::ptr::NonNull::<HashWithData>::dangling().as_ptr(),
+                0,
+            ),
+            fixed_buckets: Box::into_raw(fixed_buckets),
+            dynamic,
+            owns_buckets: true,
         }
     }
 
+    /// Builds a new lookup with the same entries as this one, spread across `bucket_count` buckets
+    ///
+    /// See [`Self::rebuild`]
+    ///
+    /// # Panics
+    /// Panics if `bucket_count` is `0`
+    pub fn rebuild_with_bucket_count(&self, bucket_count: usize) -> Self {
+        Self::rebuild(self.iter(), bucket_count)
+    }
+
+    /// Suggests a number of buckets for a lookup with `entry_count` entries, so that buckets hold
+    /// [`Self::TARGET_LOAD_FACTOR`] hashes on average
+    ///
+    /// Buckets are binary searched, so the load factor can be far higher than a typical hash map's
+    /// before lookups slow down. Every bucket adds 8 bytes to the serialized tables.
+    pub fn suggest_bucket_count(entry_count: usize) -> usize {
+        entry_count.div_ceil(Self::TARGET_LOAD_FACTOR).max(1)
+    }
+
+    /// Copies this lookup, pointing the fixed-length section at the same offset in `to` that it has in `from`
+    ///
+    /// Buckets that are owned by this lookup are copied instead.
+    ///
+    /// # Safety
+    /// See [`Table::rebased`]
+    pub(crate) unsafe fn rebased(&self, from: &[u8], to: &mut [u8]) -> Self {
+        let fixed_buckets = if self.owns_buckets {
+            Box::into_raw(Box::<[Bucket]>::from(&*self.fixed_buckets))
+        } else {
+            rebase(self.fixed_buckets, from, to)
+        };
+
+        Self {
+            fixed_hashes: rebase(self.fixed_hashes, from, to),
+            fixed_buckets,
+            dynamic: self.dynamic.clone(),
+            owns_buckets: self.owns_buckets,
+        }
+    }
+
+    /// Gets the index of the bucket that `hash` belongs in, for a lookup with `bucket_count` buckets
+    ///
+    /// This is the same bucket selection that the game uses when searching the lookup
+    pub fn bucket_index(hash: Hash40, bucket_count: usize) -> usize {
+        (hash.0 as usize) % bucket_count
+    }
+
+    /// Gets the index of the bucket that `hash` belongs in, along with the fixed hashes in that bucket
+    ///
+    /// A lookup without any buckets, or a bucket that points past the end of the fixed hashes,
+    /// has no fixed hashes instead of panicking, since both can be read from a malformed archive
     fn borrow_bucket(&self, hash: Hash40) -> (usize, &[HashWithData]) {
         // SAFETY: See above
         unsafe {
-            let length = (*self.fixed_buckets).len();
-            let bucket_index = (hash.0 as usize) % length;
-            let bucket = &(*self.fixed_buckets)[bucket_index];
+            let buckets = &*self.fixed_buckets;
+            if buckets.is_empty() {
+                return (0, &[]);
+            }
 
-            (
-                bucket_index,
-                &(*self.fixed_hashes)
-                    [bucket.start as usize..(bucket.start + bucket.count) as usize],
-            )
+            let bucket_index = Self::bucket_index(hash, buckets.len());
+            let bucket = &buckets[bucket_index];
+            let start = bucket.start as usize;
+            let hashes = (&*self.fixed_hashes)
+                .get(start..start + bucket.count as usize)
+                .unwrap_or(&[]);
+
+            (bucket_index, hashes)
         }
     }
 
     fn borrow_bucket_mut(&mut self, hash: Hash40) -> (usize, &mut [HashWithData]) {
         // SAFETY: See above
         unsafe {
-            let length = (*self.fixed_buckets).len();
-            let bucket_index = (hash.0 as usize) % length;
+            let bucket_index = Self::bucket_index(hash, (&*self.fixed_buckets).len());
             let bucket = &(*self.fixed_buckets)[bucket_index];
 
             (
                 bucket_index,
-                &mut (*self.fixed_hashes)
+                &mut (&mut *self.fixed_hashes)
                     [bucket.start as usize..(bucket.start + bucket.count) as usize],
             )
         }
@@ -655,7 +997,10 @@ impl BucketLookup {
     pub fn len(&self) -> usize {
         // SAFETY: See above
         unsafe {
-            (*self.fixed_buckets).len()
+            (*self.fixed_buckets)
+                .iter()
+                .map(|bucket| bucket.count as usize)
+                .sum::<usize>()
                 + self
                     .dynamic
                     .iter()
@@ -664,6 +1009,11 @@ impl BucketLookup {
         }
     }
 
+    /// Checks if the bucket lookup contains no hashes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns the number of buckets
     pub fn bucket_count(&self) -> usize {
         self.dynamic.len()
@@ -693,7 +1043,10 @@ impl BucketLookup {
         hashes
             .binary_search_by_key(&hash, |key| key.hash40())
             .is_ok()
-            || self.dynamic[bucket_index].contains_key(&hash)
+            || self
+                .dynamic
+                .get(bucket_index)
+                .is_some_and(|bucket| bucket.contains_key(&hash))
     }
 
     /// Gets the index that the provided hash points to
@@ -706,7 +1059,7 @@ impl BucketLookup {
             .binary_search_by_key(&hash, |key| key.hash40())
             .ok()
             .map(|index| u32::from(hashes[index].data()))
-            .or_else(|| self.dynamic[bucket_index].get(&hash).copied())
+            .or_else(|| self.dynamic.get(bucket_index)?.get(&hash).copied())
     }
 
     /// Sets the index of the provided hash
@@ -745,6 +1098,121 @@ impl BucketLookup {
         }
     }
 
+    /// Removes the provided hash from this lookup, returning the index that it pointed to
+    ///
+    /// Hashes removed from the fixed-length section are rotated to the end of their bucket and the
+    /// bucket is shrunk to exclude them, so that the rest of the bucket stays sorted
+    pub fn remove(&mut self, hash: Hash40) -> Option<u32> {
+        // SAFETY: See above
+        let bucket_index = unsafe {
+            let bucket_index = Self::bucket_index(hash, (&*self.fixed_buckets).len());
+            let bucket = &mut (*self.fixed_buckets)[bucket_index];
+            let hashes = &mut (&mut *self.fixed_hashes)
+                [bucket.start as usize..(bucket.start + bucket.count) as usize];
+
+            if let Ok(pos) = hashes.binary_search_by_key(&hash, |key| key.hash40()) {
+                let index = hashes[pos].data();
+                hashes[pos..].rotate_left(1);
+                bucket.count -= 1;
+                return Some(index);
+            }
+
+            bucket_index
+        };
+
+        self.dynamic[bucket_index].remove(&hash)
+    }
+
+    /// Replaces every index in this lookup with the result of `f`, removing the hashes
+    /// that `f` returns [`None`] for
+    pub(crate) fn remap(&mut self, mut f: impl FnMut(u32) -> Option<u32>) {
+        let mut removed = vec![];
+
+        // SAFETY: See above
+        unsafe {
+            for bucket in (*self.fixed_buckets).iter() {
+                for hash in &mut (&mut *self.fixed_hashes)
+                    [bucket.start as usize..(bucket.start + bucket.count) as usize]
+                {
+                    match f(hash.data()) {
+                        Some(index) => hash.set_data(index),
+                        None => removed.push(hash.hash40()),
+                    }
+                }
+            }
+        }
+
+        for bucket in self.dynamic.iter_mut() {
+            bucket.retain(|_, index| match f(*index) {
+                Some(new_index) => {
+                    *index = new_index;
+                    true
+                }
+                None => false,
+            });
+        }
+
+        for hash in removed {
+            self.remove(hash);
+        }
+    }
+
+    /// Calculates how the hashes in this lookup are distributed across its buckets
+    pub fn stats(&self) -> BucketStats {
+        let chains = self.buckets().map(|bucket| bucket.count as usize);
+
+        let mut stats = BucketStats {
+            bucket_count: self.bucket_count(),
+            min_chain: usize::MAX,
+            max_chain: 0,
+            avg_chain: 0.0,
+            empty_buckets: 0,
+        };
+
+        let mut total = 0;
+        for chain in chains {
+            stats.min_chain = stats.min_chain.min(chain);
+            stats.max_chain = stats.max_chain.max(chain);
+            stats.empty_buckets += (chain == 0) as usize;
+            total += chain;
+        }
+
+        if stats.bucket_count == 0 {
+            stats.min_chain = 0;
+        } else {
+            stats.avg_chain = total as f64 / stats.bucket_count as f64;
+        }
+
+        stats
+    }
+
+    /// Lists the hashes that share a bucket with another hash
+    ///
+    /// Each hash is paired with the next hash in its bucket, so a bucket with `n` hashes
+    /// produces `n - 1` pairs. Listing every combination would be quadratic in the size of the bucket.
+    pub fn collisions(&self) -> Vec<(Hash40, Hash40)> {
+        // Without any buckets there is nothing to collide, and no bucket index to compute
+        if self.bucket_count() == 0 {
+            return vec![];
+        }
+
+        let mut collisions = vec![];
+        let mut previous: Option<(usize, Hash40)> = None;
+
+        for (hash, _) in self.iter() {
+            let bucket = Self::bucket_index(hash, self.bucket_count());
+            if let Some((prev_bucket, prev_hash)) = previous {
+                if prev_bucket == bucket {
+                    collisions.push((prev_hash, hash));
+                }
+            }
+
+            previous = Some((bucket, hash));
+        }
+
+        collisions
+    }
+
     pub(crate) fn iter(&self) -> BucketLookupIter<'_> {
         // SAFETY: See above
         let fixed_hashes = unsafe { &(*self.fixed_hashes) };
@@ -1,9 +1,11 @@
 use crate::BinaryRepr;
+#[cfg(feature = "std")]
+use crate::{archive::Archive, index::Index};
 
 bitflags::bitflags! {
     /// Flags that control loading behavior and version information for a file
     #[repr(transparent)]
-    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     struct FileFlags : u32 {
         /// This file data is compressed using ZSTD. This flag is **always** set for files in the production
         /// release of Smash Ultimate. If this flag is set, then [`Self::IS_COMPRESSED`] must also be set
@@ -29,9 +31,115 @@ bitflags::bitflags! {
     }
 }
 
+/// The compression applied to the data of a [`FileData`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+    /// The data is stored as-is
+    None,
+
+    /// The data is compressed using ZSTD
+    Zstd,
+
+    /// The data is compressed, but not using ZSTD. This is never seen in the production release
+    /// of Smash Ultimate, and the format is unknown.
+    ProprietaryUnknown,
+}
+
+/// The region of the archive that a [`FileData`]'s bytes are stored in
+///
+/// Both regions live in the file data section of the archive, and the offsets of all
+/// [`FileGroup`](super::file_group::FileGroup) are relative to the start of that section regardless
+/// of region. The shared region is the tail of the section that starts at the shared file data offset
+/// in the archive's metadata, so the region is determined by comparing the absolute offset against it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DataRegion {
+    /// The data is stored before the shared file data section
+    Main,
+
+    /// The data is stored in the shared file data section
+    Shared,
+}
+
+/// Where, and how, the bytes of a [`FileData`] are stored in the archive
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DataLocation {
+    /// The offset of the data from the start of the archive
+    pub absolute_offset: u64,
+
+    /// The size of the data on disk
+    pub compressed_size: u32,
+
+    /// The size of the data once decompressed
+    pub decompressed_size: u32,
+
+    /// The compression that the data is stored with
+    pub compression: Compression,
+
+    /// The region of the archive that the data is stored in
+    pub region: DataRegion,
+}
+
+/// The parts that make up the offset of a [`FileData`]'s bytes in the archive
+///
+/// The offset of a [`FileData`] is relative to the [`FileGroup`](super::file_group::FileGroup) that contains it,
+/// and the offset of the group is relative to the start of the file data section. This is the only place that
+/// adds them up, every other location in the archive is built from it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FileLocation {
+    /// The region of the archive that the data is stored in
+    pub region: DataRegion,
+
+    /// The offset of the containing group from the start of the file data section
+    pub group_offset: u64,
+
+    /// The offset of the data from the start of the containing group
+    pub in_group_offset: u64,
+
+    /// The offset of the data from the start of the archive
+    pub absolute_offset: u64,
+}
+
+#[cfg(feature = "std")]
+impl FileLocation {
+    /// Resolves where the [`FileData`] at `file_data_index` is stored in `archive`
+    ///
+    /// The group that contains the data is found through the first [`FileDesc`](super::file_desc::FileDesc)
+    /// that points to it. Returns [`None`] if the index is out of range or no descriptor points to the data.
+    pub fn resolve(archive: &Archive, file_data_index: Index<FileData>) -> Option<Self> {
+        let file_data_index = file_data_index.get();
+        let (_, desc) = archive
+            .resource
+            .file_desc
+            .iter()
+            .find(|(_, desc)| desc.file_data_index() == file_data_index)?;
+
+        Self::in_group(archive, desc.group_index(), file_data_index)
+    }
+
+    /// Resolves where the data is stored when the group that contains it is already known
+    ///
+    /// Returns [`None`] if either row is out of range, or if the offsets add up to more than fits in a [`u64`]
+    pub(crate) fn in_group(archive: &Archive, group: u32, data: u32) -> Option<Self> {
+        let group_offset = archive.resource.file_group.get(group)?.archive_offset();
+        let in_group_offset = archive.resource.file_data.get(data)?.in_group_offset();
+        let absolute_offset = archive
+            .metadata
+            .file_data_offset
+            .checked_add(group_offset)?
+            .checked_add(in_group_offset)?;
+
+        Some(Self {
+            region: archive.region_of(absolute_offset),
+            group_offset,
+            in_group_offset,
+            absolute_offset,
+        })
+    }
+}
+
 /// Contains information on how to read the data on disk
 #[repr(C)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FileData {
     /// The offset to add to the `archive_offset` of the [`FileGroup`](super::file_group::FileGroup) that contains this data
     in_group_offset: u32,
@@ -48,4 +156,118 @@ pub struct FileData {
     flags: FileFlags,
 }
 
-impl BinaryRepr for FileData {}
+unsafe impl BinaryRepr for FileData {}
+
+/// Describes where and how the binary data of a new file is stored, used when appending
+/// files with [`ResourceTables::push_file`](crate::archive::resource::ResourceTables::push_file)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FileDataSpec {
+    /// The [`FileGroup`](super::file_group::FileGroup) that the data is stored in
+    pub group: u32,
+
+    /// The offset of the data relative to the start of `group`, in bytes
+    ///
+    /// The offset is stored divided by 4, so it has to be a multiple of 4
+    pub in_group_offset: u32,
+
+    /// The size of the data on disk
+    pub compressed_size: u32,
+
+    /// The size of the data once decompressed
+    pub decompressed_size: u32,
+
+    /// Whether the data on disk is ZSTD compressed
+    pub is_compressed: bool,
+}
+
+impl FileData {
+    pub(crate) fn new(spec: &FileDataSpec) -> Self {
+        let flags = if spec.is_compressed {
+            FileFlags::IS_ZSTD_COMPRESSION | FileFlags::IS_COMPRESSED
+        } else {
+            FileFlags::empty()
+        };
+
+        Self {
+            in_group_offset: spec.in_group_offset >> 2,
+            compressed_size: spec.compressed_size,
+            decompressed_size: spec.decompressed_size,
+            flags,
+        }
+    }
+
+    /// Checks if this data is marked as ZSTD compressed without being marked as compressed, which
+    /// makes the resource loaders abort, see [`Archive::validate`](crate::archive::Archive::validate)
+    pub(crate) fn has_zstd_without_compression(&self) -> bool {
+        self.flags.contains(FileFlags::IS_ZSTD_COMPRESSION)
+            && !self.flags.contains(FileFlags::IS_COMPRESSED)
+    }
+
+    /// The raw bits of this data's flags, including any bits that this crate does not know about
+    ///
+    /// Unknown bits are never masked off, so they are written back out unchanged when the tables are serialized
+    pub fn raw_flags(&self) -> u32 {
+        self.flags.bits()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_raw_flags(&mut self, bits: u32) {
+        self.flags = FileFlags::from_bits_retain(bits);
+    }
+
+    /// Gets the compression that the data on disk is stored with
+    pub fn compression(&self) -> Compression {
+        if !self.flags.contains(FileFlags::IS_COMPRESSED) {
+            Compression::None
+        } else if self.flags.contains(FileFlags::IS_ZSTD_COMPRESSION) {
+            Compression::Zstd
+        } else {
+            Compression::ProprietaryUnknown
+        }
+    }
+
+    /// The offset of the data relative to its [`FileGroup`](super::file_group::FileGroup), in bytes
+    ///
+    /// The raw value is stored divided by 4, since all file data is aligned to a 0x4 boundary
+    pub(crate) fn in_group_offset(&self) -> u64 {
+        (self.in_group_offset as u64) << 2
+    }
+
+    pub(crate) fn compressed_size(&self) -> u32 {
+        self.compressed_size
+    }
+
+    pub(crate) fn decompressed_size(&self) -> u32 {
+        self.decompressed_size
+    }
+
+    /// The size of the data on disk relative to its decompressed size
+    ///
+    /// Lower is better, uncompressed data has a ratio of `1.0` and data that grew when it was compressed
+    /// has a ratio above `1.0`. Empty data is treated as uncompressed.
+    pub fn compression_ratio(&self) -> f32 {
+        if self.decompressed_size == 0 {
+            1.0
+        } else {
+            self.compressed_size as f32 / self.decompressed_size as f32
+        }
+    }
+
+    /// Marks the data as being stored uncompressed
+    pub(crate) fn set_uncompressed(&mut self) {
+        self.flags
+            .remove(FileFlags::IS_COMPRESSED | FileFlags::IS_ZSTD_COMPRESSION);
+    }
+
+    /// Moves the data to `in_group_offset` bytes into its group, with `compressed_size` bytes on disk
+    ///
+    /// Uncompressed data has the same size on disk as it does decompressed, so both sizes are
+    /// updated for it.
+    pub(crate) fn set_stored(&mut self, in_group_offset: u64, compressed_size: u32) {
+        self.in_group_offset = (in_group_offset >> 2) as u32;
+        self.compressed_size = compressed_size;
+        if self.compression() == Compression::None {
+            self.decompressed_size = compressed_size;
+        }
+    }
+}
@@ -1,5 +1,8 @@
-use crate::{archive::resource::serialization::SerState, index::INVALID_INDEX, BinaryRepr};
+use crate::BinaryRepr;
+#[cfg(feature = "std")]
+use crate::{archive::resource::serialization::SerState, index::INVALID_INDEX};
 
+#[cfg(feature = "std")]
 use super::{
     file_data::FileData, file_entity::FileEntity, file_group::FileGroup, file_info::FileInfo,
 };
@@ -10,7 +13,7 @@ use super::{
 /// however I've categorized them as an enum for easier understanding
 /// and pulled out all of the combinations that exist in the archive
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum LoadMethod {
+pub enum LoadMethod {
     /// Loading file data using the descriptor this came from would be considered invalid
     /// and load invalid data in the game. This would either cause a crash or an infinite load
     ///
@@ -46,7 +49,11 @@ enum LoadMethod {
     ///
     /// For example, Lyn's voicelines are available in both English and Japanese, but no other locales. The other localized
     /// descriptors will point to the English and Japanese locales depending on which language they are for.
-    UnsupprotedRegionLocale(u32),
+    UnsupportedRegionLocale(u32),
+
+    /// A load method kind that isn't one of the above. The raw kind (the upper 8 bits) and payload (the lower
+    /// 24 bits) are kept as-is so that they can be written back out unchanged
+    Other(u8, u32),
 }
 
 impl From<LoadMethod> for FileLoadMethod {
@@ -57,9 +64,12 @@ impl From<LoadMethod> for FileLoadMethod {
             LoadMethod::PackageSkip(index) => Self((0x03 << 24) | u32::from(index)),
             LoadMethod::Unknown => Self(0x05 << 24),
             LoadMethod::SharedButOwned(index) => Self((0x09 << 24) | u32::from(index)),
-            LoadMethod::UnsupprotedRegionLocale(region_locale) => {
+            LoadMethod::UnsupportedRegionLocale(region_locale) => {
                 Self((0x10 << 24) | region_locale)
             }
+            LoadMethod::Other(kind, payload) => {
+                Self(((kind as u32) << 24) | (payload & 0x00FF_FFFF))
+            }
         }
     }
 }
@@ -67,21 +77,22 @@ impl From<LoadMethod> for FileLoadMethod {
 impl From<FileLoadMethod> for LoadMethod {
     fn from(value: FileLoadMethod) -> Self {
         let kind = value.0 >> 24;
+        let payload = value.0 & 0x00FF_FFFF;
         match kind {
-            0x00 => Self::Unowned(value.0 & 0x00FF_FFFF),
-            0x01 => Self::Owned(value.0 & 0x00FF_FFFF),
-            0x03 => Self::PackageSkip(value.0 & 0x00FF_FFFF),
+            0x00 => Self::Unowned(payload),
+            0x01 => Self::Owned(payload),
+            0x03 => Self::PackageSkip(payload),
             0x05 => Self::Unknown,
-            0x09 => Self::SharedButOwned(value.0 & 0x00FF_FFFF),
-            0x10 => Self::UnsupprotedRegionLocale(value.0 & 0x00FF_FFFF),
-            _ => panic!("Unsupported load method {:#02x}", kind),
+            0x09 => Self::SharedButOwned(payload),
+            0x10 => Self::UnsupportedRegionLocale(payload),
+            _ => Self::Other(kind as u8, payload),
         }
     }
 }
 
 /// Transparent representation of [`LoadMethod`]
 #[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 struct FileLoadMethod(u32);
 
 /// Represents information about how to load and locate binary file data
@@ -92,7 +103,7 @@ struct FileLoadMethod(u32);
 /// Once you move past the file descriptor, and into the [`FileData`](super::file_data::FileData), you have no information
 /// about the file except for how to interpret the bytes on disk
 #[repr(C)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FileDesc {
     /// The file group that contains this file descriptor. This is used to instruct the resource loader where in the archive
     /// the start of the file data chunk is
@@ -107,20 +118,43 @@ pub struct FileDesc {
     load_method: FileLoadMethod,
 }
 
-impl BinaryRepr for FileDesc {}
+unsafe impl BinaryRepr for FileDesc {}
 
 impl FileDesc {
+    pub(crate) fn new(group: u32, file_data: u32, load_method: LoadMethod) -> Self {
+        Self {
+            group,
+            file_data,
+            load_method: FileLoadMethod::from(load_method),
+        }
+    }
+
+    pub(crate) fn group_index(&self) -> u32 {
+        self.group
+    }
+
     pub(crate) fn file_data_index(&self) -> u32 {
         self.file_data
     }
+
+    /// Decodes the method the resource loaders use when loading data through this descriptor
+    ///
+    /// A handful of descriptors in retail archives use load method kinds that aren't understood yet,
+    /// those are returned as [`LoadMethod::Other`] containing the raw kind and payload
+    pub fn load_method(&self) -> LoadMethod {
+        LoadMethod::from(self.load_method)
+    }
 }
 
+#[cfg(feature = "std")]
 impl FileDesc {
     pub(crate) fn reserve(&self, state: &mut SerState) {
         state.reserve::<FileData>(self.file_data);
     }
 
-    pub(crate) fn reinternalize(&mut self, state: &SerState) {
+    /// Updates the group, data and load method indices to their reserved positions, and clears
+    /// the version of owned data unless `keep_versions` is set
+    pub(crate) fn reinternalize(&mut self, state: &SerState, keep_versions: bool) {
         self.group = state.get::<FileGroup>(self.group);
         self.file_data = state.get::<FileData>(self.file_data);
 
@@ -129,12 +163,16 @@ impl FileDesc {
         match &mut load_method {
             LoadMethod::Unowned(index) => *index = state.get::<FileEntity>(*index),
 
-            // This points to versioned data, we are eliminating that here
-            LoadMethod::Owned(index) => *index = INVALID_INDEX,
+            // This points to versioned data, which is eliminated unless it is being kept
+            LoadMethod::Owned(index) if !keep_versions => *index = INVALID_INDEX,
+            LoadMethod::Owned(_) => {}
             LoadMethod::PackageSkip(index) => *index = state.get::<FileInfo>(*index),
             LoadMethod::Unknown => {}
             LoadMethod::SharedButOwned(index) => *index = state.get::<FileEntity>(*index),
-            LoadMethod::UnsupprotedRegionLocale(_) => {}
+            LoadMethod::UnsupportedRegionLocale(_) => {}
+
+            // Unknown load methods are preserved as-is, we don't know if their payload is an index
+            LoadMethod::Other(..) => {}
         }
 
         self.load_method = FileLoadMethod::from(load_method);
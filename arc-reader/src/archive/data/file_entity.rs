@@ -1,5 +1,8 @@
-use crate::{archive::resource::serialization::SerState, BinaryRepr};
+#[cfg(feature = "std")]
+use crate::archive::{containers::TableRef, resource::serialization::SerState};
+use crate::BinaryRepr;
 
+#[cfg(feature = "std")]
 use super::{file_group::FileGroup, file_info::FileInfo, file_package::FilePackage};
 
 /// Represents a unique file entity
@@ -7,7 +10,7 @@ use super::{file_group::FileGroup, file_info::FileInfo, file_package::FilePackag
 /// These file entities are used internally to track whether or not a file has been loaded.
 /// Shared file data exists by having multiple [`FilePath`](super::file_path::FilePath)
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FileEntity {
     /// File entities can belong to either a [`FilePackage`](super::file_package::FilePackage)
     /// or a [`FileGroup`](super::file_group::FileGroup).
@@ -25,15 +28,78 @@ pub struct FileEntity {
     info: u32,
 }
 
-impl BinaryRepr for FileEntity {}
+unsafe impl BinaryRepr for FileEntity {}
+
+/// The row that owns a [`FileEntity`], see [`TableRef::owner`]
+#[cfg(feature = "std")]
+pub enum EntityOwner<'a> {
+    /// The entity is owned by a package, and is loaded with it
+    Package(TableRef<'a, FilePackage>),
+
+    /// The entity is shared between packages and is owned by a group of shared data
+    Group(TableRef<'a, FileGroup>),
+}
+
+#[cfg(feature = "std")]
+impl TableRef<'_, FileEntity> {
+    /// Resolves the [`FilePackage`] or [`FileGroup`] that owns this entity
+    pub fn owner(&self) -> EntityOwner<'_> {
+        let archive = self.archive();
+        if self.is_owned_by_group(archive.resource.package_len()) {
+            EntityOwner::Group(
+                archive
+                    .get_file_group(self.package_or_group)
+                    .expect("file entity should point to a valid file group"),
+            )
+        } else {
+            EntityOwner::Package(
+                archive
+                    .get_file_package(self.package_or_group)
+                    .expect("file entity should point to a valid file package"),
+            )
+        }
+    }
+
+    /// The [`FileInfo`] that represents the real data of this entity
+    pub fn info(&self) -> TableRef<'_, FileInfo> {
+        self.archive()
+            .get_file_info(self.info)
+            .expect("file entity should point to a valid file info")
+    }
+}
 
 impl FileEntity {
+    pub(crate) fn new(package_or_group: u32, info: u32) -> Self {
+        Self {
+            package_or_group,
+            info,
+        }
+    }
+
+    pub(crate) fn info_index(&self) -> u32 {
+        self.info
+    }
+
+    /// The index of the package or group that owns this entity, see [`Self::is_owned_by_group`]
+    pub(crate) fn package_or_group_index(&self) -> u32 {
+        self.package_or_group
+    }
+
+    /// Checks if this entity is owned by a [`FileGroup`] rather than a [`FilePackage`]
+    ///
+    /// `package_len` is the number of packages in the archive, which is also the index of the
+    /// first file group of shared data
+    pub(crate) fn is_owned_by_group(&self, package_len: u32) -> bool {
+        self.package_or_group >= package_len
+    }
+
     /// Reinternalizes a file entity
     ///
     /// Everything that a file entity references should be reserved with the [`SerState`]
     /// before calling this method ([`FilePackage`], [`FileGroup`], [`FileInfo`])
+    #[cfg(feature = "std")]
     pub(crate) fn reinternalize(&mut self, state: &SerState, package_len: u32) {
-        if self.package_or_group >= package_len {
+        if self.is_owned_by_group(package_len) {
             self.package_or_group = state.get::<FileGroup>(self.package_or_group);
         } else {
             self.package_or_group = state.get::<FilePackage>(self.package_or_group);
@@ -1,15 +1,21 @@
-use std::ops::Range;
+use core::ops::Range;
 
+#[cfg(feature = "std")]
+use crate::archive::{
+    containers::{TableRef, TableSliceRef},
+    resource::serialization::SerState,
+};
 use crate::{
-    archive::{
-        containers::{TableRef, TableSliceRef},
-        resource::serialization::SerState,
-    },
     index::{checked_range, INVALID_INDEX},
     BinaryRepr,
 };
 
-use super::{file_data::FileData, file_info::FileInfo, file_package::FilePackage};
+#[cfg(feature = "std")]
+use super::{
+    file_data::{DataRegion, FileData},
+    file_info::FileInfo,
+    file_package::FilePackage,
+};
 
 /// Represents a collection of either [`FileInfo`](super::file_info::FileInfo) or [`FileData`](super::file_data::FileData)
 ///
@@ -25,7 +31,7 @@ use super::{file_data::FileData, file_info::FileInfo, file_package::FilePackage}
 /// the resource loaders interpret the contents of these files changes: instead of using the [`FileDesc`](super::file_desc::FileDesc)
 /// to determine which group to load the data from, it uses the group itself for more optimal load times.
 #[repr(C)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FileGroup {
     /// The starting offset of this data chunk group
     ///
@@ -59,9 +65,43 @@ pub struct FileGroup {
     pub(crate) redirection: u32,
 }
 
-impl BinaryRepr for FileGroup {}
+unsafe impl BinaryRepr for FileGroup {}
 
 impl FileGroup {
+    /// Creates a group of `child_count` [`FileData`] rows starting at `child_start`, which is not
+    /// driven by any package
+    pub(crate) fn new_data(
+        archive_offset: u64,
+        size: u32,
+        child_start: u32,
+        child_count: u32,
+    ) -> Self {
+        Self {
+            archive_offset: [archive_offset as u32, (archive_offset >> 32) as u32],
+            decompressed_size: size,
+            compressed_size: size,
+            child_start,
+            child_count,
+            redirection: INVALID_INDEX,
+        }
+    }
+
+    pub(crate) fn archive_offset(&self) -> u64 {
+        ((self.archive_offset[1] as u64) << 32) | self.archive_offset[0] as u64
+    }
+
+    /// Moves this group to `archive_offset` bytes into the file data section
+    pub(crate) fn set_location(
+        &mut self,
+        archive_offset: u64,
+        compressed_size: u32,
+        decompressed_size: u32,
+    ) {
+        self.archive_offset = [archive_offset as u32, (archive_offset >> 32) as u32];
+        self.compressed_size = compressed_size;
+        self.decompressed_size = decompressed_size;
+    }
+
     pub(crate) fn child_range(&self) -> Range<u32> {
         checked_range(self.child_start, self.child_count)
     }
@@ -71,9 +111,134 @@ impl FileGroup {
     }
 }
 
+/// What the children of a [`FileGroup`] are, see [`TableRef::child_kind`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GroupChildKind {
+    /// The group points to a range of [`FileData`]
+    Data,
+
+    /// The group points to a range of [`FileInfo`]
+    Info,
+}
+
+/// Which section of the group table a [`FileGroup`] is in, see [`Archive::file_groups`](crate::archive::Archive::file_groups)
+///
+/// The group table is laid out as the groups that belong to packages, followed by the groups
+/// that contain shared [`FileInfo`], followed by the versioned groups. The size of each section
+/// comes from the counts in the [`ResourceTableHeader`](crate::archive::resource::ResourceTableHeader),
+/// limited to the groups that were read from the archive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GroupRegion {
+    /// One of the first [`file_data_group_count`](crate::archive::resource::ResourceTableHeader::file_data_group_count) groups
+    Data,
+
+    /// One of the next [`file_info_group_count`](crate::archive::resource::ResourceTableHeader::file_info_group_count) groups
+    Info,
+
+    /// One of the last [`versioned_file_group_count`](crate::archive::resource::ResourceTableHeader::versioned_file_group_count) groups
+    Versioned,
+}
+
+impl GroupRegion {
+    /// Classifies the group at `index` in a table whose first `fixed_len` rows were read from the archive
+    ///
+    /// The sections are laid out with the counts from the resource table header, but each one is clamped to
+    /// the rows that were read, so stale or malformed counts never place a section past the end of the table.
+    /// Groups past the end of the versioned section have been added since the archive was read, and they
+    /// are always package data groups.
+    pub(crate) fn of(
+        index: u32,
+        fixed_len: u32,
+        data_count: u32,
+        info_count: u32,
+        versioned_count: u32,
+    ) -> Self {
+        let section_end = |start: u32, count: u32| {
+            start
+                .checked_add(count)
+                .map_or(fixed_len, |end| end.min(fixed_len))
+        };
+
+        let info_start = data_count.min(fixed_len);
+        let versioned_start = section_end(info_start, info_count);
+        let versioned_end = section_end(versioned_start, versioned_count);
+
+        if index < info_start || index >= versioned_end {
+            Self::Data
+        } else if index < versioned_start {
+            Self::Info
+        } else {
+            Self::Versioned
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl TableRef<'_, FileGroup> {
+    /// Determines whether this group's children are [`FileData`] or [`FileInfo`]
+    ///
+    /// Groups that point to file info always redirect to themselves, every other group points to file data
+    pub fn child_kind(&self) -> GroupChildKind {
+        if self.redirection == self.index() {
+            GroupChildKind::Info
+        } else {
+            GroupChildKind::Data
+        }
+    }
+
+    /// Views this group as a group of [`FileData`], if that is what its children are
+    pub fn as_data_group(&self) -> Option<FileDataGroupRef<'_>> {
+        (self.child_kind() == GroupChildKind::Data)
+            .then(|| self.archive().get_file_group(self.index()))
+            .flatten()
+            .map(FileDataGroupRef)
+    }
+
+    /// Views this group as a group of [`FileInfo`], if that is what its children are
+    pub fn as_info_group(&self) -> Option<FileInfoGroupRef<'_>> {
+        (self.child_kind() == GroupChildKind::Info)
+            .then(|| self.archive().get_file_group(self.index()))
+            .flatten()
+            .map(FileInfoGroupRef)
+    }
+
+    /// The offset of this group's data, recombined from the two halves it is stored as
+    ///
+    /// This is relative to the start of the archive's file data section
+    pub fn archive_offset(&self) -> u64 {
+        (**self).archive_offset()
+    }
+
+    /// The region of the archive that the start of this group's data is stored in
+    ///
+    /// Use [`Archive::file_data_location`](crate::archive::Archive::file_data_location) to get the
+    /// region of a single [`FileData`] in the group.
+    pub fn region(&self) -> DataRegion {
+        let archive = self.archive();
+        archive.region_of(archive.metadata.file_data_offset + self.archive_offset())
+    }
+
+    /// Checks if this group's data is stored in the shared file data region
+    pub fn is_shared_data(&self) -> bool {
+        self.region() == DataRegion::Shared
+    }
+
+    /// The size of all of this group's contents when compressed, in bytes
+    pub fn compressed_size(&self) -> u32 {
+        self.compressed_size
+    }
+
+    /// The size of all of this group's contents when decompressed, in bytes
+    pub fn decompressed_size(&self) -> u32 {
+        self.decompressed_size
+    }
+}
+
+#[cfg(feature = "std")]
 pub struct FileInfoGroupRef<'a>(pub(super) TableRef<'a, FileGroup>);
 
-impl<'a> std::ops::Deref for FileInfoGroupRef<'a> {
+#[cfg(feature = "std")]
+impl<'a> core::ops::Deref for FileInfoGroupRef<'a> {
     type Target = TableRef<'a, FileGroup>;
 
     fn deref(&self) -> &Self::Target {
@@ -81,6 +246,7 @@ impl<'a> std::ops::Deref for FileInfoGroupRef<'a> {
     }
 }
 
+#[cfg(feature = "std")]
 impl FileInfoGroupRef<'_> {
     pub fn file_info(&self) -> TableSliceRef<'_, FileInfo> {
         self.0
@@ -94,8 +260,19 @@ impl FileInfoGroupRef<'_> {
     }
 }
 
+#[cfg(feature = "std")]
 pub struct FileDataGroupRef<'a>(pub(super) TableRef<'a, FileGroup>);
 
+#[cfg(feature = "std")]
+impl<'a> core::ops::Deref for FileDataGroupRef<'a> {
+    type Target = TableRef<'a, FileGroup>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "std")]
 impl FileDataGroupRef<'_> {
     pub fn file_data(&self) -> TableSliceRef<'_, FileData> {
         self.0
@@ -109,6 +286,7 @@ impl FileDataGroupRef<'_> {
     }
 }
 
+#[cfg(feature = "std")]
 impl FileGroup {
     pub(crate) fn reserve(&self, state: &mut SerState, is_data: bool) {
         if is_data {
@@ -137,3 +315,31 @@ impl FileGroup {
         self.redirection = state.get::<FileGroup>(self.redirection);
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_region_clamps_sections_to_the_table() {
+        // 2 data groups, 1 info group and 1 versioned group, followed by a group that was pushed
+        let regions = (0..5)
+            .map(|index| GroupRegion::of(index, 4, 2, 1, 1))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            regions,
+            [
+                GroupRegion::Data,
+                GroupRegion::Data,
+                GroupRegion::Info,
+                GroupRegion::Versioned,
+                GroupRegion::Data,
+            ]
+        );
+
+        // Counts that overflow or run past the rows that were read stop at the end of the table
+        assert_eq!(GroupRegion::of(3, 4, 2, u32::MAX, 1), GroupRegion::Info);
+        assert_eq!(GroupRegion::of(4, 4, 2, u32::MAX, 1), GroupRegion::Data);
+        assert_eq!(GroupRegion::of(1, 4, u32::MAX, 1, 1), GroupRegion::Data);
+    }
+}
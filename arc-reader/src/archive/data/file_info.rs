@@ -1,15 +1,20 @@
-use std::ops::Range;
+use core::ops::Range;
 
+#[cfg(feature = "std")]
 use crate::{
-    archive::resource::serialization::SerState, index::checked_range, BinaryRepr, Locale, Region,
+    archive::{containers::TableRef, resource::serialization::SerState, validate::check_exclusive},
+    index::Index,
+    FlaggedRow, ValidationError,
 };
+use crate::{index::checked_range, BinaryRepr, Locale, Region};
 
+#[cfg(feature = "std")]
 use super::{file_desc::FileDesc, file_entity::FileEntity, file_path::FilePath};
 
 bitflags::bitflags! {
     /// Flags that help loaders determine special behavior to apply to files
     #[repr(transparent)]
-    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     struct FileInfoFlags : u32 {
         /// This flag is mutually exclusive from [`Self::IS_GRAPHICS_ARCHIVE`]
         ///
@@ -50,7 +55,7 @@ bitflags::bitflags! {
 /// For filepaths that use shared data and are not the "source of truth" for the file, there will only be one of these
 /// and it is owned by the [`FilePackage`](super::file_package::FilePackage) that is used to load this file
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FileInfo {
     /// Points to the [`FilePath`](super::file_path::FilePath) that this info represents
     ///
@@ -72,9 +77,65 @@ pub struct FileInfo {
     flags: FileInfoFlags,
 }
 
-impl BinaryRepr for FileInfo {}
+unsafe impl BinaryRepr for FileInfo {}
 
 impl FileInfo {
+    /// Reports the flag invariants of this info that are violated, see [`Archive::validate`](crate::archive::Archive::validate)
+    #[cfg(feature = "std")]
+    pub(crate) fn validate_flags(&self, index: Index<Self>, errors: &mut Vec<ValidationError>) {
+        use FileInfoFlags as F;
+
+        check_exclusive(
+            FlaggedRow::FileInfo(index),
+            self.flags,
+            F::IS_LOCALIZED,
+            F::IS_REGIONAL,
+            errors,
+        );
+        check_exclusive(
+            FlaggedRow::FileInfo(index),
+            self.flags,
+            F::IS_REGULAR_FILE,
+            F::IS_GRAPHICS_ARCHIVE,
+            errors,
+        );
+    }
+
+    /// Creates a new, non-localized and non-regional, file info for a regular file
+    pub(crate) fn new(path: u32, entity: u32, desc: u32) -> Self {
+        Self {
+            path,
+            entity,
+            desc,
+            flags: FileInfoFlags::IS_REGULAR_FILE,
+        }
+    }
+
+    pub(crate) fn path_index(&self) -> u32 {
+        self.path
+    }
+
+    pub(crate) fn entity_index(&self) -> u32 {
+        self.entity
+    }
+
+    /// The raw bits of this info's flags, including any bits that this crate does not know about
+    ///
+    /// Unknown bits are never masked off, so they are written back out unchanged when the tables are serialized
+    pub fn raw_flags(&self) -> u32 {
+        self.flags.bits()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_raw_flags(&mut self, bits: u32) {
+        self.flags = FileInfoFlags::from_bits_retain(bits);
+    }
+
+    /// Checks if this info is for a file that is shared between packages
+    pub(crate) fn is_shared(&self) -> bool {
+        self.flags.contains(FileInfoFlags::IS_SHARED)
+    }
+
     pub(crate) fn descriptor_range(&self) -> Range<u32> {
         let count = if self.flags.intersects(FileInfoFlags::IS_LOCALIZED) {
             Locale::COUNT as u32 + 1
@@ -88,6 +149,33 @@ impl FileInfo {
     }
 }
 
+#[cfg(feature = "std")]
+impl TableRef<'_, FileInfo> {
+    /// The [`FilePath`] that this info represents
+    pub fn path(&self) -> TableRef<'_, FilePath> {
+        self.archive()
+            .get_file_path(self.path)
+            .expect("file info should point to a valid file path")
+    }
+
+    /// The [`FileEntity`] that points to the source of truth for this file
+    pub fn entity(&self) -> TableRef<'_, FileEntity> {
+        self.archive()
+            .get_file_entity(self.entity)
+            .expect("file info should point to a valid file entity")
+    }
+
+    /// The first [`FileDesc`] of this info
+    ///
+    /// For localized and regional files, this is the descriptor for the first locale/region
+    pub fn desc(&self) -> TableRef<'_, FileDesc> {
+        self.archive()
+            .get_file_desc(self.desc)
+            .expect("file info should point to a valid file desc")
+    }
+}
+
+#[cfg(feature = "std")]
 impl FileInfo {
     pub(crate) fn reserve(&self, state: &mut SerState) {
         state.reserve_range::<FileDesc>(self.desc, self.descriptor_range().count() as u32);
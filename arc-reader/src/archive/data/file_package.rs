@@ -1,14 +1,25 @@
-use std::ops::Range;
+use core::ops::Range;
 
+#[cfg(feature = "std")]
 use hash40::Hash40;
 
+#[cfg(feature = "std")]
+use crate::{
+    archive::{
+        containers::TableRef,
+        resource::serialization::SerState,
+        validate::{check_exclusive, check_requires},
+    },
+    index::{Index, INVALID_INDEX},
+    FlaggedRow, ValidationError,
+};
 use crate::{
-    archive::{containers::TableRef, resource::serialization::SerState},
     hash::{Hash, HashWithData},
-    index::{checked_range, INVALID_INDEX},
+    index::checked_range,
     BinaryRepr, Locale, Region,
 };
 
+#[cfg(feature = "std")]
 use super::{
     file_group::{FileGroup, FileInfoGroupRef},
     file_info::FileInfo,
@@ -17,7 +28,7 @@ use super::{
 bitflags::bitflags! {
     /// Flags containing information about how to interpret and/or load a package
     #[repr(transparent)]
-    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     struct FilePackageFlags : u32 {
         /// Indicates that this package points to [`LOCALE_COUNT + 1`](crate::Locale)
         /// consecutive [`FileGroups`](super::file_group::FileGroup). One for each locale and an invalid one
@@ -75,7 +86,7 @@ bitflags::bitflags! {
 /// have a child `fighter/mario/c03/camera`, which would be a symlink to `fighter/mario/cmn/camera`. This
 /// "symlink form" can also be regional/localized, although that is much more rare.
 #[repr(C)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FilePackage {
     /// Full path hash and [`FileGroup`](super::file_group::FileGroup) index. The group that this index
     /// points to contains [`FileData`](super::file_data::FileData)
@@ -113,7 +124,7 @@ pub struct FilePackage {
     flags: FilePackageFlags,
 }
 
-impl BinaryRepr for FilePackage {}
+unsafe impl BinaryRepr for FilePackage {}
 
 /// Transparent represnetation of a [`HashWithData`]
 ///
@@ -123,12 +134,17 @@ impl BinaryRepr for FilePackage {}
 /// It is considered a logical error if the index is self-recursive and a package points to itself,
 /// as this will cause infinite recursion and loading in the resource systems.
 #[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FilePackageChild(HashWithData);
 
-impl BinaryRepr for FilePackageChild {}
+unsafe impl BinaryRepr for FilePackageChild {}
 
 impl FilePackageChild {
+    #[cfg(feature = "std")]
+    pub fn path(&self) -> Hash40 {
+        self.0.hash40()
+    }
+
     pub(crate) fn inner(&self) -> &HashWithData {
         &self.0
     }
@@ -138,6 +154,7 @@ impl FilePackageChild {
     }
 }
 
+#[cfg(feature = "std")]
 impl FilePackageChild {
     pub(crate) fn reinternalize(&mut self, state: &SerState) {
         self.0.set_data(state.get::<FilePackage>(self.0.data()))
@@ -145,6 +162,52 @@ impl FilePackageChild {
 }
 
 impl FilePackage {
+    /// Creates a plain package whose data is stored in the group at `data_group`, with no children
+    ///
+    /// The name, parent and lifetime hashes are left empty
+    #[cfg(feature = "std")]
+    pub(crate) fn new(path: Hash40, data_group: u32, info_start: u32, info_count: u32) -> Self {
+        Self {
+            path_and_group: HashWithData::new(path, data_group),
+            name: Hash::new(Hash40(0)),
+            parent: Hash::new(Hash40(0)),
+            lifetime: Hash::new(Hash40(0)),
+            info_start,
+            info_count,
+            child_start: INVALID_INDEX,
+            child_count: 0,
+            flags: FilePackageFlags::empty(),
+        }
+    }
+
+    /// Reports the flag invariants of this package that are violated, see [`Archive::validate`](crate::archive::Archive::validate)
+    #[cfg(feature = "std")]
+    pub(crate) fn validate_flags(&self, index: Index<Self>, errors: &mut Vec<ValidationError>) {
+        use FilePackageFlags as F;
+
+        check_exclusive(
+            FlaggedRow::FilePackage(index),
+            self.flags,
+            F::IS_LOCALIZED,
+            F::IS_REGIONAL,
+            errors,
+        );
+        check_requires(
+            FlaggedRow::FilePackage(index),
+            self.flags,
+            F::IS_SYM_LINK,
+            F::HAS_SUB_PACKAGE,
+            errors,
+        );
+        check_requires(
+            FlaggedRow::FilePackage(index),
+            self.flags,
+            F::SYM_LINK_IS_REGIONAL,
+            F::HAS_SUB_PACKAGE | F::IS_SYM_LINK,
+            errors,
+        );
+    }
+
     pub(crate) fn info_range(&self) -> Range<u32> {
         checked_range(self.info_start, self.info_count)
     }
@@ -178,16 +241,46 @@ impl FilePackage {
         self.path_and_group.set_data(u32::from(index));
     }
 
+    /// The raw bits of this package's flags, including any bits that this crate does not know about
+    ///
+    /// Unknown bits are never masked off, so they are written back out unchanged when the tables are serialized
+    pub fn raw_flags(&self) -> u32 {
+        self.flags.bits()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_raw_flags(&mut self, bits: u32) {
+        self.flags = FilePackageFlags::from_bits_retain(bits);
+    }
+
+    #[cfg(feature = "std")]
     pub fn path(&self) -> Hash40 {
         self.path_and_group.hash40()
     }
+
+    #[cfg(feature = "std")]
+    pub fn name(&self) -> Hash40 {
+        self.name.hash40()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn parent(&self) -> Hash40 {
+        self.parent.hash40()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn lifetime(&self) -> Hash40 {
+        self.lifetime.hash40()
+    }
 }
 
+#[cfg(feature = "std")]
 pub enum SubPackageRef<'a> {
     FileGroup(FileInfoGroupRef<'a>),
     SymLink(TableRef<'a, FilePackage>),
 }
 
+#[cfg(feature = "std")]
 impl TableRef<'_, FilePackage> {
     pub fn get_sym_link(&self) -> Option<TableRef<'_, FilePackage>> {
         if !self
@@ -213,6 +306,26 @@ impl TableRef<'_, FilePackage> {
         Some(sym_link)
     }
 
+    /// Iterates over the [`FilePackageChild`] rows of this package, yielding the path of each child
+    /// along with the package that it points to
+    ///
+    /// For example, `fighter/mario/c03` can have a child `fighter/mario/c03/camera` that points to
+    /// `fighter/mario/cmn/camera`.
+    pub fn children(&self) -> impl Iterator<Item = (Hash40, TableRef<'_, FilePackage>)> + '_ {
+        let archive = self.archive();
+
+        self.child_package_range().map(move |index| {
+            let child = archive
+                .get_file_package_child(index)
+                .expect("package should point to valid package children");
+            let package = archive
+                .get_file_package(child.inner().data())
+                .expect("package child should point to a valid file package");
+
+            (child.path(), package)
+        })
+    }
+
     pub fn data_group(&self) -> TableRef<'_, FileGroup> {
         self.archive()
             .get_file_group(self.path_and_group.data())
@@ -244,6 +357,7 @@ impl TableRef<'_, FilePackage> {
     }
 }
 
+#[cfg(feature = "std")]
 impl FilePackage {
     pub(crate) fn reserve(&self, state: &mut SerState) {
         state.reserve_range::<FilePackageChild>(self.child_start, self.child_count);
@@ -255,13 +369,14 @@ impl FilePackage {
     }
 
     pub(crate) fn reinternalize(&mut self, state: &SerState) {
-        self.child_start = if self.child_count == 0 {
+        // Empty ranges are cleared when serializing, but left in place when only moving rows around
+        self.child_start = if self.child_count == 0 && !state.is_moving() {
             INVALID_INDEX
         } else {
             state.get::<FilePackageChild>(self.child_start)
         };
 
-        self.info_start = if self.info_count == 0 {
+        self.info_start = if self.info_count == 0 && !state.is_moving() {
             INVALID_INDEX
         } else {
             state.get::<FileInfo>(self.info_start)
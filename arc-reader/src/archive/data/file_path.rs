@@ -1,12 +1,14 @@
+#[cfg(feature = "std")]
 use hash40::Hash40;
 
+#[cfg(feature = "std")]
+use crate::{archive::resource::serialization::SerState, index::INVALID_INDEX};
 use crate::{
-    archive::resource::serialization::SerState,
     hash::{Hash, HashWithData},
-    index::INVALID_INDEX,
     BinaryRepr,
 };
 
+#[cfg(feature = "std")]
 use super::file_entity::FileEntity;
 
 /// Represents a single file in the archive
@@ -19,7 +21,7 @@ use super::file_entity::FileEntity;
 /// the old file data from previous versions. They reset this version information in the archive over time, so some times
 /// the archive would actually shrink in size.
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FilePath {
     /// The path of this file, and the [`FileEntity`](super::file_entity::FileEntity) that points to the "source of truth" for this
     /// file's data
@@ -37,10 +39,53 @@ pub struct FilePath {
 }
 
 impl FilePath {
+    /// Creates a new file path with no version history
+    ///
+    /// The parent, file name and extension hashes are left empty
+    #[cfg(feature = "std")]
+    pub(crate) fn new(path: Hash40, entity: u32) -> Self {
+        Self {
+            path_and_entity: HashWithData::new(path, entity),
+            ext_and_version: HashWithData::new(Hash40(0), INVALID_INDEX),
+            parent: Hash::new(Hash40(0)),
+            file_name: Hash::new(Hash40(0)),
+        }
+    }
+
+    /// The hash of the full path of this file, e.g. `fighter/mario/model/body/c00/model.nuanmb`
+    #[cfg(feature = "std")]
     pub fn path(&self) -> Hash40 {
         self.path_and_entity.hash40()
     }
 
+    /// The hash of the extension of this file, without the leading `.`, e.g. `nuanmb`
+    #[cfg(feature = "std")]
+    pub fn extension(&self) -> Hash40 {
+        self.ext_and_version.hash40()
+    }
+
+    /// The hash of the folder containing this file, e.g. `fighter/mario/model/body/c00`
+    #[cfg(feature = "std")]
+    pub fn parent(&self) -> Hash40 {
+        self.parent.hash40()
+    }
+
+    /// The hash of the name of this file, including the extension, e.g. `model.nuanmb`
+    #[cfg(feature = "std")]
+    pub fn file_name(&self) -> Hash40 {
+        self.file_name.hash40()
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn set_path(&mut self, path: Hash40) {
+        self.path_and_entity.set_hash40(path);
+    }
+
+    #[cfg(all(test, feature = "std"))]
+    pub(crate) fn set_extension(&mut self, extension: Hash40) {
+        self.ext_and_version.set_hash40(extension);
+    }
+
     pub(crate) fn file_entity_index(&self) -> u32 {
         self.path_and_entity.data()
     }
@@ -50,14 +95,19 @@ impl FilePath {
     }
 }
 
-impl BinaryRepr for FilePath {}
+unsafe impl BinaryRepr for FilePath {}
 
+#[cfg(feature = "std")]
 impl FilePath {
-    pub(crate) fn reinternalize(&mut self, state: &SerState) {
+    /// Updates the entity index to its reserved position, and clears the version index
+    /// unless `keep_versions` is set
+    pub(crate) fn reinternalize(&mut self, state: &SerState, keep_versions: bool) {
         let index = self.path_and_entity.data();
         let index = state.get::<FileEntity>(index);
         self.path_and_entity.set_data(u32::from(index));
 
-        self.ext_and_version.set_data(INVALID_INDEX);
+        if !keep_versions {
+            self.ext_and_version.set_data(INVALID_INDEX);
+        }
     }
 }
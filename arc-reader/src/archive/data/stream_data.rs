@@ -7,13 +7,42 @@ use crate::BinaryRepr;
 /// file contents are provided as file paths and offsets into the resource streaming utilities provided
 /// by the game. The data **must** be uncompressed and it is not loaded by the game
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct StreamData {
     /// The size of the file, in bytes
-    size: u64,
+    ///
+    /// This is split like [`FileGroup::archive_offset`](super::file_group::FileGroup) so that the
+    /// table only needs a 0x4 alignment, the tables before it don't always end on a 0x8 boundary
+    size: [u32; 2],
 
-    /// The offset of the first byte of file data in the archive
-    offset: u64,
+    /// The offset of the first byte of file data in the archive, split like the size
+    offset: [u32; 2],
 }
 
-impl BinaryRepr for StreamData {}
+unsafe impl BinaryRepr for StreamData {}
+
+impl StreamData {
+    pub(crate) fn new(offset: u64, size: u64) -> Self {
+        let mut data = Self {
+            size: [0; 2],
+            offset: [0; 2],
+        };
+        data.set_location(offset, size);
+        data
+    }
+
+    /// The size of the file, in bytes
+    pub fn size(&self) -> u64 {
+        ((self.size[1] as u64) << 32) | self.size[0] as u64
+    }
+
+    /// The offset of the first byte of file data, from the start of the archive
+    pub fn offset(&self) -> u64 {
+        ((self.offset[1] as u64) << 32) | self.offset[0] as u64
+    }
+
+    pub(crate) fn set_location(&mut self, offset: u64, size: u64) {
+        self.offset = [offset as u32, (offset >> 32) as u32];
+        self.size = [size as u32, (size >> 32) as u32];
+    }
+}
@@ -1,5 +1,8 @@
-use crate::{archive::resource::serialization::SerState, BinaryRepr};
+#[cfg(feature = "std")]
+use crate::archive::{containers::TableRef, resource::serialization::SerState};
+use crate::BinaryRepr;
 
+#[cfg(feature = "std")]
 use super::stream_data::StreamData;
 
 /// Simple index redirection used by [`StreamPath`](super::stream_path::StreamPath) to locate
@@ -7,16 +10,20 @@ use super::stream_data::StreamData;
 ///
 /// There can be multiple of these for each path, depending on if the path is regional or localized.
 #[repr(C)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct StreamDesc {
     /// Index into the [`StreamData`](super::stream_data::StreamData) table for this file's
     /// streamable file data
     stream_data: u32,
 }
 
-impl BinaryRepr for StreamDesc {}
+unsafe impl BinaryRepr for StreamDesc {}
 
 impl StreamDesc {
+    pub(crate) fn new(stream_data: u32) -> Self {
+        Self { stream_data }
+    }
+
     pub(crate) fn stream_data_index(&self) -> u32 {
         self.stream_data
     }
@@ -26,6 +33,17 @@ impl StreamDesc {
     }
 }
 
+#[cfg(feature = "std")]
+impl TableRef<'_, StreamDesc> {
+    /// The [`StreamData`] that this descriptor points to
+    pub fn stream_data(&self) -> TableRef<'_, StreamData> {
+        self.archive()
+            .get_stream_data(self.stream_data)
+            .expect("stream desc should point to valid stream data")
+    }
+}
+
+#[cfg(feature = "std")]
 impl StreamDesc {
     pub(crate) fn reserve(&self, state: &mut SerState) {
         state.try_reserve::<StreamData>(self.stream_data);
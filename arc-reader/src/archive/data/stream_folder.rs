@@ -1,10 +1,16 @@
-use std::ops::Range;
+use core::ops::Range;
 
-use crate::{
-    archive::resource::serialization::SerState, hash::HashWithData, index::checked_range,
-    BinaryRepr,
+#[cfg(feature = "std")]
+use hash40::Hash40;
+
+#[cfg(feature = "std")]
+use crate::archive::{
+    containers::{TableRef, TableSliceRef},
+    resource::serialization::SerState,
 };
+use crate::{hash::HashWithData, index::checked_range, BinaryRepr};
 
+#[cfg(feature = "std")]
 use super::stream_path::StreamPath;
 
 /// Represents a collection of stream file paths, all prefixed by the same folder name
@@ -12,7 +18,7 @@ use super::stream_path::StreamPath;
 /// Unlike [`FilePackage`](super::file_package::FilePackage), this type is very simple. It is
 /// just a named slice of [`StreamPath`](super::stream_path::StreamPath).
 #[repr(C)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct StreamFolder {
     /// The hash part of this value is the name of the folder, without the `stream:`
     /// prefix, and the data part is the number of [`StreamPath`](super::stream_path::StreamPath)
@@ -23,9 +29,38 @@ pub struct StreamFolder {
     child_start_index: u32,
 }
 
-impl BinaryRepr for StreamFolder {}
+unsafe impl BinaryRepr for StreamFolder {}
 
 impl StreamFolder {
+    /// Creates a folder that owns `child_count` [`StreamPath`] starting at `child_start`
+    #[cfg(feature = "std")]
+    pub(crate) fn new(name: Hash40, child_start: u32, child_count: u32) -> Self {
+        Self {
+            name_and_child_count: HashWithData::new(name, child_count),
+            child_start_index: child_start,
+        }
+    }
+
+    /// The hash of this folder's name, as it is stored in the table
+    ///
+    /// A prefix can't be stripped from a hash, so this is the hash of whatever name the folder was
+    /// created with. See [`Self::label`] for the name without the `stream:` prefix.
+    #[cfg(feature = "std")]
+    pub fn name(&self) -> Hash40 {
+        self.name_and_child_count.hash40()
+    }
+
+    /// The label of this folder's name in the global [`Hash40::label_map`], without the `stream:`
+    /// prefix or a leading `/`
+    ///
+    /// Returns [`None`] if the name has no label.
+    #[cfg(feature = "std")]
+    pub fn label(&self) -> Option<String> {
+        let label = Hash40::label_map().lock().unwrap().label_of(self.name())?;
+        let name = label.strip_prefix("stream:").unwrap_or(&label);
+        Some(name.trim_start_matches('/').to_string())
+    }
+
     pub(crate) fn stream_path_range(&self) -> Range<u32> {
         checked_range(self.child_start_index, self.name_and_child_count.data())
     }
@@ -35,6 +70,18 @@ impl StreamFolder {
     }
 }
 
+#[cfg(feature = "std")]
+impl TableRef<'_, StreamFolder> {
+    /// The stream paths contained in this folder
+    pub fn paths(&self) -> TableSliceRef<'_, StreamPath> {
+        let range = self.stream_path_range();
+        self.archive()
+            .get_stream_path_slice(range.start, range.end - range.start)
+            .expect("stream folder should point to valid stream paths")
+    }
+}
+
+#[cfg(feature = "std")]
 impl StreamFolder {
     pub(crate) fn reserve(&self, state: &mut SerState) {
         state.reserve_range::<StreamPath>(self.child_start_index, self.name_and_child_count.data());
@@ -44,3 +91,25 @@ impl StreamFolder {
         self.child_start_index = state.get::<StreamPath>(self.child_start_index);
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_strips_the_stream_prefix() {
+        Hash40::label_map()
+            .lock()
+            .unwrap()
+            .add_labels(["stream:/sound/bgm/folder_label_test".to_string()]);
+
+        let folder = StreamFolder::new(Hash40::new("stream:/sound/bgm/folder_label_test"), 0, 0);
+        assert_eq!(
+            folder.label().as_deref(),
+            Some("sound/bgm/folder_label_test")
+        );
+
+        let unlabeled = StreamFolder::new(Hash40::new("sound/bgm/folder_without_label"), 0, 0);
+        assert_eq!(unlabeled.label(), None);
+    }
+}
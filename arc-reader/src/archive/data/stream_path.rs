@@ -1,18 +1,23 @@
-use std::ops::Range;
+use core::ops::Range;
 
+#[cfg(feature = "std")]
 use hash40::Hash40;
 
+#[cfg(feature = "std")]
 use crate::{
-    archive::resource::serialization::SerState, hash::HashWithData, index::checked_range,
-    BinaryRepr, Locale, Region,
+    archive::{containers::TableRef, resource::serialization::SerState, validate::check_exclusive},
+    index::Index,
+    FlaggedRow, ValidationError,
 };
+use crate::{hash::HashWithData, index::checked_range, BinaryRepr, Locale, Region};
 
+#[cfg(feature = "std")]
 use super::stream_desc::StreamDesc;
 
 bitflags::bitflags! {
     /// Regional flags for stream file data
     #[repr(transparent)]
-    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     struct StreamFileFlags : u32 {
         /// This flag is mutually exclusive from [`Self::IS_REGIONAL`], and indicates that
         /// this [`StreamPath`] points to [`LOCALE_COUNT`](crate::Locale) [`StreamEntity`](super::stream_entity::StreamEntity),
@@ -35,7 +40,7 @@ bitflags::bitflags! {
 /// to multiple [`StreamDesc`](super::stream_desc::StreamDesc), depending on whether it is regional
 /// or localized (see [`StreamFileFlags`] for more info).
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct StreamPath {
     /// The path of this file, including the `stream:` prefix, and the start index of the [`StreamDesc`](super::stream_desc::StreamDesc)
     /// that this points to.
@@ -46,10 +51,51 @@ pub struct StreamPath {
 }
 
 impl StreamPath {
+    /// Creates a path that is neither localized nor regional, with its single [`StreamDesc`] at `desc`
+    #[cfg(feature = "std")]
+    pub(crate) fn new(path: Hash40, desc: u32) -> Self {
+        Self {
+            path_and_desc: HashWithData::new(path, desc),
+            flags: StreamFileFlags::empty(),
+        }
+    }
+
+    /// Reports the flag invariants of this path that are violated, see [`Archive::validate`](crate::archive::Archive::validate)
+    #[cfg(feature = "std")]
+    pub(crate) fn validate_flags(&self, index: Index<Self>, errors: &mut Vec<ValidationError>) {
+        use StreamFileFlags as F;
+
+        check_exclusive(
+            FlaggedRow::StreamPath(index),
+            self.flags,
+            F::IS_LOCALIZED,
+            F::IS_REGIONAL,
+            errors,
+        );
+    }
+
+    /// The raw bits of this path's flags, including any bits that this crate does not know about
+    ///
+    /// Unknown bits are never masked off, so they are written back out unchanged when the tables are serialized
+    pub fn raw_flags(&self) -> u32 {
+        self.flags.bits()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_raw_flags(&mut self, bits: u32) {
+        self.flags = StreamFileFlags::from_bits_retain(bits);
+    }
+
+    #[cfg(feature = "std")]
     pub fn path(&self) -> Hash40 {
         self.path_and_desc.hash40()
     }
 
+    /// The range of [`StreamDesc`] that this path points to
+    ///
+    /// Unlike [`FileInfo::descriptor_range`](super::file_info::FileInfo), localized and regional stream
+    /// paths have exactly [`Locale::COUNT`] or [`Region::COUNT`] descriptors. There is no trailing
+    /// descriptor for the invalid locale/region, so there is no `+ 1` here.
     pub(crate) fn descriptor_range(&self) -> Range<u32> {
         let count = if self.flags.contains(StreamFileFlags::IS_LOCALIZED) {
             Locale::COUNT as u32
@@ -67,8 +113,70 @@ impl StreamPath {
     }
 }
 
-impl BinaryRepr for StreamPath {}
+unsafe impl BinaryRepr for StreamPath {}
+
+#[cfg(feature = "std")]
+impl TableRef<'_, StreamPath> {
+    /// Selects the [`StreamDesc`] to use for `locale`
+    ///
+    /// Localized paths index their descriptors by the ordinal of the locale. Unlike file info, there
+    /// are exactly [`Locale::COUNT`] descriptors with no trailing one for the invalid locale. Paths that
+    /// are neither localized nor regional have a single descriptor, which is returned for every locale.
+    ///
+    /// Returns [`None`] for [`Locale::Invalid`], and for regional paths since those are selected
+    /// by region instead of locale with [`Self::descriptor_for_region`]
+    pub fn descriptor(&self, locale: Locale) -> Option<TableRef<'_, StreamDesc>> {
+        let range = self.descriptor_range();
+
+        let index = if self.flags.contains(StreamFileFlags::IS_LOCALIZED) {
+            if locale == Locale::Invalid {
+                return None;
+            }
+
+            range.start + locale as u32
+        } else if self.flags.contains(StreamFileFlags::IS_REGIONAL) {
+            return None;
+        } else {
+            range.start
+        };
+
+        range
+            .contains(&index)
+            .then(|| self.archive().get_stream_desc(index))
+            .flatten()
+    }
+
+    /// Selects the [`StreamDesc`] to use for `region`
+    ///
+    /// Regional paths index their descriptors by the ordinal of the region. Like localized paths, there
+    /// are exactly [`Region::COUNT`] descriptors with no trailing one for the invalid region. Paths that
+    /// are neither localized nor regional have a single descriptor, which is returned for every region.
+    ///
+    /// Returns [`None`] for [`Region::Invalid`], and for localized paths since those are selected
+    /// by locale instead of region with [`Self::descriptor`]
+    pub fn descriptor_for_region(&self, region: Region) -> Option<TableRef<'_, StreamDesc>> {
+        let range = self.descriptor_range();
+
+        let index = if self.flags.contains(StreamFileFlags::IS_REGIONAL) {
+            if region == Region::Invalid {
+                return None;
+            }
+
+            range.start + region as u32
+        } else if self.flags.contains(StreamFileFlags::IS_LOCALIZED) {
+            return None;
+        } else {
+            range.start
+        };
+
+        range
+            .contains(&index)
+            .then(|| self.archive().get_stream_desc(index))
+            .flatten()
+    }
+}
 
+#[cfg(feature = "std")]
 impl StreamPath {
     pub(crate) fn reserve(&self, state: &mut SerState) {
         state.reserve_range::<StreamDesc>(
@@ -0,0 +1,219 @@
+use std::{
+    collections::HashSet,
+    io::{Read, Seek},
+    path::{Component, Path, PathBuf},
+};
+
+use hash40::Hash40;
+
+use super::Archive;
+#[cfg(feature = "rayon")]
+use super::SharedArchiveReader;
+
+/// The name of the folder that files without a known label are extracted to
+const UNKNOWN_FOLDER: &str = ".unknown";
+
+/// Summary of an [`Archive::extract_all`] run
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ExtractReport {
+    /// The number of files that were written
+    pub extracted: usize,
+
+    /// The number of file paths that were skipped because they share their [`FileEntity`](super::file_entity::FileEntity)
+    /// with a file path that was already handled
+    pub skipped_shared: usize,
+
+    /// The number of files whose data could not be read out of the archive
+    pub failed: usize,
+}
+
+/// Progress of an [`Archive::extract_all`] run, reported after every file path
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExtractProgress {
+    /// The number of file paths that have been handled, whether they were extracted, skipped or failed
+    pub files_done: usize,
+
+    /// The total number of file paths in the archive
+    pub files_total: usize,
+
+    /// The number of decompressed bytes that have been written
+    pub bytes_done: u64,
+}
+
+impl Archive {
+    /// Extracts every file in the archive to `out_dir`, recreating the directory structure of each path
+    ///
+    /// When `resolve_labels` is set, paths are resolved using the labels loaded into
+    /// [`Hash40::label_map`]. Files that can't be resolved, or all files when `resolve_labels` is not set,
+    /// are written to `.unknown/<hash>`, as are labels that are absolute or contain anything other than
+    /// plain folder and file names, such as `..`.
+    ///
+    /// File paths that share their entity with a file path that was already handled point to the same
+    /// file, so only the first of them is extracted.
+    ///
+    /// Failing to read a file's data is counted in the report and does not stop the extraction,
+    /// failing to write to `out_dir` returns an error.
+    ///
+    /// `on_progress` is called once for every file path, after it has been handled.
+    pub fn extract_all<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        out_dir: &Path,
+        resolve_labels: bool,
+        mut on_progress: impl FnMut(ExtractProgress),
+    ) -> std::io::Result<ExtractReport> {
+        let mut report = ExtractReport::default();
+        let mut written = HashSet::new();
+        let mut progress = ExtractProgress {
+            files_done: 0,
+            files_total: self.resource.file_path.len(),
+            bytes_done: 0,
+        };
+
+        for (index, file_path) in self.resource.file_path.iter() {
+            let path = file_path.path();
+            if !written.insert(file_path.file_entity_index()) {
+                report.skipped_shared += 1;
+            } else {
+                match self.extract_file(reader, index, path, out_dir, resolve_labels)? {
+                    Some(len) => {
+                        report.extracted += 1;
+                        progress.bytes_done += len;
+                    }
+                    None => report.failed += 1,
+                }
+            }
+
+            progress.files_done += 1;
+            on_progress(progress);
+        }
+
+        Ok(report)
+    }
+
+    /// Extracts every file in the archive to `out_dir` like [`Self::extract_all`], but reads, decompresses
+    /// and writes the files on the rayon thread pool
+    ///
+    /// The archive at `archive_path` is opened once as a [`SharedArchiveReader`], and every worker reads
+    /// from it through its own cursor. This trades memory for speed, since each worker holds a reader buffer
+    /// and the decompressed data of the file it is working on.
+    #[cfg(feature = "rayon")]
+    pub fn extract_all_parallel(
+        &self,
+        archive_path: &Path,
+        out_dir: &Path,
+        resolve_labels: bool,
+    ) -> std::io::Result<ExtractReport> {
+        use rayon::prelude::*;
+        use std::io::BufReader;
+
+        let archive = SharedArchiveReader::open(archive_path)?;
+
+        let mut written = HashSet::new();
+        let mut paths = Vec::with_capacity(self.resource.file_path.len());
+        let mut skipped_shared = 0;
+
+        for (index, file_path) in self.resource.file_path.iter() {
+            if written.insert(file_path.file_entity_index()) {
+                paths.push((index, file_path.path()));
+            } else {
+                skipped_shared += 1;
+            }
+        }
+
+        let report = paths
+            .par_iter()
+            .map_init(
+                || BufReader::new(archive.cursor()),
+                |reader, (index, path)| {
+                    self.extract_file(reader, *index, *path, out_dir, resolve_labels)
+                },
+            )
+            .try_fold(ExtractReport::default, |mut report, result| {
+                match result? {
+                    Some(_) => report.extracted += 1,
+                    None => report.failed += 1,
+                }
+
+                Ok::<_, std::io::Error>(report)
+            })
+            .try_reduce(ExtractReport::default, |a, b| {
+                Ok(ExtractReport {
+                    extracted: a.extracted + b.extracted,
+                    skipped_shared: a.skipped_shared + b.skipped_shared,
+                    failed: a.failed + b.failed,
+                })
+            })?;
+
+        Ok(ExtractReport {
+            skipped_shared,
+            ..report
+        })
+    }
+
+    /// Reads the file at `index` in the file path table and writes it under `out_dir`, returning the
+    /// number of bytes written
+    ///
+    /// Returns [`None`] if the data could not be read out of the archive
+    fn extract_file<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        index: u32,
+        path: Hash40,
+        out_dir: &Path,
+        resolve_labels: bool,
+    ) -> std::io::Result<Option<u64>> {
+        let Ok(data) = self.read_file_by_index(reader, index) else {
+            return Ok(None);
+        };
+
+        let out_path = out_dir.join(output_path(path, resolve_labels));
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(out_path, &data)?;
+
+        Ok(Some(data.len() as u64))
+    }
+}
+
+fn output_path(path: Hash40, resolve_labels: bool) -> PathBuf {
+    let label = resolve_labels
+        .then(|| Hash40::label_map().lock().unwrap().label_of(path))
+        .flatten();
+
+    match label.as_deref().and_then(label_path) {
+        Some(label) => label,
+        None => Path::new(UNKNOWN_FOLDER).join(format!("{:#012x}", path.0)),
+    }
+}
+
+/// Converts a label to a path relative to the output folder, unless it is empty, absolute or has
+/// any component other than a plain folder or file name
+fn label_path(label: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(label);
+    let mut components = path.components().peekable();
+
+    (components.peek().is_some()
+        && components.all(|component| matches!(component, Component::Normal(_))))
+    .then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_path_rejects_escaping_labels() {
+        assert_eq!(
+            label_path("fighter/mario/model.nutexb"),
+            Some(PathBuf::from("fighter/mario/model.nutexb"))
+        );
+        assert_eq!(label_path(""), None);
+        assert_eq!(label_path("../../etc/passwd"), None);
+        assert_eq!(label_path("fighter/../../x"), None);
+        assert_eq!(label_path("/etc/passwd"), None);
+        assert_eq!(label_path("./fighter"), None);
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    io::{self, Write},
+};
+
+use hash40::Hash40;
+
+use crate::IntoHash;
+
+use super::{file_package::SubPackageRef, Archive};
+
+/// Formats a hash as a quoted DOT string, using its label if there is one
+fn quoted(hash: Hash40) -> String {
+    format!("{:?}", hash.to_string())
+}
+
+impl Archive {
+    /// Writes the packages that can be reached from the package at `root` as a GraphViz DOT graph
+    ///
+    /// Every package is a node, labeled with its path. Packages are connected to their
+    /// [`FilePackageChild`](super::file_package::FilePackageChild) packages with solid edges, to the package that
+    /// they are a sym link to with dashed edges, and to the file info group that they use as a
+    /// sub package with dotted edges. File info groups are drawn as boxes.
+    ///
+    /// Returns [`io::ErrorKind::NotFound`] if there is no package at `root`.
+    pub fn package_graph_dot<W: Write>(&self, mut out: W, root: impl IntoHash) -> io::Result<()> {
+        let root = root.into_hash();
+        let root = self.lookup_file_package(root).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Package {root} does not exist"),
+            )
+        })?;
+
+        writeln!(out, "digraph packages {{")?;
+
+        let mut visited = HashSet::from([root.index()]);
+        let mut queue = VecDeque::from([root.index()]);
+
+        while let Some(index) = queue.pop_front() {
+            let package = self
+                .get_file_package(index)
+                .expect("queued package should exist");
+
+            writeln!(out, "    p{index} [label={}];", quoted(package.path()))?;
+
+            let children = package
+                .child_package_range()
+                .filter_map(|child| self.get_file_package_child(child))
+                .map(|child| child.inner().data())
+                .filter(|child| self.get_file_package(*child).is_some());
+
+            for child in children {
+                writeln!(out, "    p{index} -> p{child};")?;
+                if visited.insert(child) {
+                    queue.push_back(child);
+                }
+            }
+
+            match package.sub_package() {
+                Some(SubPackageRef::SymLink(sym_link)) => {
+                    let target = sym_link.index();
+                    writeln!(out, "    p{index} -> p{target} [style=dashed];")?;
+                    if visited.insert(target) {
+                        queue.push_back(target);
+                    }
+                }
+                Some(SubPackageRef::FileGroup(group)) => {
+                    let group = group.index();
+                    writeln!(
+                        out,
+                        "    g{group} [shape=box, label=\"group {group:#x}\"];\n    p{index} -> g{group} [style=dotted];"
+                    )?;
+                }
+                None => {}
+            }
+        }
+
+        writeln!(out, "}}")
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    io::{Read, Seek},
+    ops::Range,
+};
+
+use crate::BinaryRepr;
+
+use super::{
+    resource::{ResourceLayout, ResourceTables},
+    Archive, ArchiveMetadata, ArchiveReadOptions, FileData, FileDesc, FileEntity, FileGroup,
+    FileInfo, FilePackage, FilePackageChild, FilePath, StreamData, StreamDesc, StreamFolder,
+    StreamPath,
+};
+
+/// A read-only archive that only casts its resource tables when they are accessed
+///
+/// Reading a lazy archive decompresses the resource section and computes where each table is,
+/// but does not build any of the tables or lookups. This is useful for tools that only need
+/// to look at one or two tables. Use [`Archive::read`] to get the full, editable archive, or
+/// [`Self::into_archive`] to upgrade a lazy archive after the fact.
+pub struct LazyArchive {
+    metadata: ArchiveMetadata,
+    raw_data: Box<[u8]>,
+    layout: ResourceLayout,
+}
+
+macro_rules! decl_lazy_access {
+    ($($name:ident: $field:ident => $t:ty),*) => {
+        $(
+            #[doc = concat!("Gets every row of the [`", stringify!($t), "`] table")]
+            pub fn $name(&self) -> &[$t] {
+                self.cast(self.layout.$field.clone())
+            }
+        )*
+    }
+}
+
+impl LazyArchive {
+    /// Reads the metadata and decompresses the resource section of an archive, without building its tables
+    ///
+    /// Fails like [`Archive::read`] if `reader` is not reading an archive or the resource section is corrupt
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, std::io::Error> {
+        Self::read_with(reader, &ArchiveReadOptions::default())
+    }
+
+    /// Reads a lazy archive, following `options`
+    ///
+    /// When the stream tables are not parsed, their accessors return empty slices
+    pub fn read_with<R: Read + Seek>(
+        reader: &mut R,
+        options: &ArchiveReadOptions,
+    ) -> Result<Self, std::io::Error> {
+        let (metadata, raw_data) =
+            ArchiveMetadata::read_with_resource(reader, options.decompressor.as_ref())?;
+        let mut layout = ResourceLayout::parse(&raw_data, options.validate_counts)?;
+        if !options.parse_streams {
+            layout.skip_streams();
+        }
+
+        Ok(Self {
+            metadata,
+            raw_data,
+            layout,
+        })
+    }
+
+    fn cast<T: BinaryRepr>(&self, range: Range<usize>) -> &[T] {
+        // SAFETY: The layout has checked that the range is contained within the resource section,
+        //      and the resource section contains valid table data
+        unsafe { T::cast_slice(&self.raw_data[range]) }
+    }
+
+    decl_lazy_access! {
+        stream_folders: stream_folder => StreamFolder,
+        stream_paths: stream_path => StreamPath,
+        stream_descs: stream_desc => StreamDesc,
+        stream_data: stream_data => StreamData,
+        file_paths: file_path => FilePath,
+        file_entities: file_entity => FileEntity,
+        file_packages: file_package => FilePackage,
+        file_groups: file_group => FileGroup,
+        file_package_children: file_package_child => FilePackageChild,
+        file_infos: file_info => FileInfo,
+        file_descs: file_desc => FileDesc,
+        file_data: file_data => FileData
+    }
+
+    /// Builds every table and lookup, turning this into a full [`Archive`]
+    pub fn into_archive(self) -> Result<Archive, std::io::Error> {
+        Ok(Archive {
+            metadata: self.metadata,
+            resource: ResourceTables::from_bytes(self.raw_data)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::archive::ArchiveBuilder;
+
+    fn built() -> Vec<u8> {
+        ArchiveBuilder::new()
+            .add_package_file(
+                "fighter/mario/c00",
+                "fighter/mario/model/body/c00/model.numdlb",
+                b"model".as_slice(),
+            )
+            .add_file("ui/param/database/ui_chara_db.prc", b"param".as_slice())
+            .add_stream_file(
+                "sound/bgm",
+                "stream:/sound/bgm/bgm_crs2_01_menu.nus3audio",
+                b"music".as_slice(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn lazy_tables_match_the_archive() {
+        let bytes = built();
+        let lazy = LazyArchive::read(&mut Cursor::new(bytes.as_slice())).unwrap();
+        let archive = Archive::read(&mut Cursor::new(bytes.as_slice())).unwrap();
+        let resource = &archive.resource;
+
+        assert_eq!(lazy.stream_folders(), resource.stream_folder.fixed());
+        assert_eq!(lazy.stream_paths(), resource.stream_path.fixed());
+        assert_eq!(lazy.stream_descs(), resource.stream_desc.fixed());
+        assert_eq!(lazy.stream_data(), resource.stream_data.fixed());
+        assert_eq!(lazy.file_paths(), resource.file_path.fixed());
+        assert_eq!(lazy.file_entities(), resource.file_entity.fixed());
+        assert_eq!(lazy.file_packages(), resource.file_package.fixed());
+        assert_eq!(lazy.file_groups(), resource.file_group.fixed());
+        assert_eq!(
+            lazy.file_package_children(),
+            resource.file_package_child.fixed()
+        );
+        assert_eq!(lazy.file_infos(), resource.file_info.fixed());
+        assert_eq!(lazy.file_descs(), resource.file_desc.fixed());
+        assert_eq!(lazy.file_data(), resource.file_data.fixed());
+        assert!(!lazy.stream_paths().is_empty());
+    }
+
+    #[test]
+    fn lazy_read_with_skips_streams() {
+        let bytes = built();
+        let lazy = LazyArchive::read_with(
+            &mut Cursor::new(bytes),
+            ArchiveReadOptions::new().parse_streams(false),
+        )
+        .unwrap();
+
+        assert!(lazy.stream_folders().is_empty());
+        assert!(lazy.stream_paths().is_empty());
+        assert!(!lazy.file_paths().is_empty());
+    }
+}
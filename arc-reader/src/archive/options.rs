@@ -0,0 +1,52 @@
+use crate::{Decompressor, ZstdDecompressor};
+
+/// Options that control how [`Archive::read_with`](super::Archive::read_with) reads an archive
+///
+/// The defaults match [`Archive::read`](super::Archive::read): the locale and region counts are validated,
+/// the stream tables are parsed and the resource section is decompressed with [`ZstdDecompressor`].
+pub struct ArchiveReadOptions {
+    pub(crate) validate_counts: bool,
+    pub(crate) parse_streams: bool,
+    pub(crate) decompressor: Box<dyn Decompressor>,
+}
+
+impl Default for ArchiveReadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArchiveReadOptions {
+    /// Creates the default options
+    pub fn new() -> Self {
+        Self {
+            validate_counts: true,
+            parse_streams: true,
+            decompressor: Box::new(ZstdDecompressor),
+        }
+    }
+
+    /// Sets whether the locale and region counts in the resource table header are checked
+    ///
+    /// These counts are the only sanity check that the resource section was found at the right location,
+    /// so this should only be disabled for archives that are known to have been edited.
+    pub fn validate_counts(&mut self, validate_counts: bool) -> &mut Self {
+        self.validate_counts = validate_counts;
+        self
+    }
+
+    /// Sets whether the stream folders, paths, descriptors and data are read
+    ///
+    /// When they are not read, the stream tables of the archive are empty. Serializing an archive that was
+    /// read this way drops all of its stream files.
+    pub fn parse_streams(&mut self, parse_streams: bool) -> &mut Self {
+        self.parse_streams = parse_streams;
+        self
+    }
+
+    /// Sets the decompressor used for the resource section
+    pub fn decompressor(&mut self, decompressor: impl Decompressor + 'static) -> &mut Self {
+        self.decompressor = Box::new(decompressor);
+        self
+    }
+}
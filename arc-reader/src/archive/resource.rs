@@ -1,19 +1,22 @@
+use std::{collections::HashSet, ops::Range};
+
 use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
 use hash40::Hash40;
 
 use crate::{
     archive::{containers::Bucket, file_package::SubPackageRef, resource::serialization::SerState},
     hash::HashWithData,
+    index::{checked_range, Index, INVALID_INDEX},
     io::WriteBinExt,
-    BinaryRepr,
+    BinaryRepr, DuplicatePath, Locale, Region, RoundtripMismatch,
 };
 
 use super::{
     containers::{BucketLookup, IndexLookup, Table},
-    file_data::FileData,
-    file_desc::FileDesc,
+    file_data::{FileData, FileDataSpec},
+    file_desc::{FileDesc, LoadMethod},
     file_entity::FileEntity,
-    file_group::FileGroup,
+    file_group::{FileGroup, GroupRegion},
     file_info::FileInfo,
     file_package::{FilePackage, FilePackageChild},
     file_path::FilePath,
@@ -21,13 +24,13 @@ use super::{
     stream_desc::StreamDesc,
     stream_folder::StreamFolder,
     stream_path::StreamPath,
-    Archive,
+    Archive, ArchiveReadOptions,
 };
 
 pub(crate) mod serialization;
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct ResourceTableHeader {
     resource_data_size: u32,
     file_path_count: u32,
@@ -69,26 +72,181 @@ pub struct ResourceTableHeader {
     stream_data_count: u32,
 }
 
-impl BinaryRepr for ResourceTableHeader {}
-
-pub(crate) struct ResourceTables {
-    pub raw_data: Box<[u8]>,
-    pub stream_folder: Table<StreamFolder>,
-    pub stream_path_lookup: IndexLookup,
-    pub stream_path: Table<StreamPath>,
-    pub stream_desc: Table<StreamDesc>,
-    pub stream_data: Table<StreamData>,
-
-    pub file_path_lookup: BucketLookup,
-    pub file_path: Table<FilePath>,
-    pub file_entity: Table<FileEntity>,
-    pub file_package_lookup: IndexLookup,
-    pub file_package: Table<FilePackage>,
-    pub file_group: Table<FileGroup>,
-    pub file_package_child: Table<FilePackageChild>,
-    pub file_info: Table<FileInfo>,
-    pub file_desc: Table<FileDesc>,
-    pub file_data: Table<FileData>,
+unsafe impl BinaryRepr for ResourceTableHeader {}
+
+/// A best-guess decoding of one row of [`ResourceTableHeader::local_region_hash_to_region`]
+///
+/// The meaning of these rows has not been confirmed, this decoding assumes that:
+/// - the rows are ordered by [`Locale`] ordinal
+/// - the first two values are a hash with data, laid out the same way as every other hash in
+///   the resource tables (the CRC, followed by the length in the low 8 bits and 24 bits of data)
+/// - the last value is the ordinal of the [`Region`] that the locale falls back to
+///
+/// The raw values are kept in [`Self::raw`] so that the decoding can be checked.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LocaleRegionEntry {
+    /// The locale that this row is for
+    pub locale: Locale,
+
+    /// The hash stored in the first two values, presumably the name of the locale or region
+    pub hash: Hash40,
+
+    /// The 24 bits of data stored alongside [`Self::hash`]
+    pub hash_data: u32,
+
+    /// The region in the last value, or [`None`] if it is not a valid region ordinal
+    pub region: Option<Region>,
+
+    /// The values of the row as they are stored in the header
+    pub raw: [u32; 3],
+}
+
+impl LocaleRegionEntry {
+    fn new(locale: Locale, raw: [u32; 3]) -> Self {
+        Self {
+            locale,
+            hash: Hash40(((raw[1] as u64 & 0xFF) << 32) | raw[0] as u64),
+            hash_data: raw[1] >> 8,
+            region: Region::ALL.get(raw[2] as usize).copied(),
+            raw,
+        }
+    }
+}
+
+macro_rules! decl_header_counts {
+    ($($(#[$meta:meta])* $name:ident),*) => {
+        $(
+            $(#[$meta])*
+            pub fn $name(&self) -> u32 {
+                self.$name
+            }
+        )*
+    }
+}
+
+impl ResourceTableHeader {
+    decl_header_counts! {
+        /// The size of the decompressed resource section, in bytes
+        resource_data_size,
+        file_path_count,
+        file_entity_count,
+        file_package_count,
+        /// The number of [`FileGroup`] that belong to packages
+        file_data_group_count,
+        file_package_child_count,
+        /// The number of [`FileInfo`] that belong to packages
+        file_package_info_count,
+        /// The number of [`FileDesc`] that belong to packages
+        file_package_desc_count,
+        /// The number of [`FileData`] that belong to packages
+        file_package_data_count,
+        /// The number of [`FileGroup`] that contain shared [`FileInfo`]
+        file_info_group_count,
+        /// The number of [`FileInfo`], [`FileDesc`] and [`FileData`] that belong to shared groups
+        file_group_info_count,
+        versioned_file_group_count,
+        versioned_file_count,
+        versioned_file_info_count,
+        versioned_file_desc_count,
+        versioned_file_data_count,
+        stream_folder_count,
+        stream_path_count,
+        stream_desc_count,
+        stream_data_count
+    }
+
+    /// The number of locales, this is always 14
+    pub fn locale_count(&self) -> u8 {
+        self.locale_count
+    }
+
+    /// The number of regions, this is always 5
+    pub fn region_count(&self) -> u8 {
+        self.region_count
+    }
+
+    /// The version of the archive, as `(major, minor, patch)`
+    pub fn version(&self) -> (u16, u8, u8) {
+        (self.version_major, self.version_minor, self.version_patch)
+    }
+
+    /// The table used by the resource service to pick a region for each locale, one row per
+    /// locale. The meaning of the individual values is not known yet.
+    pub fn local_region_hash_to_region(&self) -> &[[u32; 3]; 14] {
+        &self.local_region_hash_to_region
+    }
+
+    /// Decodes [`Self::local_region_hash_to_region`], see [`LocaleRegionEntry`] for the assumptions made
+    pub fn locale_region_map(&self) -> [LocaleRegionEntry; Locale::COUNT] {
+        std::array::from_fn(|index| {
+            LocaleRegionEntry::new(Locale::ALL[index], self.local_region_hash_to_region[index])
+        })
+    }
+}
+
+/// The decompressed resource tables of an archive
+///
+/// The tables are zero-copy views into `raw_data`, any rows that are added after reading
+/// the archive are stored in the dynamic regions of each table.
+pub struct ResourceTables {
+    pub(crate) raw_data: Box<[u8]>,
+    /// Hash of `raw_data` when the tables were read, before any of the fixed rows were edited
+    #[cfg(feature = "xxhash")]
+    pub(crate) raw_hash: u64,
+    pub(crate) header: ResourceTableHeader,
+    pub(crate) stream_folder: Table<StreamFolder>,
+    pub(crate) stream_path_lookup: IndexLookup,
+    pub(crate) stream_path: Table<StreamPath>,
+    pub(crate) stream_desc: Table<StreamDesc>,
+    pub(crate) stream_data: Table<StreamData>,
+
+    pub(crate) file_path_lookup: BucketLookup,
+    pub(crate) file_path: Table<FilePath>,
+    pub(crate) file_entity: Table<FileEntity>,
+    pub(crate) file_package_lookup: IndexLookup,
+    pub(crate) file_package: Table<FilePackage>,
+    pub(crate) file_group: Table<FileGroup>,
+    pub(crate) file_package_child: Table<FilePackageChild>,
+    pub(crate) file_info: Table<FileInfo>,
+    pub(crate) file_desc: Table<FileDesc>,
+    pub(crate) file_data: Table<FileData>,
+}
+
+impl Clone for ResourceTables {
+    /// Copies the tables along with the bytes they were read from, so that the copy can be edited
+    /// without affecting the original
+    ///
+    /// The copied tables point into the copied bytes at the same offsets as the original tables.
+    fn clone(&self) -> Self {
+        let mut raw_data = self.raw_data.clone();
+        let (from, to) = (&*self.raw_data, &mut *raw_data);
+
+        // SAFETY: Every table points into `self.raw_data` and no two tables share a range, `raw_data` is a copy
+        //      of it that is moved into the returned tables
+        unsafe {
+            Self {
+                header: self.header,
+                stream_folder: self.stream_folder.rebased(from, to),
+                stream_path_lookup: self.stream_path_lookup.rebased(from, to),
+                stream_path: self.stream_path.rebased(from, to),
+                stream_desc: self.stream_desc.rebased(from, to),
+                stream_data: self.stream_data.rebased(from, to),
+                file_path_lookup: self.file_path_lookup.rebased(from, to),
+                file_path: self.file_path.rebased(from, to),
+                file_entity: self.file_entity.rebased(from, to),
+                file_package_lookup: self.file_package_lookup.rebased(from, to),
+                file_package: self.file_package.rebased(from, to),
+                file_group: self.file_group.rebased(from, to),
+                file_package_child: self.file_package_child.rebased(from, to),
+                file_info: self.file_info.rebased(from, to),
+                file_desc: self.file_desc.rebased(from, to),
+                file_data: self.file_data.rebased(from, to),
+                raw_data,
+                #[cfg(feature = "xxhash")]
+                raw_hash: self.raw_hash,
+            }
+        }
+    }
 }
 
 fn write_table<T: BinaryRepr + Copy>(
@@ -120,6 +278,184 @@ fn write_lookup<T: 'static>(
     Ok(())
 }
 
+/// The number of rows removed from each table by [`ResourceTables::compact`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CompactionReport {
+    pub file_entity: usize,
+    pub file_info: usize,
+    pub file_desc: usize,
+    pub file_data: usize,
+}
+
+impl CompactionReport {
+    /// The total number of rows that were removed
+    pub fn total(&self) -> usize {
+        self.file_entity + self.file_info + self.file_desc + self.file_data
+    }
+}
+
+/// The size of each table and lookup when written by [`ResourceTables::quick_serialize`], in bytes
+///
+/// Lookups are sized by their own number of entries, which is not always the number of rows in the
+/// table that they index. The file path lookup includes its bucket list and the two counts before it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TableSizes {
+    pub header: usize,
+    pub stream_folder: usize,
+    pub stream_path_lookup: usize,
+    pub stream_path: usize,
+    pub stream_desc: usize,
+    pub stream_data: usize,
+    pub file_path_lookup: usize,
+    pub file_path: usize,
+    pub file_entity: usize,
+    pub file_package_lookup: usize,
+    pub file_package: usize,
+    pub file_group: usize,
+    pub file_package_child: usize,
+    pub file_info: usize,
+    pub file_desc: usize,
+    pub file_data: usize,
+}
+
+impl TableSizes {
+    /// The total size of the tables, which is the length of the output of [`ResourceTables::quick_serialize`]
+    pub fn total(&self) -> usize {
+        self.header
+            + self.stream_folder
+            + self.stream_path_lookup
+            + self.stream_path
+            + self.stream_desc
+            + self.stream_data
+            + self.file_path_lookup
+            + self.file_path
+            + self.file_entity
+            + self.file_package_lookup
+            + self.file_package
+            + self.file_group
+            + self.file_package_child
+            + self.file_info
+            + self.file_desc
+            + self.file_data
+    }
+}
+
+/// The rows of each table that have been reached while walking the tables, indexed by row
+pub(crate) struct Reachable {
+    pub file_entity: Vec<bool>,
+    pub file_info: Vec<bool>,
+    pub file_desc: Vec<bool>,
+    pub file_data: Vec<bool>,
+}
+
+impl Reachable {
+    /// Marks a row, returning whether it was not marked before
+    fn mark(marks: &mut [bool], index: u32) -> bool {
+        marks
+            .get_mut(index as usize)
+            .is_some_and(|mark| !std::mem::replace(mark, true))
+    }
+
+    /// Marks an entity, along with its info
+    fn mark_entity(&mut self, tables: &ResourceTables, index: u32) {
+        if !Self::mark(&mut self.file_entity, index) {
+            return;
+        }
+
+        if let Some(entity) = tables.file_entity.get(index) {
+            self.mark_info(tables, entity.info_index());
+        }
+    }
+
+    /// Marks an info, along with its entity, its descriptors, their data and anything that
+    /// their load methods point to
+    fn mark_info(&mut self, tables: &ResourceTables, index: u32) {
+        if !Self::mark(&mut self.file_info, index) {
+            return;
+        }
+
+        let Some(info) = tables.file_info.get(index) else {
+            return;
+        };
+
+        self.mark_entity(tables, info.entity_index());
+
+        for desc in info.descriptor_range() {
+            if !Self::mark(&mut self.file_desc, desc) {
+                continue;
+            }
+
+            let Some(desc) = tables.file_desc.get(desc) else {
+                continue;
+            };
+
+            Self::mark(&mut self.file_data, desc.file_data_index());
+
+            match desc.load_method() {
+                LoadMethod::Unowned(entity) | LoadMethod::SharedButOwned(entity) => {
+                    self.mark_entity(tables, entity)
+                }
+                LoadMethod::PackageSkip(info) => self.mark_info(tables, info),
+                _ => {}
+            }
+        }
+    }
+
+    /// Lists the rows that were never marked
+    pub fn unmarked<T>(marks: &[bool]) -> Vec<Index<T>> {
+        marks
+            .iter()
+            .enumerate()
+            .filter(|(_, mark)| !**mark)
+            .map(|(index, _)| Index::new(index as u32))
+            .collect()
+    }
+}
+
+/// Reserves the rows `0..len` of `T` in order, skipping the rows in `removed`
+fn reserve_kept<T: 'static>(state: &mut SerState, len: u32, removed: Range<u32>) {
+    for index in (0..len).filter(|index| !removed.contains(index)) {
+        state.reserve::<T>(index);
+    }
+}
+
+/// Reserves the rows `0..len` of `T` in order, skipping the rows that are not marked in `kept`
+fn reserve_marked<T: 'static>(state: &mut SerState, len: u32, kept: &[bool]) {
+    for index in (0..len).filter(|index| kept.get(*index as usize) == Some(&true)) {
+        state.reserve::<T>(index);
+    }
+}
+
+/// Reserves the rows of `T` in `order`
+fn reserve_order<T: 'static>(state: &mut SerState, order: &[u32]) {
+    for index in order {
+        state.reserve::<T>(*index);
+    }
+}
+
+/// The new order of the rows of a table, see [`ResourceTables::place_dynamic_rows`]
+struct PlacedOrder {
+    order: Vec<u32>,
+
+    /// The number of rows that were moved into the first section
+    placed: u32,
+}
+
+/// Orders the `len` rows of a table so that the rows past the `counted` rows of its sections follow
+/// the first section, which ends at `first_end`
+fn placed_order(len: u32, (first_end, counted): (u32, u32)) -> PlacedOrder {
+    let counted = counted.min(len);
+    let first_end = first_end.min(counted);
+
+    PlacedOrder {
+        order: (0..first_end)
+            .chain(counted..len)
+            .chain(first_end..counted)
+            .collect(),
+        placed: len - counted,
+    }
+}
+
 fn quick_serialize_table<T: BinaryRepr + Copy>(table: &Table<T>, buffer: &mut Vec<u8>) {
     buffer.extend_from_slice(T::cast_slice_bytes(table.fixed()));
     buffer.extend_from_slice(T::cast_slice_bytes(table.dynamic()));
@@ -131,32 +467,977 @@ fn quick_serialize_lookup(lookup: impl Iterator<Item = (Hash40, u32)>, buffer: &
     }
 }
 
+fn compare_table<T: PartialEq>(
+    table: &'static str,
+    expected: &Table<T>,
+    found: &Table<T>,
+) -> Result<(), RoundtripMismatch> {
+    if expected.len() != found.len() {
+        return Err(RoundtripMismatch::Length {
+            table,
+            expected: expected.len(),
+            found: found.len(),
+        });
+    }
+
+    match expected
+        .iter()
+        .zip(found.iter())
+        .position(|((_, a), (_, b))| a != b)
+    {
+        Some(index) => Err(RoundtripMismatch::Row { table, index }),
+        None => Ok(()),
+    }
+}
+
+fn compare_lookup(
+    table: &'static str,
+    expected: impl Iterator<Item = (Hash40, u32)>,
+    found: impl Iterator<Item = (Hash40, u32)>,
+) -> Result<(), RoundtripMismatch> {
+    let expected = expected.collect::<Vec<_>>();
+    let found = found.collect::<Vec<_>>();
+
+    if expected.len() != found.len() {
+        return Err(RoundtripMismatch::Length {
+            table,
+            expected: expected.len(),
+            found: found.len(),
+        });
+    }
+
+    match expected.iter().zip(found.iter()).position(|(a, b)| a != b) {
+        Some(index) => Err(RoundtripMismatch::Row { table, index }),
+        None => Ok(()),
+    }
+}
+
+/// The byte ranges of every table in a decompressed resource section
+///
+/// This is computed from the [`ResourceTableHeader`] and the file path lookup's own header, and every
+/// range is checked to be contained within the section.
+#[derive(Debug, Clone)]
+pub(crate) struct ResourceLayout {
+    pub header: ResourceTableHeader,
+    pub stream_folder: Range<usize>,
+    pub stream_path_lookup: Range<usize>,
+    pub stream_path: Range<usize>,
+    pub stream_desc: Range<usize>,
+    pub stream_data: Range<usize>,
+    pub file_path_buckets: Range<usize>,
+    pub file_path_lookup: Range<usize>,
+    pub file_path: Range<usize>,
+    pub file_entity: Range<usize>,
+    pub file_package_lookup: Range<usize>,
+    pub file_package: Range<usize>,
+    pub file_group: Range<usize>,
+    pub file_package_child: Range<usize>,
+    pub file_info: Range<usize>,
+    pub file_desc: Range<usize>,
+    pub file_data: Range<usize>,
+}
+
+impl ResourceLayout {
+    /// Computes where each table is in the decompressed resource section, only checking the locale
+    /// and region counts if `validate_counts` is set
+    pub fn parse(bytes: &[u8], validate_counts: bool) -> std::io::Result<Self> {
+        fn truncated(what: &str) -> std::io::Error {
+            std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("Resource section is too small to contain the {what}"),
+            )
+        }
+
+        fn total(what: &str, counts: &[u32]) -> std::io::Result<u32> {
+            counts
+                .iter()
+                .try_fold(0u32, |total, count| total.checked_add(*count))
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("The number of {what} does not fit in 32 bits"),
+                    )
+                })
+        }
+
+        if bytes.len() < std::mem::size_of::<ResourceTableHeader>() {
+            return Err(truncated("header"));
+        }
+
+        // SAFETY: We read the resource table and then perform some checks on
+        //      data that should be consistent if we have read it from the right location
+        let header = unsafe {
+            let header = *ResourceTableHeader::cast(bytes);
+            if validate_counts && header.locale_count != 14 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Expected 14 locales, found {:#x}", header.locale_count),
+                ));
+            }
+
+            if validate_counts && header.region_count != 5 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Expected 5 regions, found {:#x}", header.region_count),
+                ));
+            }
+
+            header
+        };
+
+        let mut cursor = std::mem::size_of::<ResourceTableHeader>();
+
+        let mut next = |what: &str, size: usize, count: u32| {
+            let start = cursor;
+            let end = (count as usize)
+                .checked_mul(size)
+                .and_then(|len| start.checked_add(len))
+                .filter(|end| *end <= bytes.len())
+                .ok_or_else(|| truncated(what))?;
+
+            cursor = end;
+            Ok::<_, std::io::Error>(start..end)
+        };
+
+        macro_rules! next {
+            ($what:literal, $t:ty, $count:expr) => {
+                next($what, std::mem::size_of::<$t>(), $count)?
+            };
+        }
+
+        let stream_folder = next!("stream folders", StreamFolder, header.stream_folder_count);
+        let stream_path_lookup =
+            next!("stream path lookup", HashWithData, header.stream_path_count);
+        let stream_path = next!("stream paths", StreamPath, header.stream_path_count);
+        let stream_desc = next!("stream descs", StreamDesc, header.stream_desc_count);
+        let stream_data = next!("stream data", StreamData, header.stream_data_count);
+
+        let lookup_header = next!("file path lookup header", u32, 2);
+        let file_path_lookup_count = LittleEndian::read_u32(&bytes[lookup_header.start..]);
+        let file_path_bucket_count = LittleEndian::read_u32(&bytes[lookup_header.start + 4..]);
+        if file_path_bucket_count == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The file path lookup has no buckets",
+            ));
+        }
+
+        let file_path_buckets = next!("file path buckets", Bucket, file_path_bucket_count);
+        let file_path_lookup = next!("file path lookup", HashWithData, file_path_lookup_count);
+        let file_path = next!("file paths", FilePath, header.file_path_count);
+        let file_entity = next!("file entities", FileEntity, header.file_entity_count);
+        let file_package_lookup = next!(
+            "file package lookup",
+            HashWithData,
+            header.file_package_count
+        );
+        let file_package = next!("file packages", FilePackage, header.file_package_count);
+        let file_group = next!(
+            "file groups",
+            FileGroup,
+            total(
+                "file groups",
+                &[
+                    header.file_info_group_count,
+                    header.file_data_group_count,
+                    header.versioned_file_group_count,
+                ],
+            )?
+        );
+        let file_package_child = next!(
+            "file package children",
+            FilePackageChild,
+            header.file_package_child_count
+        );
+        let file_info = next!(
+            "file infos",
+            FileInfo,
+            total(
+                "file infos",
+                &[
+                    header.file_package_info_count,
+                    header.file_group_info_count,
+                    header.versioned_file_info_count,
+                ],
+            )?
+        );
+        let file_desc = next!(
+            "file descs",
+            FileDesc,
+            total(
+                "file descs",
+                &[
+                    header.file_package_desc_count,
+                    header.file_group_info_count,
+                    header.versioned_file_desc_count,
+                ],
+            )?
+        );
+        let file_data = next!(
+            "file data",
+            FileData,
+            total(
+                "file data",
+                &[
+                    header.file_package_data_count,
+                    header.file_group_info_count,
+                    header.versioned_file_data_count,
+                ],
+            )?
+        );
+
+        Ok(Self {
+            header,
+            stream_folder,
+            stream_path_lookup,
+            stream_path,
+            stream_desc,
+            stream_data,
+            file_path_buckets,
+            file_path_lookup,
+            file_path,
+            file_entity,
+            file_package_lookup,
+            file_package,
+            file_group,
+            file_package_child,
+            file_info,
+            file_desc,
+            file_data,
+        })
+    }
+
+    /// Empties the ranges of the stream tables, so that they are read as if the archive had no stream files
+    pub fn skip_streams(&mut self) {
+        for range in [
+            &mut self.stream_folder,
+            &mut self.stream_path_lookup,
+            &mut self.stream_path,
+            &mut self.stream_desc,
+            &mut self.stream_data,
+        ] {
+            range.end = range.start;
+        }
+    }
+}
+
 impl ResourceTables {
-    pub fn quick_serialize(&self) -> Vec<u8> {
-        let mut buffer: Vec<u8> = Vec::with_capacity(
-            self.file_data.len() * std::mem::size_of::<FileData>()
-                + self.file_desc.len() * std::mem::size_of::<FileDesc>()
-                + self.file_info.len() * std::mem::size_of::<FileInfo>()
-                + self.file_entity.len() * std::mem::size_of::<FileEntity>()
-                + self.file_path.len() * std::mem::size_of::<FilePath>()
-                + self.file_package.len() * std::mem::size_of::<FilePackage>()
-                + self.file_package_child.len() * std::mem::size_of::<FilePackageChild>()
-                + self.file_group.len() * std::mem::size_of::<FileGroup>()
-                + self.file_path.len() * std::mem::size_of::<HashWithData>()
-                + 0x400 * std::mem::size_of::<Bucket>()
-                + self.file_package.len() * std::mem::size_of::<HashWithData>()
-                + self.stream_folder.len() * std::mem::size_of::<StreamFolder>()
-                + self.stream_path.len() * std::mem::size_of::<StreamPath>()
-                + self.stream_path.len() * std::mem::size_of::<HashWithData>()
-                + self.stream_desc.len() * std::mem::size_of::<StreamDesc>()
-                + self.stream_data.len() * std::mem::size_of::<StreamData>(),
+    /// Creates resource tables that do not contain any rows, with `bucket_count` empty buckets
+    /// in the file path lookup
+    ///
+    /// # Panics
+    /// Panics if `bucket_count` is `0`
+    pub(crate) fn empty(bucket_count: u32) -> Self {
+        Self::from_bytes(Self::empty_bytes(bucket_count).into_boxed_slice())
+            .expect("empty resource tables should be valid")
+    }
+
+    /// The bytes of resource tables that do not contain any rows, see [`Self::empty`]
+    fn empty_bytes(bucket_count: u32) -> Vec<u8> {
+        let header = ResourceTableHeader {
+            locale_count: 14,
+            region_count: 5,
+            ..Default::default()
+        };
+
+        let mut bytes = Vec::new();
+        let _ = bytes.write_binary(&header);
+        let _ = bytes.write_u32::<LittleEndian>(0);
+        let _ = bytes.write_u32::<LittleEndian>(bucket_count);
+        bytes.resize(
+            bytes.len() + bucket_count as usize * std::mem::size_of::<Bucket>(),
+            0,
+        );
+
+        let size = bytes.len() as u32;
+        LittleEndian::write_u32(&mut bytes[..4], size);
+
+        bytes
+    }
+
+    /// The number of file packages, which is also the index of the first [`FileGroup`] of shared data
+    ///
+    /// Indices that can refer to either a package or a group (such as the owner of a [`FileEntity`])
+    /// refer to a group if they are at or past this boundary
+    pub(crate) fn package_len(&self) -> u32 {
+        self.file_package.len() as u32
+    }
+
+    /// Which section of the group table the group at `index` is in, see [`GroupRegion::of`]
+    pub(crate) fn group_region(&self, index: u32) -> GroupRegion {
+        GroupRegion::of(
+            index,
+            self.file_group.fixed_len() as u32,
+            self.header.file_data_group_count,
+            self.header.file_info_group_count,
+            self.header.versioned_file_group_count,
+        )
+    }
+
+    /// Appends a new file to the tables, returning the index of the new [`FilePath`]
+    ///
+    /// This pushes a [`FilePath`], [`FileEntity`], [`FileInfo`], [`FileDesc`] and [`FileData`]
+    /// to the dynamic regions of their tables, links them together and adds the path
+    /// to the file path lookup. The new entity is owned by the group that the data is stored in.
+    ///
+    /// This does not check if the path is already present in the archive, see [`Self::try_push_file`]
+    ///
+    /// # Panics
+    /// Panics if the `group` of `data` is not a [`GroupRegion::Data`] group in the table, or if its
+    /// `in_group_offset` is not a multiple of 4
+    pub fn push_file(&mut self, path: Hash40, data: FileDataSpec) -> Index<FilePath> {
+        assert!(
+            self.file_group.contains(data.group)
+                && self.group_region(data.group) == GroupRegion::Data,
+            "file data has to be stored in a data group, but group {:#x} is not one",
+            data.group
+        );
+        assert!(
+            data.in_group_offset.is_multiple_of(4),
+            "in-group offsets are stored divided by 4, but {:#x} is not a multiple of 4",
+            data.in_group_offset
+        );
+
+        let file_data = self.file_data.push(FileData::new(&data));
+        let desc = self.file_desc.push(FileDesc::new(
+            data.group,
+            file_data,
+            LoadMethod::Owned(INVALID_INDEX),
+        ));
+
+        let file_path = self.file_path.len() as u32;
+        let entity = self.file_entity.len() as u32;
+
+        let info = self.file_info.push(FileInfo::new(file_path, entity, desc));
+        self.file_entity.push(FileEntity::new(data.group, info));
+        self.file_path.push(FilePath::new(path, entity));
+        self.file_path_lookup.insert(path, file_path);
+
+        Index::new(file_path)
+    }
+
+    /// Appends a new file to the tables like [`Self::push_file`], unless the path is already present
+    /// in the file path lookup
+    ///
+    /// The lookup does not deduplicate hashes on its own, so pushing the same path twice leaves
+    /// two rows with the same hash and only one of them can be looked up.
+    pub fn try_push_file(
+        &mut self,
+        path: Hash40,
+        data: FileDataSpec,
+    ) -> Result<Index<FilePath>, DuplicatePath> {
+        if let Some(index) = self.file_path_lookup.get(path) {
+            return Err(DuplicatePath {
+                path,
+                index: Index::new(index),
+            });
+        }
+
+        Ok(self.push_file(path, data))
+    }
+
+    /// Appends a new package that owns `files`, returning the index of the new [`FilePackage`]
+    ///
+    /// The package gets a single data [`FileGroup`] that starts `archive_offset` bytes into the file data
+    /// section and is `size` bytes long. Every file is pushed like with [`Self::push_file`], except that its
+    /// entity is owned by the new package and its data is always stored in the new group, so the `group` of
+    /// each spec is ignored. The package has no children and no sub package.
+    ///
+    /// Since the number of packages is also the index of the first group that can own an entity, packages
+    /// should be pushed before any file whose entity is owned by a group
+    pub(crate) fn push_package(
+        &mut self,
+        path: Hash40,
+        archive_offset: u64,
+        size: u32,
+        files: &[(Hash40, FileDataSpec)],
+    ) -> Index<FilePackage> {
+        let package = self.package_len();
+        let group = self.file_group.push(FileGroup::new_data(
+            archive_offset,
+            size,
+            self.file_data.len() as u32,
+            files.len() as u32,
+        ));
+
+        let info_start = self.file_info.len() as u32;
+        for (path, data) in files {
+            let file_data = self.file_data.push(FileData::new(data));
+            let desc = self.file_desc.push(FileDesc::new(
+                group,
+                file_data,
+                LoadMethod::Owned(INVALID_INDEX),
+            ));
+
+            let file_path = self.file_path.len() as u32;
+            let entity = self.file_entity.len() as u32;
+
+            let info = self.file_info.push(FileInfo::new(file_path, entity, desc));
+            self.file_entity.push(FileEntity::new(package, info));
+            self.file_path.push(FilePath::new(*path, entity));
+            self.file_path_lookup.insert(*path, file_path);
+        }
+
+        self.file_package.push(FilePackage::new(
+            path,
+            group,
+            info_start,
+            files.len() as u32,
+        ));
+        self.file_package_lookup.insert(path, package);
+
+        Index::new(package)
+    }
+
+    /// Appends a new stream folder that owns a [`StreamPath`] for each of `files`, returning the index
+    /// of the new [`StreamFolder`]
+    ///
+    /// Each file is given as its path, the offset of its data from the start of the archive and the size
+    /// of its data. The paths are neither localized nor regional.
+    pub(crate) fn push_stream_folder(
+        &mut self,
+        name: Hash40,
+        files: &[(Hash40, u64, u64)],
+    ) -> Index<StreamFolder> {
+        let child_start = self.stream_path.len() as u32;
+        for (path, offset, size) in files {
+            let data = self.stream_data.push(StreamData::new(*offset, *size));
+            let desc = self.stream_desc.push(StreamDesc::new(data));
+            let stream_path = self.stream_path.push(StreamPath::new(*path, desc));
+            self.stream_path_lookup.insert(*path, stream_path);
+        }
+
+        Index::new(self.stream_folder.push(StreamFolder::new(
+            name,
+            child_start,
+            files.len() as u32,
+        )))
+    }
+
+    /// Updates every reference between the tables after the rows of `T` have been moved around
+    ///
+    /// `order` contains the previous index of each row of `T`, in their new order. Rows that are not
+    /// present in `order` are considered removed, and nothing else is allowed to reference them.
+    /// `package_len` is the number of packages before any rows were moved.
+    ///
+    /// Only the references to rows of `T` change, everything else (including the version history)
+    /// is left as it is
+    pub(crate) fn remap_rows<T: 'static>(&mut self, order: &[u32], package_len: u32) {
+        let mut state = SerState::moving();
+        reserve_order::<T>(&mut state, order);
+
+        self.reinternalize_rows(&state, package_len, true);
+    }
+
+    /// Updates every reference between the tables using the new row indices in `state`
+    ///
+    /// `package_len` is the number of packages before any rows were moved. References to previous
+    /// versions of files are cleared unless `keep_versions` is set.
+    fn reinternalize_rows(&mut self, state: &SerState, package_len: u32, keep_versions: bool) {
+        // Groups that contain file info redirect to themselves
+        let info_groups = self
+            .file_group
+            .iter()
+            .filter(|(index, group)| state.try_get::<FileGroup>(group.redirection) == Some(*index))
+            .map(|(index, _)| index)
+            .collect::<HashSet<_>>();
+
+        for (_, folder) in self.stream_folder.iter_mut() {
+            folder.reinternalize(state);
+        }
+
+        for (_, path) in self.stream_path.iter_mut() {
+            path.reinternalize(state);
+        }
+
+        for (_, desc) in self.stream_desc.iter_mut() {
+            desc.reinternalize(state);
+        }
+
+        for (_, path) in self.file_path.iter_mut() {
+            path.reinternalize(state, keep_versions);
+        }
+
+        for (_, entity) in self.file_entity.iter_mut() {
+            entity.reinternalize(state, package_len);
+        }
+
+        for (_, package) in self.file_package.iter_mut() {
+            package.reinternalize(state);
+        }
+
+        for (index, group) in self.file_group.iter_mut() {
+            if info_groups.contains(&index) {
+                group.reinternalize_info(state);
+            } else {
+                group.reinternalize_data(state, package_len);
+            }
+        }
+
+        for (_, child) in self.file_package_child.iter_mut() {
+            child.reinternalize(state);
+        }
+
+        for (_, info) in self.file_info.iter_mut() {
+            info.reinternalize(state);
+        }
+
+        for (_, desc) in self.file_desc.iter_mut() {
+            desc.reinternalize(state, keep_versions);
+        }
+
+        self.stream_path_lookup
+            .remap(|index| state.try_get::<StreamPath>(index));
+        self.file_path_lookup
+            .remap(|index| state.try_get::<FilePath>(index));
+        self.file_package_lookup
+            .remap(|index| state.try_get::<FilePackage>(index));
+    }
+
+    /// Removes the version history from the tables, returning the number of bytes that the
+    /// removed rows took up
+    ///
+    /// The versioned groups, infos, descriptors and data are removed, every reference to a previous
+    /// version of a file is cleared and the versioned counts in the header are reset to zero. This is
+    /// the same history that [`Self::into_bytes`] drops. The versioned section of each table is found
+    /// using the counts in the header, like [`Self::from_bytes`]. Rows pushed since the tables were
+    /// read are past every section that the header counts, so they are never treated as versions.
+    pub fn strip_versions(&mut self) -> usize {
+        let package_len = self.package_len();
+        let lens = (
+            self.file_group.len() as u32,
+            self.file_info.len() as u32,
+            self.file_desc.len() as u32,
+            self.file_data.len() as u32,
         );
 
+        let [groups, infos, descs, data] = self.versioned_sections();
+        let groups = self.file_group.remove_fixed_range(groups);
+        let infos = self.file_info.remove_fixed_range(infos);
+        let descs = self.file_desc.remove_fixed_range(descs);
+        let data = self.file_data.remove_fixed_range(data);
+
+        let mut state = SerState::new();
+        reserve_kept::<StreamFolder>(&mut state, self.stream_folder.len() as u32, 0..0);
+        reserve_kept::<StreamPath>(&mut state, self.stream_path.len() as u32, 0..0);
+        reserve_kept::<StreamDesc>(&mut state, self.stream_desc.len() as u32, 0..0);
+        reserve_kept::<StreamData>(&mut state, self.stream_data.len() as u32, 0..0);
+        reserve_kept::<FilePath>(&mut state, self.file_path.len() as u32, 0..0);
+        reserve_kept::<FileEntity>(&mut state, self.file_entity.len() as u32, 0..0);
+        reserve_kept::<FilePackage>(&mut state, self.file_package.len() as u32, 0..0);
+        reserve_kept::<FileGroup>(&mut state, lens.0, groups.clone());
+        reserve_kept::<FilePackageChild>(&mut state, self.file_package_child.len() as u32, 0..0);
+        reserve_kept::<FileInfo>(&mut state, lens.1, infos.clone());
+        reserve_kept::<FileDesc>(&mut state, lens.2, descs.clone());
+        reserve_kept::<FileData>(&mut state, lens.3, data.clone());
+
+        self.reinternalize_rows(&state, package_len, false);
+
+        self.header.versioned_file_group_count = 0;
+        self.header.versioned_file_count = 0;
+        self.header.versioned_file_info_count = 0;
+        self.header.versioned_file_desc_count = 0;
+        self.header.versioned_file_data_count = 0;
+
+        groups.len() * std::mem::size_of::<FileGroup>()
+            + infos.len() * std::mem::size_of::<FileInfo>()
+            + descs.len() * std::mem::size_of::<FileDesc>()
+            + data.len() * std::mem::size_of::<FileData>()
+    }
+
+    /// The versioned sections of the group, info, desc and data tables, found using the counts in
+    /// the header
+    ///
+    /// A section whose start does not fit in a `u32` is empty, like the ranges of [`checked_range`]
+    fn versioned_sections(&self) -> [Range<u32>; 4] {
+        let header = &self.header;
+        let section = |first: u32, second: u32, count: u32| match first.checked_add(second) {
+            Some(start) => checked_range(start, count),
+            None => 0..0,
+        };
+
+        [
+            section(
+                header.file_data_group_count,
+                header.file_info_group_count,
+                header.versioned_file_group_count,
+            ),
+            section(
+                header.file_package_info_count,
+                header.file_group_info_count,
+                header.versioned_file_info_count,
+            ),
+            section(
+                header.file_package_desc_count,
+                header.file_group_info_count,
+                header.versioned_file_desc_count,
+            ),
+            section(
+                header.file_package_data_count,
+                header.file_group_info_count,
+                header.versioned_file_data_count,
+            ),
+        ]
+    }
+
+    /// Reserves the rows in the versioned section of each table, in their original order
+    ///
+    /// The sections are found using the counts in the header, like [`Self::from_bytes`], so the rows
+    /// pushed since the tables were read are never reserved here
+    fn reserve_versions(&self, state: &mut SerState) {
+        let [groups, infos, descs, data] = self.versioned_sections();
+
+        for group in groups {
+            if self.file_group.contains(group) {
+                state.try_reserve::<FileGroup>(group);
+            }
+        }
+
+        for info in infos {
+            if self.file_info.contains(info) {
+                state.try_reserve::<FileInfo>(info);
+            }
+        }
+
+        for desc in descs {
+            if self.file_desc.contains(desc) {
+                state.try_reserve::<FileDesc>(desc);
+            }
+        }
+
+        for data in data {
+            if self.file_data.contains(data) {
+                state.try_reserve::<FileData>(data);
+            }
+        }
+    }
+
+    /// Walks the tables from every file path, package and group, marking the file entities, infos,
+    /// descriptors and data that are reached
+    pub(crate) fn reachable(&self) -> Reachable {
+        let mut reachable = Reachable {
+            file_entity: vec![false; self.file_entity.len()],
+            file_info: vec![false; self.file_info.len()],
+            file_desc: vec![false; self.file_desc.len()],
+            file_data: vec![false; self.file_data.len()],
+        };
+
+        for (_, path) in self.file_path.iter() {
+            reachable.mark_entity(self, path.file_entity_index());
+        }
+
+        for (_, package) in self.file_package.iter() {
+            for info in package.info_range() {
+                reachable.mark_info(self, info);
+            }
+        }
+
+        // Groups are never removed, so their children always stay reachable. This covers both the
+        // data groups of packages and the info groups that packages use as sub packages
+        for (index, group) in self.file_group.iter() {
+            if group.redirection_index() == index {
+                for info in group.child_range() {
+                    reachable.mark_info(self, info);
+                }
+            } else {
+                for data in group.child_range() {
+                    Reachable::mark(&mut reachable.file_data, data);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Removes the file entities, infos, descriptors and data that can not be reached, returning how
+    /// many rows were removed from each table
+    ///
+    /// Rows are reached by walking the tables:
+    /// - file paths reach their entity
+    /// - packages reach the infos in their info range
+    /// - groups reach their children, which are infos for groups that redirect to themselves and data otherwise
+    /// - entities reach their info
+    /// - infos reach their entity and descriptors
+    /// - descriptors reach their data, and the entity or info that their load method points to
+    ///
+    /// The rows in the group sections of the info, descriptor and data tables are always kept, since
+    /// the header has a single count for all three of them.
+    ///
+    /// Every reference to the rows that remain is updated and the counts in the header are recomputed
+    /// from the rows that were kept. Like [`Self::into_bytes`], this drops the version history of the
+    /// tables. It is removed with [`Self::strip_versions`] first, so the versioned rows are not
+    /// counted in the report.
+    pub fn compact(&mut self) -> CompactionReport {
+        self.strip_versions();
+
+        let mut reachable = self.reachable();
+        let package_len = self.package_len();
+        let [_, info, desc, data] = self.section_bounds();
+        let group_info_count = self.header.file_group_info_count;
+
+        let mut first_section_counts = [0u32; 3];
+        for ((marks, (first_end, _)), count) in [
+            &mut reachable.file_info,
+            &mut reachable.file_desc,
+            &mut reachable.file_data,
+        ]
+        .into_iter()
+        .zip([info, desc, data])
+        .zip(&mut first_section_counts)
+        {
+            for index in checked_range(first_end, group_info_count) {
+                Reachable::mark(marks, index);
+            }
+
+            *count = marks
+                .iter()
+                .take(first_end as usize)
+                .filter(|kept| **kept)
+                .count() as u32;
+        }
+
+        let lens = (
+            self.file_entity.len() as u32,
+            self.file_info.len() as u32,
+            self.file_desc.len() as u32,
+            self.file_data.len() as u32,
+        );
+
+        self.file_entity.retain_rows(&reachable.file_entity);
+        self.file_info.retain_rows(&reachable.file_info);
+        self.file_desc.retain_rows(&reachable.file_desc);
+        self.file_data.retain_rows(&reachable.file_data);
+
+        let mut state = SerState::new();
+        reserve_kept::<StreamFolder>(&mut state, self.stream_folder.len() as u32, 0..0);
+        reserve_kept::<StreamPath>(&mut state, self.stream_path.len() as u32, 0..0);
+        reserve_kept::<StreamDesc>(&mut state, self.stream_desc.len() as u32, 0..0);
+        reserve_kept::<StreamData>(&mut state, self.stream_data.len() as u32, 0..0);
+        reserve_kept::<FilePath>(&mut state, self.file_path.len() as u32, 0..0);
+        reserve_marked::<FileEntity>(&mut state, lens.0, &reachable.file_entity);
+        reserve_kept::<FilePackage>(&mut state, self.file_package.len() as u32, 0..0);
+        reserve_kept::<FileGroup>(&mut state, self.file_group.len() as u32, 0..0);
+        reserve_kept::<FilePackageChild>(&mut state, self.file_package_child.len() as u32, 0..0);
+        reserve_marked::<FileInfo>(&mut state, lens.1, &reachable.file_info);
+        reserve_marked::<FileDesc>(&mut state, lens.2, &reachable.file_desc);
+        reserve_marked::<FileData>(&mut state, lens.3, &reachable.file_data);
+
+        self.reinternalize_rows(&state, package_len, false);
+
+        let [info, desc, data] = first_section_counts;
+        self.header.file_package_info_count = info;
+        self.header.file_package_desc_count = desc;
+        self.header.file_package_data_count = data;
+        self.header.file_entity_count = self.file_entity.len() as u32;
+
+        CompactionReport {
+            file_entity: lens.0 as usize - self.file_entity.len(),
+            file_info: lens.1 as usize - self.file_info.len(),
+            file_desc: lens.2 as usize - self.file_desc.len(),
+            file_data: lens.3 as usize - self.file_data.len(),
+        }
+    }
+
+    /// The end of the first section and the number of rows counted by all of the sections of the
+    /// group, info, descriptor and data tables, in that order
+    fn section_bounds(&self) -> [(u32, u32); 4] {
+        let header = &self.header;
+
+        [
+            (
+                header.file_data_group_count,
+                header
+                    .file_data_group_count
+                    .saturating_add(header.file_info_group_count)
+                    .saturating_add(header.versioned_file_group_count),
+            ),
+            (
+                header.file_package_info_count,
+                header
+                    .file_package_info_count
+                    .saturating_add(header.file_group_info_count)
+                    .saturating_add(header.versioned_file_info_count),
+            ),
+            (
+                header.file_package_desc_count,
+                header
+                    .file_package_desc_count
+                    .saturating_add(header.file_group_info_count)
+                    .saturating_add(header.versioned_file_desc_count),
+            ),
+            (
+                header.file_package_data_count,
+                header
+                    .file_package_data_count
+                    .saturating_add(header.file_group_info_count)
+                    .saturating_add(header.versioned_file_data_count),
+            ),
+        ]
+    }
+
+    /// Whether any of the tables that are split across multiple counts in the header has rows past
+    /// the ones that the counts account for, such as the rows added with [`Self::push_file`]
+    fn has_unplaced_rows(&self) -> bool {
+        let lens = [
+            self.file_group.len(),
+            self.file_info.len(),
+            self.file_desc.len(),
+            self.file_data.len(),
+        ];
+
+        self.section_bounds()
+            .iter()
+            .zip(lens)
+            .any(|((_, counted), len)| len > *counted as usize)
+    }
+
+    /// Moves the rows that no count in the header accounts for to the end of the first section of
+    /// their table and adds them to its count, updating every reference to the rows that moved
+    ///
+    /// The sections of a table are read back to back, so the rows of the later sections (such as the
+    /// version history) are shifted up to make room. The version indices are relative to their
+    /// sections, so they are kept as they are.
+    ///
+    /// Returns the previous index of each row of file data, in their new order.
+    pub(crate) fn place_dynamic_rows(&mut self) -> Vec<u32> {
+        let package_len = self.package_len();
+        let [group, info, desc, data] = self.section_bounds();
+
+        let group = placed_order(self.file_group.len() as u32, group);
+        let info = placed_order(self.file_info.len() as u32, info);
+        let desc = placed_order(self.file_desc.len() as u32, desc);
+        let data = placed_order(self.file_data.len() as u32, data);
+
+        let mut state = SerState::moving();
+        reserve_order::<FileGroup>(&mut state, &group.order);
+        reserve_order::<FileInfo>(&mut state, &info.order);
+        reserve_order::<FileDesc>(&mut state, &desc.order);
+        reserve_order::<FileData>(&mut state, &data.order);
+
+        self.file_group.reorder(&group.order);
+        self.file_info.reorder(&info.order);
+        self.file_desc.reorder(&desc.order);
+        self.file_data.reorder(&data.order);
+
+        self.reinternalize_rows(&state, package_len, true);
+
+        self.header.file_data_group_count += group.placed;
+        self.header.file_package_info_count += info.placed;
+        self.header.file_package_desc_count += desc.placed;
+        self.header.file_package_data_count += data.placed;
+
+        data.order
+    }
+
+    /// Finds the [`FileGroup`] that contains each row of [`FileData`], indexed by the row
+    ///
+    /// Rows in the child range of a data group belong to that group. Rows that are not in any range,
+    /// like the rows added with [`Self::push_file`], belong to the group of the first [`FileDesc`] that
+    /// points to them. Rows that nothing points to have [`INVALID_INDEX`].
+    pub(crate) fn file_data_groups(&self) -> Vec<u32> {
+        let mut groups = vec![INVALID_INDEX; self.file_data.len()];
+
+        // Groups that contain file info redirect to themselves
+        for (index, group) in self.file_group.iter() {
+            if group.redirection_index() == index {
+                continue;
+            }
+
+            for data in group.child_range() {
+                if let Some(slot) = groups.get_mut(data as usize) {
+                    if *slot == INVALID_INDEX {
+                        *slot = index;
+                    }
+                }
+            }
+        }
+
+        for (_, desc) in self.file_desc.iter() {
+            if let Some(group) = groups.get_mut(desc.file_data_index() as usize) {
+                if *group == INVALID_INDEX {
+                    *group = desc.group_index();
+                }
+            }
+        }
+
+        groups
+    }
+
+    /// Copies the header that the tables were read with, updating the counts to the length of
+    /// every table that is not split across multiple counts
+    ///
+    /// The tables that are split must not have any unplaced rows, see [`Self::place_dynamic_rows`]
+    fn quick_serialize_header(&self) -> ResourceTableHeader {
+        let mut header = self.header;
+
+        header.stream_folder_count = self.stream_folder.len() as u32;
+        header.stream_path_count = self.stream_path.len() as u32;
+        header.stream_desc_count = self.stream_desc.len() as u32;
+        header.stream_data_count = self.stream_data.len() as u32;
+
+        header.file_path_count = self.file_path.len() as u32;
+        header.file_entity_count = self.file_entity.len() as u32;
+        header.file_package_count = self.file_package.len() as u32;
+        header.file_package_child_count = self.file_package_child.len() as u32;
+
+        header
+    }
+
+    /// Computes the size of every table and lookup as they would be written by [`Self::quick_serialize`]
+    pub fn table_sizes(&self) -> TableSizes {
+        fn rows<T>(table: &Table<T>) -> usize {
+            table.len() * std::mem::size_of::<T>()
+        }
+
+        let lookup = |len: usize| len * std::mem::size_of::<HashWithData>();
+
+        TableSizes {
+            header: std::mem::size_of::<ResourceTableHeader>(),
+            stream_folder: rows(&self.stream_folder),
+            stream_path_lookup: lookup(self.stream_path_lookup.len()),
+            stream_path: rows(&self.stream_path),
+            stream_desc: rows(&self.stream_desc),
+            stream_data: rows(&self.stream_data),
+            file_path_lookup: 2 * std::mem::size_of::<u32>()
+                + self.file_path_lookup.bucket_count() * std::mem::size_of::<Bucket>()
+                + lookup(self.file_path_lookup.len()),
+            file_path: rows(&self.file_path),
+            file_entity: rows(&self.file_entity),
+            file_package_lookup: lookup(self.file_package_lookup.len()),
+            file_package: rows(&self.file_package),
+            file_group: rows(&self.file_group),
+            file_package_child: rows(&self.file_package_child),
+            file_info: rows(&self.file_info),
+            file_desc: rows(&self.file_desc),
+            file_data: rows(&self.file_data),
+        }
+    }
+
+    /// Serializes every row of every table in its current position, without compacting
+    /// anything
+    ///
+    /// The only rows that move are the rows that were added to the group, info, descriptor and data
+    /// tables, which are written at the end of the first section of their table so that the counts
+    /// in the header describe them. Every reference to the rows that move is updated.
+    ///
+    /// The output can be read back with [`Self::from_bytes`], provided that the stream path and
+    /// file package lookups contain as many entries as the tables they index
+    pub fn quick_serialize(&self) -> Vec<u8> {
+        if self.has_unplaced_rows() {
+            let mut tables = self.clone();
+            tables.place_dynamic_rows();
+            return tables.quick_serialize();
+        }
+
+        let mut buffer: Vec<u8> = Vec::with_capacity(self.table_sizes().total());
+
+        let _ = buffer.write_binary(&self.quick_serialize_header());
+
         quick_serialize_table(&self.stream_folder, &mut buffer);
         quick_serialize_lookup(self.stream_path_lookup.iter(), &mut buffer);
         quick_serialize_table(&self.stream_path, &mut buffer);
         quick_serialize_table(&self.stream_desc, &mut buffer);
-        quick_serialize_lookup(self.file_path_lookup.iter(), &mut buffer);
+        quick_serialize_table(&self.stream_data, &mut buffer);
 
         let _ = buffer.write_u32::<LittleEndian>(self.file_path_lookup.len() as u32);
         let _ = buffer.write_u32::<LittleEndian>(self.file_path_lookup.bucket_count() as u32);
@@ -165,6 +1446,7 @@ impl ResourceTables {
             let _ = buffer.write_binary(&bucket);
         }
 
+        quick_serialize_lookup(self.file_path_lookup.iter(), &mut buffer);
         quick_serialize_table(&self.file_path, &mut buffer);
         quick_serialize_table(&self.file_entity, &mut buffer);
         quick_serialize_lookup(self.file_package_lookup.iter(), &mut buffer);
@@ -173,106 +1455,171 @@ impl ResourceTables {
         quick_serialize_table(&self.file_package_child, &mut buffer);
         quick_serialize_table(&self.file_info, &mut buffer);
         quick_serialize_table(&self.file_desc, &mut buffer);
-        quick_serialize_table(&self.file_data, &mut buffer);
-
-        buffer
-    }
-
-    pub fn into_bytes(&self, archive: &Archive) -> Result<Box<[u8]>, std::io::Error> {
-        let mut cache = SerState::new();
-
-        let mut info_groups = Vec::with_capacity(0x100);
-
-        for (index, package) in self.file_package.iter() {
-            cache.reserve::<FilePackage>(index);
-            package.reserve(&mut cache);
-
-            for group in package.data_group_range() {
-                let group = self
-                    .file_group
-                    .get(group)
-                    .expect("file data group is missing");
-                group.reserve(&mut cache, true);
-            }
+        quick_serialize_table(&self.file_data, &mut buffer);
 
-            for info in package.info_range() {
-                let info = self.file_info.get(info).expect("file info is missing");
-                info.reserve(&mut cache);
-            }
+        let size = buffer.len() as u32;
+        LittleEndian::write_u32(&mut buffer[..4], size);
 
-            let package = archive
-                .get_file_package(index)
-                .expect("file package is missing");
+        buffer
+    }
 
-            if let Some(SubPackageRef::FileGroup(group)) = package.sub_package() {
-                info_groups.push(group.index());
-            }
+    /// Serializes the tables with [`Self::quick_serialize`], reparses them and compares every table
+    /// and lookup against the reparsed version, returning the first difference
+    ///
+    /// Rows that [`Self::quick_serialize`] moves are compared at the position they are written to.
+    pub fn roundtrip_check(&self) -> Result<(), RoundtripMismatch> {
+        if self.has_unplaced_rows() {
+            let mut tables = self.clone();
+            tables.place_dynamic_rows();
+            return tables.roundtrip_check();
         }
 
-        let mut info_start = None;
+        if self.stream_path_lookup.len() != self.stream_path.len() {
+            return Err(RoundtripMismatch::LookupLength {
+                table: "stream_path",
+                lookup_len: self.stream_path_lookup.len(),
+                table_len: self.stream_path.len(),
+            });
+        }
 
-        for group in info_groups {
-            if !cache.try_reserve::<FileGroup>(group) {
-                continue;
-            }
+        if self.file_package_lookup.len() != self.file_package.len() {
+            return Err(RoundtripMismatch::LookupLength {
+                table: "file_package",
+                lookup_len: self.file_package_lookup.len(),
+                table_len: self.file_package.len(),
+            });
+        }
 
-            if info_start.is_none() {
-                info_start = Some(group);
-            }
+        let reparsed = Self::from_bytes(self.quick_serialize().into_boxed_slice())
+            .map_err(RoundtripMismatch::Parse)?;
 
-            let group = self
-                .file_group
-                .get(group)
-                .expect("file group index should be valid");
-            group.reserve(&mut cache, false);
+        compare_table(
+            "stream_folder",
+            &self.stream_folder,
+            &reparsed.stream_folder,
+        )?;
+        compare_lookup(
+            "stream_path_lookup",
+            self.stream_path_lookup.iter(),
+            reparsed.stream_path_lookup.iter(),
+        )?;
+        compare_table("stream_path", &self.stream_path, &reparsed.stream_path)?;
+        compare_table("stream_desc", &self.stream_desc, &reparsed.stream_desc)?;
+        compare_table("stream_data", &self.stream_data, &reparsed.stream_data)?;
+        compare_lookup(
+            "file_path_lookup",
+            self.file_path_lookup.iter(),
+            reparsed.file_path_lookup.iter(),
+        )?;
+        compare_table("file_path", &self.file_path, &reparsed.file_path)?;
+        compare_table("file_entity", &self.file_entity, &reparsed.file_entity)?;
+        compare_lookup(
+            "file_package_lookup",
+            self.file_package_lookup.iter(),
+            reparsed.file_package_lookup.iter(),
+        )?;
+        compare_table("file_package", &self.file_package, &reparsed.file_package)?;
+        compare_table("file_group", &self.file_group, &reparsed.file_group)?;
+        compare_table(
+            "file_package_child",
+            &self.file_package_child,
+            &reparsed.file_package_child,
+        )?;
+        compare_table("file_info", &self.file_info, &reparsed.file_info)?;
+        compare_table("file_desc", &self.file_desc, &reparsed.file_desc)?;
+        compare_table("file_data", &self.file_data, &reparsed.file_data)?;
 
-            for info in group.child_range() {
-                let info = self
-                    .file_info
-                    .get(info)
-                    .expect("file info index should be valid");
-                info.reserve(&mut cache);
+        Ok(())
+    }
 
-                for desc in info.descriptor_range() {
-                    let desc = self
-                        .file_desc
-                        .get(desc)
-                        .expect("file desc index should be valid");
-                    desc.reserve(&mut cache);
-                }
-            }
-        }
+    /// Serializes only the rows that are reachable from the packages, entities and stream folders,
+    /// compacting the tables and reindexing every reference
+    ///
+    /// The output is deterministic: rows are reserved by walking the tables in index order and the lookups
+    /// are written in hash order, so serializing the same tables always produces the same bytes.
+    ///
+    /// The version history of the tables is dropped, use [`Self::into_bytes_preserving_versions`]
+    /// to keep it.
+    pub fn into_bytes(&self, archive: &Archive) -> Result<Box<[u8]>, std::io::Error> {
+        self.serialize_compacted(archive, false, self.file_path_lookup.bucket_count())
+    }
 
-        let info_start = info_start.unwrap();
+    /// Serializes the tables like [`Self::into_bytes`], but spreads the file path lookup across
+    /// `bucket_count` buckets instead of keeping its current number of buckets
+    ///
+    /// [`BucketLookup::suggest_bucket_count`] gives a count that suits the number of file paths.
+    ///
+    /// # Panics
+    /// Panics if `bucket_count` is `0`
+    pub fn into_bytes_with_bucket_count(
+        &self,
+        archive: &Archive,
+        bucket_count: usize,
+    ) -> Result<Box<[u8]>, std::io::Error> {
+        self.serialize_compacted(archive, false, bucket_count)
+    }
 
-        for (index, _) in self.file_path.iter() {
-            cache.reserve::<FilePath>(index);
-        }
+    /// Serializes the tables like [`Self::into_bytes`], but keeps the version history
+    ///
+    /// The versioned groups, infos, descriptors and data are written after every other row of their
+    /// tables, in their original order. The version indices in the file paths and descriptors are
+    /// relative to those sections, so they are written as-is.
+    pub fn into_bytes_preserving_versions(
+        &self,
+        archive: &Archive,
+    ) -> Result<Box<[u8]>, std::io::Error> {
+        self.serialize_compacted(archive, true, self.file_path_lookup.bucket_count())
+    }
 
-        for (index, _) in self.file_entity.iter() {
-            cache.reserve::<FileEntity>(index);
+    /// Computes the size of the tables that [`Self::into_bytes`] would serialize, in bytes
+    ///
+    /// This runs the same reservation pass as [`Self::into_bytes`], but only counts the rows that
+    /// would be written instead of writing them.
+    pub fn serialized_size(&self, archive: &Archive) -> usize {
+        fn rows<T: 'static>(state: &SerState) -> usize {
+            state.iter::<T>().count() * std::mem::size_of::<T>()
         }
 
-        for (index, stream_folder) in self.stream_folder.iter() {
-            cache.reserve::<StreamFolder>(index);
-            stream_folder.reserve(&mut cache);
-
-            for path in stream_folder.stream_path_range() {
-                let stream_path = self
-                    .stream_path
-                    .get(path)
-                    .expect("stream path index should be invalid");
-                stream_path.reserve(&mut cache);
+        let (cache, _) = self.reserve_compacted(archive, false);
+
+        let lookups = self.stream_path_lookup.len()
+            + self.file_path_lookup.len()
+            + self.file_package_lookup.len();
+
+        rows::<StreamFolder>(&cache)
+            + rows::<StreamPath>(&cache)
+            + rows::<StreamDesc>(&cache)
+            + rows::<StreamData>(&cache)
+            + 2 * std::mem::size_of::<u32>()
+            + self.file_path_lookup.bucket_count() * std::mem::size_of::<Bucket>()
+            + lookups * std::mem::size_of::<HashWithData>()
+            + rows::<FilePath>(&cache)
+            + rows::<FileEntity>(&cache)
+            + rows::<FilePackage>(&cache)
+            + rows::<FileGroup>(&cache)
+            + rows::<FilePackageChild>(&cache)
+            + rows::<FileInfo>(&cache)
+            + rows::<FileDesc>(&cache)
+            + rows::<FileData>(&cache)
+    }
 
-                for desc in stream_path.descriptor_range() {
-                    let desc = self
-                        .stream_desc
-                        .get(desc)
-                        .expect("file entity index should be valid");
-                    desc.reserve(&mut cache);
-                }
-            }
-        }
+    fn serialize_compacted(
+        &self,
+        archive: &Archive,
+        keep_versions: bool,
+        bucket_count: usize,
+    ) -> Result<Box<[u8]>, std::io::Error> {
+        let (cache, info_start) = self.reserve_compacted(archive, keep_versions);
+
+        let rebuilt_lookup;
+        let file_path_lookup = if bucket_count == self.file_path_lookup.bucket_count() {
+            &self.file_path_lookup
+        } else {
+            rebuilt_lookup = self
+                .file_path_lookup
+                .rebuild_with_bucket_count(bucket_count);
+            &rebuilt_lookup
+        };
 
         let mut buffer: Vec<u8> = Vec::with_capacity(
             self.file_data.len() * std::mem::size_of::<FileData>()
@@ -284,7 +1631,7 @@ impl ResourceTables {
                 + self.file_package_child.len() * std::mem::size_of::<FilePackageChild>()
                 + self.file_group.len() * std::mem::size_of::<FileGroup>()
                 + self.file_path.len() * std::mem::size_of::<HashWithData>()
-                + 0x400 * std::mem::size_of::<Bucket>()
+                + bucket_count * std::mem::size_of::<Bucket>()
                 + self.file_package.len() * std::mem::size_of::<HashWithData>()
                 + self.stream_folder.len() * std::mem::size_of::<StreamFolder>()
                 + self.stream_path.len() * std::mem::size_of::<StreamPath>()
@@ -319,18 +1666,18 @@ impl ResourceTables {
             &mut buffer,
         )?;
 
-        buffer.write_u32::<LittleEndian>(self.file_path_lookup.len() as u32)?;
-        buffer.write_u32::<LittleEndian>(self.file_path_lookup.bucket_count() as u32)?;
+        buffer.write_u32::<LittleEndian>(file_path_lookup.len() as u32)?;
+        buffer.write_u32::<LittleEndian>(file_path_lookup.bucket_count() as u32)?;
 
-        for bucket in self.file_path_lookup.buckets() {
+        for bucket in file_path_lookup.buckets() {
             buffer.write_binary(&bucket)?;
         }
 
-        write_lookup::<FilePath>(self.file_path_lookup.iter(), &cache, &mut buffer)?;
+        write_lookup::<FilePath>(file_path_lookup.iter(), &cache, &mut buffer)?;
         write_table(
             &self.file_path,
             cache.iter::<FilePath>(),
-            |path| path.reinternalize(&cache),
+            |path| path.reinternalize(&cache, keep_versions),
             &mut buffer,
         )?;
         write_table(
@@ -377,7 +1724,7 @@ impl ResourceTables {
         write_table(
             &self.file_desc,
             cache.iter::<FileDesc>(),
-            |desc| desc.reinternalize(&cache),
+            |desc| desc.reinternalize(&cache, keep_versions),
             &mut buffer,
         )?;
         write_table(
@@ -390,108 +1737,244 @@ impl ResourceTables {
         Ok(buffer.into_boxed_slice())
     }
 
-    pub fn from_bytes(mut bytes: Box<[u8]>) -> std::io::Result<Self> {
-        // SAFETY: We read the resource table and then perform some checks on
-        //      data that should be consistent if we have read it from the right location
-        let resource_table = unsafe {
-            let resource_table = *ResourceTableHeader::cast(&bytes);
-            if resource_table.locale_count != 14 {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!(
-                        "Expected 14 locales, found {:#x}",
-                        resource_table.locale_count
-                    ),
-                ));
+    /// Reserves every row that is written by [`Self::into_bytes`] in the order that it is written,
+    /// returning the state along with the index of the first group of shared file infos
+    fn reserve_compacted(&self, archive: &Archive, keep_versions: bool) -> (SerState, u32) {
+        let mut cache = SerState::new();
+
+        let mut info_groups = Vec::with_capacity(0x100);
+
+        for (index, package) in self.file_package.iter() {
+            cache.reserve::<FilePackage>(index);
+            package.reserve(&mut cache);
+
+            for group in package.data_group_range() {
+                let group = self
+                    .file_group
+                    .get(group)
+                    .expect("file data group is missing");
+                group.reserve(&mut cache, true);
             }
 
-            if resource_table.region_count != 5 {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!(
-                        "Expected 5 regions, found {:#x}",
-                        resource_table.region_count
-                    ),
-                ));
+            for info in package.info_range() {
+                let info = self.file_info.get(info).expect("file info is missing");
+                info.reserve(&mut cache);
             }
 
-            resource_table
-        };
+            let package = archive
+                .get_file_package(index)
+                .expect("file package is missing");
 
-        let mut cursor_pos = std::mem::size_of::<ResourceTableHeader>();
+            if let Some(SubPackageRef::FileGroup(group)) = package.sub_package() {
+                info_groups.push(group.index());
+            }
+        }
 
-        macro_rules! get {
-            ($t:path, $size:expr) => {
-                unsafe {
-                    let value = <$t>::new(&mut bytes[cursor_pos..], ($size) as usize);
-                    cursor_pos += value.fixed_byte_len();
-                    value
-                }
+        // Files outside of the packages, like the ones added with `push_file` or by the builder, are
+        // only reachable through their entity. Their data groups are kept along with the data groups
+        // of the packages, which come before every info group
+        let info_group_set = info_groups.iter().copied().collect::<HashSet<_>>();
+        for (_, entity) in self.file_entity.iter() {
+            let Some(info) = self.file_info.get(entity.info_index()) else {
+                continue;
             };
+
+            for desc in info.descriptor_range() {
+                let desc = self
+                    .file_desc
+                    .get(desc)
+                    .expect("file desc index should be valid");
+                let group = desc.group_index();
+                if info_group_set.contains(&group) || !cache.try_reserve::<FileGroup>(group) {
+                    continue;
+                }
+
+                self.file_group
+                    .get(group)
+                    .expect("file data group is missing")
+                    .reserve(&mut cache, true);
+            }
+        }
+
+        let mut info_start = None;
+
+        for group in info_groups {
+            if !cache.try_reserve::<FileGroup>(group) {
+                continue;
+            }
+
+            if info_start.is_none() {
+                info_start = Some(group);
+            }
+
+            let group = self
+                .file_group
+                .get(group)
+                .expect("file group index should be valid");
+            group.reserve(&mut cache, false);
+
+            for info in group.child_range() {
+                let info = self
+                    .file_info
+                    .get(info)
+                    .expect("file info index should be valid");
+                info.reserve(&mut cache);
+
+                for desc in info.descriptor_range() {
+                    let desc = self
+                        .file_desc
+                        .get(desc)
+                        .expect("file desc index should be valid");
+                    desc.reserve(&mut cache);
+                }
+            }
         }
 
-        let stream_folder = get!(Table<StreamFolder>, resource_table.stream_folder_count);
-        let stream_path_lookup = get!(IndexLookup, resource_table.stream_path_count);
-        let stream_path = get!(Table<StreamPath>, resource_table.stream_path_count);
-        let stream_desc = get!(Table<StreamDesc>, resource_table.stream_desc_count);
-        let stream_data = get!(Table<StreamData>, resource_table.stream_data_count);
+        for (index, _) in self.file_path.iter() {
+            cache.reserve::<FilePath>(index);
+        }
 
-        let file_path_lookup_count =
-            LittleEndian::read_u32(&bytes[cursor_pos..cursor_pos + 4]) as usize;
-        let file_path_bucket_count =
-            LittleEndian::read_u32(&bytes[cursor_pos + 4..cursor_pos + 8]) as usize;
+        for (index, _) in self.file_entity.iter() {
+            cache.reserve::<FileEntity>(index);
+        }
 
-        cursor_pos += 8;
+        // The infos of files outside of the packages are kept through their entity as well. Every
+        // other info is reached through the packages and groups above, in their order
+        for (_, entity) in self.file_entity.iter() {
+            if !cache.try_reserve::<FileInfo>(entity.info_index()) {
+                continue;
+            }
 
-        let file_path_lookup = unsafe {
-            let lookup = BucketLookup::new(
-                &mut bytes[cursor_pos..],
-                file_path_lookup_count,
-                file_path_bucket_count,
-            );
-            cursor_pos += lookup.fixed_byte_len();
-            lookup
-        };
+            let info = self
+                .file_info
+                .get(entity.info_index())
+                .expect("file info index should be valid");
+            info.reserve(&mut cache);
+
+            for desc in info.descriptor_range() {
+                let desc = self
+                    .file_desc
+                    .get(desc)
+                    .expect("file desc index should be valid");
+                cache.try_reserve::<FileData>(desc.file_data_index());
+            }
+        }
 
-        let file_path = get!(Table<FilePath>, resource_table.file_path_count);
-        let file_entity = get!(Table<FileEntity>, resource_table.file_entity_count);
-        let file_package_lookup = get!(IndexLookup, resource_table.file_package_count);
-        let file_package = get!(Table<FilePackage>, resource_table.file_package_count);
-        let file_group = get!(
-            Table<FileGroup>,
-            resource_table.file_info_group_count
-                + resource_table.file_data_group_count
-                + resource_table.versioned_file_group_count
-        );
+        if keep_versions {
+            self.reserve_versions(&mut cache);
+        }
 
-        let file_package_child = get!(
-            Table<FilePackageChild>,
-            resource_table.file_package_child_count
-        );
+        for (index, stream_folder) in self.stream_folder.iter() {
+            cache.reserve::<StreamFolder>(index);
+            stream_folder.reserve(&mut cache);
 
-        let file_info = get!(
-            Table<FileInfo>,
-            resource_table.file_package_info_count
-                + resource_table.file_group_info_count
-                + resource_table.versioned_file_info_count
-        );
+            for path in stream_folder.stream_path_range() {
+                let stream_path = self
+                    .stream_path
+                    .get(path)
+                    .expect("stream path index should be invalid");
+                stream_path.reserve(&mut cache);
 
-        let file_desc = get!(
-            Table<FileDesc>,
-            resource_table.file_package_desc_count
-                + resource_table.file_group_info_count
-                + resource_table.versioned_file_desc_count
-        );
+                for desc in stream_path.descriptor_range() {
+                    let desc = self
+                        .stream_desc
+                        .get(desc)
+                        .expect("file entity index should be valid");
+                    desc.reserve(&mut cache);
+                }
+            }
+        }
 
-        let file_data = get!(
-            Table<FileData>,
-            resource_table.file_package_data_count
-                + resource_table.file_group_info_count
-                + resource_table.versioned_file_data_count
-        );
+        // Without any info groups, every group that was kept is a data group
+        (cache, info_start.unwrap_or(u32::MAX))
+    }
+
+    /// Reads a standalone resource section from `path`
+    ///
+    /// The file must contain the decompressed resource tables on their own, without the archive
+    /// metadata or any file data around them. These tables can be inspected and validated, but
+    /// nothing that they point to can be read since there is no archive to read it from.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        Self::from_bytes(std::fs::read(path)?.into_boxed_slice())
+    }
+
+    pub fn from_bytes(bytes: Box<[u8]>) -> std::io::Result<Self> {
+        Self::from_bytes_with(bytes, &ArchiveReadOptions::default())
+    }
+
+    /// Parses the tables like [`Self::from_bytes`], following the table related `options`
+    ///
+    /// The decompressor of the options is not used, since the bytes are already decompressed
+    pub fn from_bytes_with(
+        mut bytes: Box<[u8]>,
+        options: &ArchiveReadOptions,
+    ) -> std::io::Result<Self> {
+        let mut layout = ResourceLayout::parse(&bytes, options.validate_counts)?;
+
+        #[cfg(feature = "xxhash")]
+        let raw_hash = xxhash_rust::xxh3::xxh3_64(&bytes);
+
+        if !options.parse_streams {
+            layout.skip_streams();
+        }
+
+        macro_rules! get {
+            ($t:ty, $range:expr) => {{
+                let range = $range.clone();
+                // SAFETY: The layout has checked that the range is contained within the bytes,
+                //      and none of the ranges overlap
+                unsafe {
+                    Table::<$t>::new(
+                        &mut bytes[range.clone()],
+                        range.len() / std::mem::size_of::<$t>(),
+                    )
+                }
+            }};
+        }
+
+        macro_rules! get_lookup {
+            ($range:expr) => {{
+                let range = $range.clone();
+                // SAFETY: See above
+                unsafe {
+                    IndexLookup::new(
+                        &mut bytes[range.clone()],
+                        range.len() / std::mem::size_of::<HashWithData>(),
+                    )
+                }
+            }};
+        }
+
+        let stream_folder = get!(StreamFolder, layout.stream_folder);
+        let stream_path_lookup = get_lookup!(layout.stream_path_lookup);
+        let stream_path = get!(StreamPath, layout.stream_path);
+        let stream_desc = get!(StreamDesc, layout.stream_desc);
+        let stream_data = get!(StreamData, layout.stream_data);
+
+        // SAFETY: See above
+        let file_path_lookup = unsafe {
+            BucketLookup::new(
+                &mut bytes[layout.file_path_buckets.start..layout.file_path_lookup.end],
+                layout.file_path_lookup.len() / std::mem::size_of::<HashWithData>(),
+                layout.file_path_buckets.len() / std::mem::size_of::<Bucket>(),
+            )
+        };
+
+        let file_path = get!(FilePath, layout.file_path);
+        let file_entity = get!(FileEntity, layout.file_entity);
+        let file_package_lookup = get_lookup!(layout.file_package_lookup);
+        let file_package = get!(FilePackage, layout.file_package);
+        let file_group = get!(FileGroup, layout.file_group);
+        let file_package_child = get!(FilePackageChild, layout.file_package_child);
+        let file_info = get!(FileInfo, layout.file_info);
+        let file_desc = get!(FileDesc, layout.file_desc);
+        let file_data = get!(FileData, layout.file_data);
 
         Ok(Self {
             raw_data: bytes,
+            #[cfg(feature = "xxhash")]
+            raw_hash,
+            header: layout.header,
             stream_folder,
             stream_path_lookup,
             stream_path,
@@ -510,3 +1993,224 @@ impl ResourceTables {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntoHash;
+
+    fn spec(in_group_offset: u32) -> FileDataSpec {
+        FileDataSpec {
+            group: 0,
+            in_group_offset,
+            compressed_size: 0x10,
+            decompressed_size: 0x10 + in_group_offset,
+            is_compressed: false,
+        }
+    }
+
+    fn reparse(tables: &ResourceTables) -> ResourceTables {
+        ResourceTables::from_bytes(tables.quick_serialize().into_boxed_slice()).unwrap()
+    }
+
+    /// Follows the file at `path` down to its data, returning its decompressed size
+    fn decompressed_size(tables: &ResourceTables, path: &str) -> Option<u32> {
+        let path = tables
+            .file_path
+            .get(tables.file_path_lookup.get(path.into_hash())?)?;
+        let entity = tables.file_entity.get(path.file_entity_index())?;
+        let info = tables.file_info.get(entity.info_index())?;
+        let desc = tables.file_desc.get(info.descriptor_range().start)?;
+        Some(
+            tables
+                .file_data
+                .get(desc.file_data_index())?
+                .decompressed_size(),
+        )
+    }
+
+    /// Tables with a single file and one unreferenced row in the versioned section of the info,
+    /// descriptor and data tables
+    fn tables_with_history() -> ResourceTables {
+        let mut tables = ResourceTables::empty(1);
+        tables.file_group.push(FileGroup::new_data(0, 0, 0, 1));
+        tables.push_file("a".into_hash(), spec(0));
+        tables
+            .file_info
+            .push(FileInfo::new(INVALID_INDEX, INVALID_INDEX, INVALID_INDEX));
+        tables.file_desc.push(FileDesc::new(
+            0,
+            INVALID_INDEX,
+            LoadMethod::Owned(INVALID_INDEX),
+        ));
+        tables.file_data.push(FileData::new(&spec(0x100)));
+
+        let mut tables = reparse(&tables);
+        tables.header.file_package_info_count -= 1;
+        tables.header.file_package_desc_count -= 1;
+        tables.header.file_package_data_count -= 1;
+        tables.header.versioned_file_info_count = 1;
+        tables.header.versioned_file_desc_count = 1;
+        tables.header.versioned_file_data_count = 1;
+        tables
+    }
+
+    #[test]
+    fn quick_serialize_places_pushed_rows_before_versions() {
+        let mut tables = tables_with_history();
+        tables.push_file("b".into_hash(), spec(0x20));
+        tables.roundtrip_check().unwrap();
+
+        let reparsed = reparse(&tables);
+        assert_eq!(reparsed.header.file_package_info_count, 2);
+        assert_eq!(reparsed.header.versioned_file_info_count, 1);
+        assert_eq!(decompressed_size(&reparsed, "a"), Some(0x10));
+        assert_eq!(decompressed_size(&reparsed, "b"), Some(0x30));
+
+        // The versioned rows are still the last rows of their tables
+        assert_eq!(
+            reparsed.file_info.get(2).unwrap().path_index(),
+            INVALID_INDEX
+        );
+        assert_eq!(
+            reparsed.file_data.get(2).unwrap().decompressed_size(),
+            0x110
+        );
+    }
+
+    #[test]
+    fn strip_versions_after_reparse_keeps_pushed_rows() {
+        let mut tables = tables_with_history();
+        tables.push_file("b".into_hash(), spec(0x20));
+
+        let mut reparsed = reparse(&tables);
+        reparsed.strip_versions();
+
+        assert_eq!(reparsed.file_info.len(), 2);
+        assert_eq!(reparsed.file_desc.len(), 2);
+        assert_eq!(reparsed.file_data.len(), 2);
+        assert_eq!(decompressed_size(&reparsed, "a"), Some(0x10));
+        assert_eq!(decompressed_size(&reparsed, "b"), Some(0x30));
+        reparse(&reparsed).roundtrip_check().unwrap();
+    }
+
+    #[test]
+    fn compact_recomputes_section_counts() {
+        let mut tables = tables_with_history();
+        tables.file_data.push(FileData::new(&spec(0x200)));
+        tables.push_file("b".into_hash(), spec(0x20));
+
+        let mut tables = reparse(&tables);
+        let report = tables.compact();
+        assert_eq!(report.file_data, 1);
+        assert_eq!(report.file_info, 0);
+
+        let reparsed = reparse(&tables);
+        assert_eq!(reparsed.header.file_package_data_count, 2);
+        assert_eq!(reparsed.header.versioned_file_data_count, 0);
+        assert_eq!(reparsed.file_data.len(), 2);
+        assert_eq!(decompressed_size(&reparsed, "a"), Some(0x10));
+        assert_eq!(decompressed_size(&reparsed, "b"), Some(0x30));
+        reparsed.roundtrip_check().unwrap();
+    }
+
+    #[test]
+    fn remap_rows_keeps_versions() {
+        let mut tables = ResourceTables::empty(1);
+        tables.file_group.push(FileGroup::new_data(0, 0, 0, 1));
+        tables.push_file("a".into_hash(), spec(0));
+        let junk = tables.file_data.push(FileData::new(&spec(0x200)));
+        tables.push_file("b".into_hash(), spec(0x20));
+
+        let desc = tables.file_desc.get_mut(0).unwrap();
+        *desc = FileDesc::new(
+            desc.group_index(),
+            desc.file_data_index(),
+            LoadMethod::Owned(5),
+        );
+
+        let package_len = tables.package_len();
+        let len = tables.file_data.len() as u32;
+        tables.file_data.remove_dynamic(junk).unwrap();
+        let order = (0..len).filter(|row| *row != junk).collect::<Vec<_>>();
+        tables.remap_rows::<FileData>(&order, package_len);
+
+        assert_eq!(decompressed_size(&tables, "a"), Some(0x10));
+        assert_eq!(decompressed_size(&tables, "b"), Some(0x30));
+        assert_eq!(
+            tables.file_desc.get(0).unwrap().load_method(),
+            LoadMethod::Owned(5)
+        );
+    }
+
+    #[test]
+    fn serialization_keeps_unknown_flag_bits() {
+        const UNKNOWN: u32 = 1 << 31;
+
+        let mut tables = ResourceTables::empty(1);
+        tables.file_group.push(FileGroup::new_data(0, 0, 0, 1));
+        tables.push_file("a".into_hash(), spec(0));
+
+        // Every bit pattern is valid for the rows, so zeroed rows stand in for the ones this
+        // crate has no constructor for
+        let mut package: FilePackage = unsafe { std::mem::zeroed() };
+        package.set_raw_flags(UNKNOWN);
+        let index = tables.file_package.push(package);
+        tables.file_package_lookup.insert("p".into_hash(), index);
+        let mut stream_path: StreamPath = unsafe { std::mem::zeroed() };
+        stream_path.set_raw_flags(UNKNOWN);
+        let index = tables.stream_path.push(stream_path);
+        tables.stream_path_lookup.insert("s".into_hash(), index);
+        // The descriptor that the path points to
+        tables.stream_desc.push(unsafe { std::mem::zeroed() });
+
+        let data = tables.file_data.get_mut(0).unwrap();
+        data.set_raw_flags(data.raw_flags() | UNKNOWN);
+        let info = tables.file_info.get_mut(0).unwrap();
+        info.set_raw_flags(info.raw_flags() | UNKNOWN);
+
+        let reparsed = reparse(&tables);
+        for raw_flags in [
+            reparsed.file_data.get(0).unwrap().raw_flags(),
+            reparsed.file_info.get(0).unwrap().raw_flags(),
+            reparsed.file_package.get(0).unwrap().raw_flags(),
+            reparsed.stream_path.get(0).unwrap().raw_flags(),
+        ] {
+            assert_eq!(raw_flags & UNKNOWN, UNKNOWN);
+        }
+    }
+
+    #[test]
+    fn overflowing_section_counts_are_rejected() {
+        let mut tables = tables_with_history();
+        tables.header.file_info_group_count = u32::MAX;
+
+        let error = ResourceTables::from_bytes(tables.quick_serialize().into_boxed_slice())
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        // The versioned sections start past the end of every table, so nothing is stripped
+        tables.header.file_group_info_count = u32::MAX;
+        assert_eq!(tables.strip_versions(), 0);
+    }
+
+    #[test]
+    fn lookup_without_buckets_is_rejected() {
+        let bytes = ResourceTables::empty_bytes(0).into_boxed_slice();
+        let error = ResourceTables::from_bytes(bytes).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn collisions_without_buckets_is_empty() {
+        // Tables with a lookup like this are rejected when parsed, but the lookup can still be created
+        #[repr(C, align(8))]
+        struct Aligned([u8; 0]);
+
+        let mut bytes = Aligned([]);
+        // SAFETY: There are no buckets or hashes to read
+        let lookup = unsafe { BucketLookup::new(&mut bytes.0, 0, 0) };
+        assert!(lookup.collisions().is_empty());
+    }
+}
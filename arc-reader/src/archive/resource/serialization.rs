@@ -4,23 +4,50 @@ use indexmap::IndexSet;
 
 use crate::index::{checked_range, INVALID_INDEX};
 
+/// Tracks which rows of each table are kept when serializing, and the new index of each row
+///
+/// The new index of a row is the order that it was reserved in. The `HashMap` is only ever used to look up
+/// the set for a single type and is never iterated, so the output order only depends on the order that rows
+/// are reserved in, and never on hashing.
 pub(crate) struct SerState {
     type_map: HashMap<TypeId, IndexSet<u32>>,
+    moving: bool,
 }
 
 impl SerState {
     pub fn new() -> Self {
         Self {
             type_map: HashMap::with_capacity(48),
+            moving: false,
         }
     }
 
+    /// Creates a state for moving rows around in place, instead of serializing them
+    ///
+    /// Only the tables whose rows move need to be reserved. Indices of tables that were not reserved
+    /// at all, and indices that were not reserved in the tables that were, are kept as they are.
+    pub fn moving() -> Self {
+        Self {
+            type_map: HashMap::with_capacity(48),
+            moving: true,
+        }
+    }
+
+    /// Whether this state was created with [`Self::moving`]
+    pub fn is_moving(&self) -> bool {
+        self.moving
+    }
+
     #[track_caller]
     pub fn get<T: 'static>(&self, index: u32) -> u32 {
         if index == INVALID_INDEX {
             return index;
         }
 
+        if self.moving {
+            return self.try_get::<T>(index).unwrap_or(index);
+        }
+
         let map = self.type_map.get(&TypeId::of::<T>()).unwrap_or_else(|| {
             panic!(
                 "Failed to get the reserved indexes for {}",
@@ -74,15 +101,19 @@ impl SerState {
         set.insert(index)
     }
 
+    /// Gets the new index of a row, or [`None`] if it was not reserved
+    ///
+    /// When moving rows, every index of a table that was not reserved at all is kept as it is
     pub fn try_get<T: 'static>(&self, index: u32) -> Option<u32> {
         if index == INVALID_INDEX {
             return None;
         }
 
-        self.type_map
-            .get(&TypeId::of::<T>())?
-            .get_index_of(&index)
-            .map(|idx| idx as u32)
+        match self.type_map.get(&TypeId::of::<T>()) {
+            Some(set) => set.get_index_of(&index).map(|idx| idx as u32),
+            None if self.moving => Some(index),
+            None => None,
+        }
     }
 
     #[track_caller]
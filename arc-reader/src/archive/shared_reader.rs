@@ -0,0 +1,135 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// A handle to an archive on disk that can be read from multiple threads at once
+///
+/// Reads are positioned, they take the offset to read from instead of sharing a cursor, so threads do
+/// not contend on the position of the file. This uses `pread` on unix and `ReadFile` with an offset on
+/// windows. On every other platform, reads are serialized behind a lock and seek before reading.
+///
+/// Use [`Self::cursor`] to get a [`Read`] + [`Seek`] view for the methods that read from the archive,
+/// such as [`Archive::read_file`](super::Archive::read_file). Each thread should use its own cursor.
+pub struct SharedArchiveReader {
+    #[cfg(any(unix, windows))]
+    file: File,
+
+    #[cfg(not(any(unix, windows)))]
+    file: std::sync::Mutex<File>,
+
+    len: u64,
+}
+
+impl SharedArchiveReader {
+    /// Opens the archive at `path` for reading
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+
+    /// Wraps an already opened archive
+    pub fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len();
+
+        Ok(Self {
+            #[cfg(any(unix, windows))]
+            file,
+            #[cfg(not(any(unix, windows)))]
+            file: std::sync::Mutex::new(file),
+            len,
+        })
+    }
+
+    /// The size of the archive, in bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Checks if the archive is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads bytes starting at `offset` into `buf`, returning how many were read
+    ///
+    /// Like [`Read::read`], this may read fewer bytes than fit in `buf`, and returns 0 at the end of the archive
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::FileExt::read_at(&self.file, buf, offset)
+        }
+
+        #[cfg(windows)]
+        {
+            std::os::windows::fs::FileExt::seek_read(&self.file, buf, offset)
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        {
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start(offset))?;
+            file.read(buf)
+        }
+    }
+
+    /// Reads exactly enough bytes starting at `offset` to fill `buf`
+    ///
+    /// Returns [`io::ErrorKind::UnexpectedEof`] if the archive ends first
+    pub fn read_exact_at(&self, mut offset: u64, mut buf: &mut [u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.read_at(offset, buf) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                Ok(read) => {
+                    offset += read as u64;
+                    buf = &mut buf[read..];
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a cursor over the archive that starts at the beginning
+    pub fn cursor(&self) -> SharedArchiveCursor<'_> {
+        SharedArchiveCursor {
+            reader: self,
+            position: 0,
+        }
+    }
+}
+
+/// A [`Read`] + [`Seek`] view of a [`SharedArchiveReader`] with its own position, see [`SharedArchiveReader::cursor`]
+pub struct SharedArchiveCursor<'a> {
+    reader: &'a SharedArchiveReader,
+    position: u64,
+}
+
+impl Read for SharedArchiveCursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read_at(self.position, buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for SharedArchiveCursor<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.reader.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seeked to a negative or overflowing position",
+            )
+        })?;
+
+        Ok(self.position)
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, Read, Seek, SeekFrom},
+};
+
+use crate::{index::Index, io::ReadBinExt};
+
+use super::{
+    file_data::{Compression, FileData},
+    stream_data::StreamData,
+    Archive,
+};
+
+/// Supplies the bytes of every file when an archive is written with [`Archive::write`](super::Archive::write)
+///
+/// The bytes are exactly what ends up in the archive, so data that is reported as compressed
+/// must already be compressed.
+pub trait FileSource {
+    /// The bytes to store for the [`FileData`] at `file_data`
+    fn data_for(&self, file_data: Index<FileData>) -> io::Result<Cow<'_, [u8]>>;
+
+    /// Whether the bytes returned by [`Self::data_for`] are compressed
+    ///
+    /// Compressed data keeps the compression and decompressed size of its row, uncompressed data
+    /// has its row marked as uncompressed.
+    fn is_compressed(&self, file_data: Index<FileData>) -> bool;
+
+    /// The bytes to store for the [`StreamData`] at `stream_data`
+    fn stream_data_for(&self, stream_data: Index<StreamData>) -> io::Result<Cow<'_, [u8]>>;
+
+    /// The bytes of the user table and the unknown table, which follow the resource tables
+    ///
+    /// They are copied to the end of the written archive as-is. By default there are none, and both
+    /// tables are written as empty.
+    fn trailing_tables(&self) -> io::Result<Cow<'_, [u8]>> {
+        Ok(Cow::Borrowed(&[]))
+    }
+}
+
+/// A [`FileSource`] that reads the bytes of every file unchanged from the archive that they were read from
+pub struct PassthroughSource<'a, R> {
+    archive: &'a Archive,
+    reader: RefCell<R>,
+
+    /// The group that each row of file data is read from, indexed by the row
+    groups: Vec<u32>,
+}
+
+impl<'a, R: Read + Seek> PassthroughSource<'a, R> {
+    /// Creates a source that reads the files of `archive` from `reader`
+    ///
+    /// `reader` must be reading from the same archive that `archive` was read from.
+    pub fn new(archive: &'a Archive, reader: R) -> Self {
+        Self {
+            archive,
+            reader: RefCell::new(reader),
+            groups: archive.file_data_groups(),
+        }
+    }
+
+    /// Reads `size` bytes at `offset` from the start of the archive
+    fn read_at(&self, offset: u64, size: usize) -> io::Result<Cow<'_, [u8]>> {
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_count_vec(size).map(Cow::Owned)
+    }
+}
+
+impl<R: Read + Seek> FileSource for PassthroughSource<'_, R> {
+    fn data_for(&self, file_data: Index<FileData>) -> io::Result<Cow<'_, [u8]>> {
+        let location = self
+            .groups
+            .get(file_data.get() as usize)
+            .and_then(|group| self.archive.data_location(*group, file_data.get()))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("File data {:#x} is not in any group", file_data.get()),
+                )
+            })?;
+
+        self.read_at(location.absolute_offset, location.compressed_size as usize)
+    }
+
+    fn is_compressed(&self, file_data: Index<FileData>) -> bool {
+        self.archive
+            .resource
+            .file_data
+            .get(file_data.get())
+            .is_some_and(|data| data.compression() != Compression::None)
+    }
+
+    fn stream_data_for(&self, stream_data: Index<StreamData>) -> io::Result<Cow<'_, [u8]>> {
+        let (offset, size) = self
+            .archive
+            .stream_data_location(stream_data)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Stream data {:#x} is out of range", stream_data.get()),
+                )
+            })?;
+
+        self.read_at(offset, size as usize)
+    }
+
+    fn trailing_tables(&self) -> io::Result<Cow<'_, [u8]>> {
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(self.archive.metadata.user_table_offset))?;
+
+        let mut tables = Vec::new();
+        reader.read_to_end(&mut tables)?;
+        Ok(Cow::Owned(tables))
+    }
+}
+
+/// A [`FileSource`] that replaces the bytes of some files, and takes every other file from another source
+///
+/// Replacement bytes are always stored uncompressed.
+pub struct OverlaySource<S> {
+    base: S,
+    files: HashMap<u32, Vec<u8>>,
+    streams: HashMap<u32, Vec<u8>>,
+}
+
+impl<S: FileSource> OverlaySource<S> {
+    /// Creates an overlay without any replacements on top of `base`
+    pub fn new(base: S) -> Self {
+        Self {
+            base,
+            files: HashMap::new(),
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Replaces the bytes of the [`FileData`] at `file_data` with uncompressed `data`
+    pub fn replace(&mut self, file_data: Index<FileData>, data: impl Into<Vec<u8>>) -> &mut Self {
+        self.files.insert(file_data.get(), data.into());
+        self
+    }
+
+    /// Replaces the bytes of the [`StreamData`] at `stream_data` with `data`
+    pub fn replace_stream(
+        &mut self,
+        stream_data: Index<StreamData>,
+        data: impl Into<Vec<u8>>,
+    ) -> &mut Self {
+        self.streams.insert(stream_data.get(), data.into());
+        self
+    }
+}
+
+impl<S: FileSource> FileSource for OverlaySource<S> {
+    fn data_for(&self, file_data: Index<FileData>) -> io::Result<Cow<'_, [u8]>> {
+        match self.files.get(&file_data.get()) {
+            Some(data) => Ok(Cow::Borrowed(data)),
+            None => self.base.data_for(file_data),
+        }
+    }
+
+    fn is_compressed(&self, file_data: Index<FileData>) -> bool {
+        !self.files.contains_key(&file_data.get()) && self.base.is_compressed(file_data)
+    }
+
+    fn stream_data_for(&self, stream_data: Index<StreamData>) -> io::Result<Cow<'_, [u8]>> {
+        match self.streams.get(&stream_data.get()) {
+            Some(data) => Ok(Cow::Borrowed(data)),
+            None => self.base.stream_data_for(stream_data),
+        }
+    }
+
+    fn trailing_tables(&self) -> io::Result<Cow<'_, [u8]>> {
+        self.base.trailing_tables()
+    }
+}
@@ -0,0 +1,316 @@
+//! Export of the resource tables to a SQLite database, enabled with the `sqlite` feature
+
+use std::path::Path;
+
+use hash40::Hash40;
+use rusqlite::{params, Connection};
+
+use super::Archive;
+
+/// The tables that [`Archive::export_sqlite`] creates
+///
+/// Every table has an `idx` column with the index of the row in the archive. Columns that end in `_label`
+/// hold the label of the hash column before them, or `NULL` if the hash has no label.
+const SCHEMA: &str = "
+CREATE TABLE file_path (
+    idx INTEGER PRIMARY KEY,
+    path INTEGER NOT NULL,
+    path_label TEXT,
+    extension INTEGER NOT NULL,
+    extension_label TEXT,
+    parent INTEGER NOT NULL,
+    parent_label TEXT,
+    file_name INTEGER NOT NULL,
+    file_name_label TEXT,
+    file_entity INTEGER NOT NULL
+);
+
+CREATE TABLE file_entity (
+    idx INTEGER PRIMARY KEY,
+    owner_package INTEGER,
+    owner_group INTEGER,
+    file_info INTEGER NOT NULL
+);
+
+CREATE TABLE file_info (
+    idx INTEGER PRIMARY KEY,
+    file_path INTEGER NOT NULL,
+    file_entity INTEGER NOT NULL,
+    desc_start INTEGER NOT NULL,
+    desc_count INTEGER NOT NULL,
+    flags INTEGER NOT NULL
+);
+
+CREATE TABLE file_desc (
+    idx INTEGER PRIMARY KEY,
+    file_group INTEGER NOT NULL,
+    file_data INTEGER NOT NULL,
+    load_method TEXT NOT NULL
+);
+
+CREATE TABLE file_data (
+    idx INTEGER PRIMARY KEY,
+    in_group_offset INTEGER NOT NULL,
+    compressed_size INTEGER NOT NULL,
+    decompressed_size INTEGER NOT NULL,
+    flags INTEGER NOT NULL
+);
+
+CREATE TABLE file_group (
+    idx INTEGER PRIMARY KEY,
+    archive_offset INTEGER NOT NULL,
+    compressed_size INTEGER NOT NULL,
+    decompressed_size INTEGER NOT NULL,
+    child_start INTEGER NOT NULL,
+    child_count INTEGER NOT NULL,
+    redirection INTEGER NOT NULL
+);
+
+CREATE TABLE file_package (
+    idx INTEGER PRIMARY KEY,
+    path INTEGER NOT NULL,
+    path_label TEXT,
+    name INTEGER NOT NULL,
+    name_label TEXT,
+    parent INTEGER NOT NULL,
+    parent_label TEXT,
+    lifetime INTEGER NOT NULL,
+    lifetime_label TEXT,
+    info_start INTEGER NOT NULL,
+    info_count INTEGER NOT NULL,
+    child_start INTEGER NOT NULL,
+    child_count INTEGER NOT NULL,
+    group_start INTEGER NOT NULL,
+    group_count INTEGER NOT NULL,
+    flags INTEGER NOT NULL
+);
+
+CREATE TABLE file_package_child (
+    idx INTEGER PRIMARY KEY,
+    path INTEGER NOT NULL,
+    path_label TEXT,
+    file_package INTEGER NOT NULL
+);
+
+CREATE TABLE stream_folder (
+    idx INTEGER PRIMARY KEY,
+    name INTEGER NOT NULL,
+    name_label TEXT,
+    path_start INTEGER NOT NULL,
+    path_count INTEGER NOT NULL
+);
+
+CREATE TABLE stream_path (
+    idx INTEGER PRIMARY KEY,
+    path INTEGER NOT NULL,
+    path_label TEXT,
+    desc_start INTEGER NOT NULL,
+    desc_count INTEGER NOT NULL,
+    flags INTEGER NOT NULL
+);
+
+CREATE TABLE stream_desc (
+    idx INTEGER PRIMARY KEY,
+    stream_data INTEGER NOT NULL
+);
+
+CREATE TABLE stream_data (
+    idx INTEGER PRIMARY KEY,
+    offset INTEGER NOT NULL,
+    size INTEGER NOT NULL
+);
+";
+
+/// Converts a hash to the integer stored in the database, hashes only use the lower 40 bits
+fn hash(hash: Hash40) -> i64 {
+    hash.0 as i64
+}
+
+impl Archive {
+    /// Writes the resource tables to a new SQLite database at `path`
+    ///
+    /// Each table of the archive is written to a table of the same name, with one column for each
+    /// field of the row. Ranges are written as a start and count column, and hashes are written
+    /// alongside their label from the global [`Hash40::label_map`]. See the `SCHEMA` in this module
+    /// for the exact layout.
+    ///
+    /// Everything is written in a single transaction, nothing is written if any row fails to insert.
+    pub fn export_sqlite(&self, path: impl AsRef<Path>) -> rusqlite::Result<()> {
+        let mut connection = Connection::open(path)?;
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(SCHEMA)?;
+
+        self.export_file_tables(&transaction)?;
+        self.export_stream_tables(&transaction)?;
+
+        transaction.commit()
+    }
+
+    fn export_file_tables(&self, db: &Connection) -> rusqlite::Result<()> {
+        let resource = &self.resource;
+        let labels = Hash40::label_map();
+        let labels = labels.lock().unwrap();
+        let package_len = resource.package_len();
+
+        let mut insert =
+            db.prepare("INSERT INTO file_path VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")?;
+        for (index, row) in resource.file_path.iter() {
+            insert.execute(params![
+                index,
+                hash(row.path()),
+                labels.label_of(row.path()),
+                hash(row.extension()),
+                labels.label_of(row.extension()),
+                hash(row.parent()),
+                labels.label_of(row.parent()),
+                hash(row.file_name()),
+                labels.label_of(row.file_name()),
+                row.file_entity_index(),
+            ])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO file_entity VALUES (?, ?, ?, ?)")?;
+        for (index, row) in resource.file_entity.iter() {
+            let owner = row.package_or_group_index();
+            let (package, group) = if row.is_owned_by_group(package_len) {
+                (None, Some(owner))
+            } else {
+                (Some(owner), None)
+            };
+
+            insert.execute(params![index, package, group, row.info_index()])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO file_info VALUES (?, ?, ?, ?, ?, ?)")?;
+        for (index, row) in resource.file_info.iter() {
+            let descs = row.descriptor_range();
+            insert.execute(params![
+                index,
+                row.path_index(),
+                row.entity_index(),
+                descs.start,
+                descs.len() as u32,
+                row.raw_flags(),
+            ])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO file_desc VALUES (?, ?, ?, ?)")?;
+        for (index, row) in resource.file_desc.iter() {
+            insert.execute(params![
+                index,
+                row.group_index(),
+                row.file_data_index(),
+                format!("{:?}", row.load_method()),
+            ])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO file_data VALUES (?, ?, ?, ?, ?)")?;
+        for (index, row) in resource.file_data.iter() {
+            insert.execute(params![
+                index,
+                row.in_group_offset() as i64,
+                row.compressed_size(),
+                row.decompressed_size(),
+                row.raw_flags(),
+            ])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO file_group VALUES (?, ?, ?, ?, ?, ?, ?)")?;
+        for (index, row) in resource.file_group.iter() {
+            let children = row.child_range();
+            insert.execute(params![
+                index,
+                row.archive_offset() as i64,
+                row.compressed_size,
+                row.decompressed_size,
+                children.start,
+                children.len() as u32,
+                row.redirection_index(),
+            ])?;
+        }
+
+        let mut insert = db.prepare(
+            "INSERT INTO file_package VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for (index, row) in resource.file_package.iter() {
+            let (infos, children, groups) = (
+                row.info_range(),
+                row.child_package_range(),
+                row.data_group_range(),
+            );
+            insert.execute(params![
+                index,
+                hash(row.path()),
+                labels.label_of(row.path()),
+                hash(row.name()),
+                labels.label_of(row.name()),
+                hash(row.parent()),
+                labels.label_of(row.parent()),
+                hash(row.lifetime()),
+                labels.label_of(row.lifetime()),
+                infos.start,
+                infos.len() as u32,
+                children.start,
+                children.len() as u32,
+                groups.start,
+                groups.len() as u32,
+                row.raw_flags(),
+            ])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO file_package_child VALUES (?, ?, ?, ?)")?;
+        for (index, row) in resource.file_package_child.iter() {
+            insert.execute(params![
+                index,
+                hash(row.path()),
+                labels.label_of(row.path()),
+                row.inner().data(),
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    fn export_stream_tables(&self, db: &Connection) -> rusqlite::Result<()> {
+        let resource = &self.resource;
+        let labels = Hash40::label_map();
+        let labels = labels.lock().unwrap();
+
+        let mut insert = db.prepare("INSERT INTO stream_folder VALUES (?, ?, ?, ?, ?)")?;
+        for (index, row) in resource.stream_folder.iter() {
+            let paths = row.stream_path_range();
+            insert.execute(params![
+                index,
+                hash(row.name()),
+                labels.label_of(row.name()),
+                paths.start,
+                paths.len() as u32,
+            ])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO stream_path VALUES (?, ?, ?, ?, ?, ?)")?;
+        for (index, row) in resource.stream_path.iter() {
+            let descs = row.descriptor_range();
+            insert.execute(params![
+                index,
+                hash(row.path()),
+                labels.label_of(row.path()),
+                descs.start,
+                descs.len() as u32,
+                row.raw_flags(),
+            ])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO stream_desc VALUES (?, ?)")?;
+        for (index, row) in resource.stream_desc.iter() {
+            insert.execute(params![index, row.stream_data_index()])?;
+        }
+
+        let mut insert = db.prepare("INSERT INTO stream_data VALUES (?, ?, ?)")?;
+        for (index, row) in resource.stream_data.iter() {
+            insert.execute(params![index, row.offset() as i64, row.size() as i64])?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use bitflags::Flags;
+
+use crate::{index::Index, BackRefAnomaly, FlaggedRow, ValidationError};
+
+use super::Archive;
+
+/// Formats `flags` the same way that they are written in the flag definitions, e.g. `HAS_SUB_PACKAGE | IS_SYM_LINK`
+fn flag_names<F: Flags>(flags: F) -> String
+where
+    F::Bits: bitflags::parser::WriteHex,
+{
+    let mut names = String::new();
+    let _ = bitflags::parser::to_writer(&flags, &mut names);
+    names
+}
+
+/// Reports a [`ValidationError::ConflictingFlags`] if both `first` and `second` are set in `flags`
+pub(crate) fn check_exclusive<F: Flags + Copy>(
+    row: FlaggedRow,
+    flags: F,
+    first: F,
+    second: F,
+    errors: &mut Vec<ValidationError>,
+) where
+    F::Bits: bitflags::parser::WriteHex,
+{
+    if flags.contains(first) && flags.contains(second) {
+        errors.push(ValidationError::ConflictingFlags {
+            row,
+            first: flag_names(first),
+            second: flag_names(second),
+        });
+    }
+}
+
+/// Reports a [`ValidationError::MissingRequiredFlags`] if `flag` is set in `flags` without all of `required`
+pub(crate) fn check_requires<F: Flags + Copy>(
+    row: FlaggedRow,
+    flags: F,
+    flag: F,
+    required: F,
+    errors: &mut Vec<ValidationError>,
+) where
+    F::Bits: bitflags::parser::WriteHex,
+{
+    if flags.contains(flag) && !flags.contains(required) {
+        errors.push(ValidationError::MissingRequiredFlags {
+            row,
+            flag: flag_names(flag),
+            required: flag_names(required),
+        });
+    }
+}
+
+impl Archive {
+    /// Checks the invariants of every row in the archive, returning every violation that was found
+    ///
+    /// The following flag invariants are checked:
+    /// - [`FilePackage`](super::file_package::FilePackage) and [`FileInfo`](super::file_info::FileInfo) can not be both localized and regional
+    /// - [`FilePackage`](super::file_package::FilePackage) sym links require a sub package, and regional sym links require both
+    /// - [`FileInfo`](super::file_info::FileInfo) can not be both a regular file and a graphics archive
+    /// - [`StreamPath`](super::stream_path::StreamPath) can not be both localized and regional
+    /// - [`FileData`](super::file_data::FileData) that is ZSTD compressed must also be marked as compressed
+    ///
+    /// An archive read straight from the game has no violations, these usually come from flags
+    /// that have been edited by hand.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (index, package) in self.resource.file_package.iter() {
+            package.validate_flags(Index::new(index), &mut errors);
+        }
+
+        for (index, info) in self.resource.file_info.iter() {
+            info.validate_flags(Index::new(index), &mut errors);
+        }
+
+        for (index, path) in self.resource.stream_path.iter() {
+            path.validate_flags(Index::new(index), &mut errors);
+        }
+
+        for (index, data) in self.resource.file_data.iter() {
+            if data.has_zstd_without_compression() {
+                let index = Index::new(index);
+                errors.push(ValidationError::ZstdWithoutCompression {
+                    index,
+                    path: self.file_data_owner(index).map(|path| path.path()),
+                });
+            }
+        }
+
+        errors
+    }
+
+    /// Follows the references between every [`FilePath`](super::file_path::FilePath),
+    /// [`FileEntity`](super::file_entity::FileEntity) and [`FileInfo`](super::file_info::FileInfo),
+    /// returning every chain that is broken
+    ///
+    /// Many paths can share one entity, and an info does not have to be the info of the entity or path that it
+    /// points to, so most asymmetry is normal and is not reported. What is reported:
+    /// - paths, entities and infos that point to a row that does not exist
+    /// - entities whose info points to a different entity
+    /// - entities whose info points to a path that points to a different entity
+    ///
+    /// Like [`Self::validate`], an archive read straight from the game has none of these.
+    pub fn check_back_references(&self) -> Vec<BackRefAnomaly> {
+        let resource = &self.resource;
+        let mut anomalies = Vec::new();
+
+        for (index, path) in resource.file_path.iter() {
+            let entity = path.file_entity_index();
+            if resource.file_entity.get(entity).is_none() {
+                anomalies.push(BackRefAnomaly::MissingPathEntity {
+                    path: Index::new(index),
+                    entity: Index::new(entity),
+                });
+            }
+        }
+
+        for (index, info) in resource.file_info.iter() {
+            if resource.file_path.get(info.path_index()).is_none() {
+                anomalies.push(BackRefAnomaly::MissingInfoPath {
+                    info: Index::new(index),
+                    path: Index::new(info.path_index()),
+                });
+            }
+
+            if resource.file_entity.get(info.entity_index()).is_none() {
+                anomalies.push(BackRefAnomaly::MissingInfoEntity {
+                    info: Index::new(index),
+                    entity: Index::new(info.entity_index()),
+                });
+            }
+        }
+
+        for (index, entity) in resource.file_entity.iter() {
+            let info_index = entity.info_index();
+            let Some(info) = resource.file_info.get(info_index) else {
+                anomalies.push(BackRefAnomaly::MissingEntityInfo {
+                    entity: Index::new(index),
+                    info: Index::new(info_index),
+                });
+                continue;
+            };
+
+            if info.entity_index() != index {
+                anomalies.push(BackRefAnomaly::InfoEntityMismatch {
+                    entity: Index::new(index),
+                    info: Index::new(info_index),
+                    found: Index::new(info.entity_index()),
+                });
+            }
+
+            let Some(path) = resource.file_path.get(info.path_index()) else {
+                continue;
+            };
+
+            if path.file_entity_index() != index {
+                anomalies.push(BackRefAnomaly::PathEntityMismatch {
+                    entity: Index::new(index),
+                    info: Index::new(info_index),
+                    path: Index::new(info.path_index()),
+                    found: Index::new(path.file_entity_index()),
+                });
+            }
+        }
+
+        anomalies
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+use crate::{index::Index, io::WriteBinExt};
+
+use super::{file_data::DataRegion, source::FileSource, Archive, ArchiveMetadata};
+
+/// Writes zeroes until the position of `out`, relative to `base`, is a multiple of `alignment`,
+/// returning the aligned position
+fn pad_to<W: Write + Seek>(out: &mut W, base: u64, alignment: u64) -> io::Result<u64> {
+    let position = out.stream_position()? - base;
+    let aligned = position.next_multiple_of(alignment);
+    io::copy(&mut io::repeat(0).take(aligned - position), out)?;
+    Ok(aligned)
+}
+
+impl Archive {
+    /// Writes the archive to `out`, taking the bytes of every file from `file_source`
+    ///
+    /// The archive is laid out as the metadata, followed by the stream data, the file data, the
+    /// ZSTD compressed resource tables and the user and unknown tables from
+    /// [`FileSource::trailing_tables`]. Every offset and size in the tables and the metadata is
+    /// rewritten to match the new layout, while the rows themselves are written in their current
+    /// positions like [`Self::quick_serialize`].
+    ///
+    /// File data is written group by group. Each [`FileData`](super::file_data::FileData) is placed
+    /// in the group whose child range contains it, or the group of the first descriptor that points
+    /// to it if there is none, and groups stay in the region that they were read from. Groups that
+    /// no data is placed in are given an empty extent.
+    ///
+    /// All offsets are relative to the position of `out` when this is called.
+    pub fn write<W: Write + Seek>(
+        &self,
+        out: &mut W,
+        file_source: &impl FileSource,
+    ) -> io::Result<()> {
+        let base = out.stream_position()?;
+
+        // The source is indexed by the rows of this archive, so keep track of where they are placed
+        let mut resource = self.resource.clone();
+        let source_rows = resource.place_dynamic_rows();
+
+        out.write_all(&[0u8; std::mem::size_of::<ArchiveMetadata>()])?;
+
+        let stream_data_offset = pad_to(out, base, 0x10)?;
+        for (index, stream_data) in resource.stream_data.iter_mut() {
+            let offset = pad_to(out, base, 0x10)?;
+            let data = file_source.stream_data_for(Index::new(index))?;
+            out.write_all(&data)?;
+            stream_data.set_location(offset, data.len() as u64);
+        }
+
+        // The rows of file data that are placed in each group, by region
+        let mut groups: [BTreeMap<u32, Vec<u32>>; 2] = Default::default();
+        for (index, group) in resource.file_group.iter() {
+            let region = self.region_of(self.metadata.file_data_offset + group.archive_offset());
+            groups[(region == DataRegion::Shared) as usize].insert(index, Vec::new());
+        }
+
+        for (data, group) in resource.file_data_groups().into_iter().enumerate() {
+            if let Some(children) = groups.iter_mut().find_map(|groups| groups.get_mut(&group)) {
+                children.push(data as u32);
+            }
+        }
+
+        let file_data_offset = pad_to(out, base, 0x10)?;
+        let mut shared_file_data_offset = file_data_offset;
+        for (region, groups) in groups.into_iter().enumerate() {
+            if region == 1 {
+                shared_file_data_offset = pad_to(out, base, 0x10)?;
+            }
+
+            for (group_index, children) in groups {
+                let group_start = pad_to(out, base, 0x10)?;
+                let mut decompressed_size = 0u32;
+
+                for data_index in children {
+                    let source_index = Index::new(source_rows[data_index as usize]);
+                    let offset = pad_to(out, base, 0x4)?;
+                    let data = file_source.data_for(source_index)?;
+                    out.write_all(&data)?;
+
+                    if let Some(file_data) = resource.file_data.get_mut(data_index) {
+                        if !file_source.is_compressed(source_index) {
+                            file_data.set_uncompressed();
+                        }
+
+                        file_data.set_stored(offset - group_start, data.len() as u32);
+                        decompressed_size += file_data.decompressed_size();
+                    }
+                }
+
+                let group_end = out.stream_position()? - base;
+                if let Some(group) = resource.file_group.get_mut(group_index) {
+                    group.set_location(
+                        group_start - file_data_offset,
+                        (group_end - group_start) as u32,
+                        decompressed_size,
+                    );
+                }
+            }
+        }
+
+        let resource_table_offset = pad_to(out, base, 0x10)?;
+        out.write_compressed_section(&resource.quick_serialize())?;
+
+        let trailing = file_source.trailing_tables()?;
+        let user_table_offset = pad_to(out, base, 0x10)?;
+        out.write_all(&trailing)?;
+        let end = out.stream_position()? - base;
+
+        // The unknown table keeps its position relative to the user table
+        let unknown_table_offset = user_table_offset
+            + self
+                .metadata
+                .unknown_table_offset
+                .saturating_sub(self.metadata.user_table_offset)
+                .min(trailing.len() as u64);
+
+        let metadata = ArchiveMetadata {
+            magic: ArchiveMetadata::MAGIC,
+            stream_data_offset,
+            file_data_offset,
+            shared_file_data_offset,
+            resource_table_offset,
+            user_table_offset,
+            unknown_table_offset,
+        };
+
+        out.seek(SeekFrom::Start(base))?;
+        out.write_binary(&metadata)?;
+        out.seek(SeekFrom::Start(base + end))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::archive::{source::PassthroughSource, ArchiveBuilder};
+
+    #[test]
+    fn write_copies_files_and_trailing_tables() {
+        let mut bytes = ArchiveBuilder::new()
+            .add_file("a.bin", b"hello".as_slice())
+            .add_file("b.bin", b"archive".as_slice())
+            .build();
+        bytes.extend_from_slice(b"user table");
+
+        let archive = Archive::read(&mut Cursor::new(&bytes)).unwrap();
+        let mut out = Cursor::new(Vec::new());
+        archive
+            .write(
+                &mut out,
+                &PassthroughSource::new(&archive, Cursor::new(&bytes)),
+            )
+            .unwrap();
+
+        out.set_position(0);
+        let written = Archive::read(&mut out).unwrap();
+        assert_eq!(written.read_file(&mut out, "a.bin").unwrap(), b"hello");
+        assert_eq!(written.read_file(&mut out, "b.bin").unwrap(), b"archive");
+
+        let user_table = written.metadata.user_table_offset as usize;
+        assert_eq!(&out.get_ref()[user_table..], b"user table");
+    }
+}
@@ -0,0 +1,77 @@
+use std::io;
+
+/// Decompresses the compressed sections and file data of an archive
+///
+/// Every compressed section in the archive is ZSTD compressed, so [`ZstdDecompressor`] is used unless
+/// another implementation is provided (e.g. with [`Archive::read_with_decompressor`](crate::archive::Archive::read_with_decompressor)).
+pub trait Decompressor {
+    /// Decompresses all of `input`, which is expected to decompress to `decompressed_size` bytes
+    ///
+    /// The caller checks the size of the output, implementations only need to use it as a hint
+    fn decompress(&self, input: &[u8], decompressed_size: usize) -> io::Result<Vec<u8>>;
+}
+
+/// The default [`Decompressor`], which decompresses ZSTD data
+///
+/// On desktop this uses the `zstd` crate, on switch this uses the decompressor built into the game
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ZstdDecompressor;
+
+impl Decompressor for ZstdDecompressor {
+    #[cfg(not(target_os = "switch"))]
+    fn decompress(&self, input: &[u8], decompressed_size: usize) -> io::Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(decompressed_size);
+        zstd::stream::copy_decode(input, &mut data)?;
+        Ok(data)
+    }
+
+    #[cfg(target_os = "switch")]
+    fn decompress(&self, input: &[u8], decompressed_size: usize) -> io::Result<Vec<u8>> {
+        #[repr(C)]
+        struct ZSTD_PtrBuffer {
+            pub ptr: *mut u8,
+            pub size: usize,
+            pub pos: usize,
+        }
+
+        #[skyline::from_offset(0x39a2fc0)]
+        fn decompress_stream(
+            thing: *mut u64,
+            output: &mut ZSTD_PtrBuffer,
+            input: &mut ZSTD_PtrBuffer,
+        ) -> usize;
+
+        #[skyline::from_offset(0x35410b0)]
+        fn initialize_decompressor(ptr: *mut u64);
+
+        #[skyline::from_offset(0x3541030)]
+        fn finalize_decompressor(ptr: *mut u64);
+
+        let mut data = Vec::with_capacity(decompressed_size);
+
+        let mut decompressor = [0u64; 2];
+        unsafe {
+            initialize_decompressor(decompressor.as_mut_ptr());
+            data.set_len(decompressed_size);
+        }
+
+        let mut input_buffer = ZSTD_PtrBuffer {
+            ptr: input.as_ptr() as _,
+            size: input.len(),
+            pos: 0,
+        };
+
+        let mut output_buffer = ZSTD_PtrBuffer {
+            ptr: data.as_mut_ptr(),
+            size: data.len(),
+            pos: 0,
+        };
+
+        unsafe {
+            decompress_stream(decompressor[1] as _, &mut output_buffer, &mut input_buffer);
+            finalize_decompressor(decompressor.as_mut_ptr());
+        }
+
+        Ok(data)
+    }
+}
@@ -0,0 +1,241 @@
+use hash40::Hash40;
+
+use crate::{
+    archive::{
+        file_data::FileData, file_entity::FileEntity, file_info::FileInfo,
+        file_package::FilePackage, file_path::FilePath, stream_path::StreamPath,
+    },
+    index::Index,
+};
+
+/// Errors that can occur when reading data out of an archive
+///
+/// Functions that return [`std::io::Error`] carry these errors as the inner error of the [`std::io::Error`],
+/// and they are unwrapped again when converting back. So the variant survives being passed through
+/// the `io` helpers of this crate.
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    /// Reading from the archive failed, for example because it is truncated
+    #[error(transparent)]
+    Io(std::io::Error),
+
+    /// Data was read from the archive, but it could not be decompressed
+    #[error("Failed to decompress data: {0}")]
+    Decompress(String),
+
+    /// The archive does not start with the magic of an archive, so it is likely not an archive at all
+    #[error("Expected magic {expected:#x}, found {found:#x}")]
+    BadMagic { expected: u64, found: u64 },
+
+    /// A row points to a row of another table that does not exist
+    #[error("Table {table} has no row at index {index:#x}")]
+    IndexOutOfRange { table: &'static str, index: u32 },
+
+    /// The requested path is not present in the file path lookup
+    #[error("Path {0} is not in the archive")]
+    MissingPath(Hash40),
+
+    /// The [`FileData`](crate::prelude::FileData) at this index uses a compression other than ZSTD,
+    /// which this crate cannot decompress
+    #[error("File data {0:#x} uses an unsupported proprietary compression")]
+    UnsupportedCompression(u32),
+
+    /// The offset of the [`FileData`](crate::prelude::FileData) at `data`, added to the offset of the
+    /// [`FileGroup`](crate::prelude::FileGroup) at `group`, does not fit in a [`u64`]
+    #[error("File data {data:#x} in group {group:#x} has an offset that does not fit in 64 bits")]
+    DataOffsetOverflow { group: u32, data: u32 },
+}
+
+impl From<std::io::Error> for ArchiveError {
+    fn from(error: std::io::Error) -> Self {
+        if !error
+            .get_ref()
+            .is_some_and(|inner| inner.is::<ArchiveError>())
+        {
+            return Self::Io(error);
+        }
+
+        *error
+            .into_inner()
+            .expect("error should have an inner error")
+            .downcast::<ArchiveError>()
+            .expect("inner error should be an archive error")
+    }
+}
+
+impl From<ArchiveError> for std::io::Error {
+    fn from(error: ArchiveError) -> Self {
+        let kind = match &error {
+            ArchiveError::Io(_) => std::io::ErrorKind::Other,
+            ArchiveError::MissingPath(_) => std::io::ErrorKind::NotFound,
+            ArchiveError::UnsupportedCompression(_) => std::io::ErrorKind::Unsupported,
+            _ => std::io::ErrorKind::InvalidData,
+        };
+
+        match error {
+            ArchiveError::Io(error) => error,
+            error => std::io::Error::new(kind, error),
+        }
+    }
+}
+
+/// Returned by [`Archive::try_push_file`](crate::archive::Archive::try_push_file) when the path
+/// is already present in the file path lookup
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Path {path} is already in the archive at file path {:#x}", index.get())]
+pub struct DuplicatePath {
+    /// The path that was being added
+    pub path: Hash40,
+
+    /// The index of the [`FilePath`] that already has this path
+    pub index: Index<FilePath>,
+}
+
+/// The first difference found by [`Archive::roundtrip_check`](crate::archive::Archive::roundtrip_check)
+/// between an archive's tables and the tables reparsed from their serialized form
+#[derive(Debug, thiserror::Error)]
+pub enum RoundtripMismatch {
+    /// The serialized tables could not be parsed again
+    #[error("Failed to reparse the serialized tables")]
+    Parse(#[source] std::io::Error),
+
+    /// A lookup does not contain the same number of entries as the table that it indexes,
+    /// which the serialized format cannot represent
+    #[error("Lookup {table} has {lookup_len:#x} entries but its table has {table_len:#x} rows")]
+    LookupLength {
+        table: &'static str,
+        lookup_len: usize,
+        table_len: usize,
+    },
+
+    /// The reparsed table has a different number of rows
+    #[error("Table {table} has {expected:#x} rows, but {found:#x} were reparsed")]
+    Length {
+        table: &'static str,
+        expected: usize,
+        found: usize,
+    },
+
+    /// The row at `index` of the reparsed table is different
+    #[error("Table {table} differs at index {index:#x}")]
+    Row { table: &'static str, index: usize },
+}
+
+/// A row found by [`Archive::validate`](crate::archive::Archive::validate) that breaks
+/// one of the invariants of the archive
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    /// Two flags that are mutually exclusive are both set
+    #[error("{row} has both {first} and {second} set")]
+    ConflictingFlags {
+        row: FlaggedRow,
+        first: String,
+        second: String,
+    },
+
+    /// A flag is set without the flags that it requires
+    #[error("{row} has {flag} set without {required}")]
+    MissingRequiredFlags {
+        row: FlaggedRow,
+        flag: String,
+        required: String,
+    },
+
+    /// A [`FileData`](crate::prelude::FileData) is marked as ZSTD compressed without being marked
+    /// as compressed, which makes the resource loaders abort
+    ///
+    /// `path` is the path of the first [`FileInfo`](crate::prelude::FileInfo) that uses the data, if any
+    #[error("File data {:#x} used by {} has IS_ZSTD_COMPRESSION set without IS_COMPRESSED", index.get(), describe_owner(.path))]
+    ZstdWithoutCompression {
+        index: Index<FileData>,
+        path: Option<Hash40>,
+    },
+}
+
+/// The row that a [`ValidationError`] about flags was found in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlaggedRow {
+    /// A row of the file package table
+    FilePackage(Index<FilePackage>),
+
+    /// A row of the file info table
+    FileInfo(Index<FileInfo>),
+
+    /// A row of the stream path table
+    StreamPath(Index<StreamPath>),
+}
+
+impl std::fmt::Display for FlaggedRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (table, index) = match self {
+            Self::FilePackage(index) => ("file_package", index.get()),
+            Self::FileInfo(index) => ("file_info", index.get()),
+            Self::StreamPath(index) => ("stream_path", index.get()),
+        };
+
+        write!(f, "Table {table} at index {index:#x}")
+    }
+}
+
+/// Formats the path of the file that owns a row, for errors that might not have one
+fn describe_owner(path: &Option<Hash40>) -> String {
+    path.map_or_else(|| String::from("no file"), |path| path.to_string())
+}
+
+/// A break in the references between file paths, entities and infos found by
+/// [`Archive::check_back_references`](crate::archive::Archive::check_back_references)
+///
+/// Infos and paths do not always point back to each other, so these only report the chains that can not
+/// be followed at all, or that lead back to a different row than the one they started from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BackRefAnomaly {
+    /// A path points to a file entity that does not exist
+    #[error("File path {:#x} points to file entity {:#x}, which does not exist", path.get(), entity.get())]
+    MissingPathEntity {
+        path: Index<FilePath>,
+        entity: Index<FileEntity>,
+    },
+
+    /// An info points to a path that does not exist
+    #[error("File info {:#x} points to file path {:#x}, which does not exist", info.get(), path.get())]
+    MissingInfoPath {
+        info: Index<FileInfo>,
+        path: Index<FilePath>,
+    },
+
+    /// An info points to a file entity that does not exist
+    #[error("File info {:#x} points to file entity {:#x}, which does not exist", info.get(), entity.get())]
+    MissingInfoEntity {
+        info: Index<FileInfo>,
+        entity: Index<FileEntity>,
+    },
+
+    /// An entity points to an info that does not exist
+    #[error("File entity {:#x} points to file info {:#x}, which does not exist", entity.get(), info.get())]
+    MissingEntityInfo {
+        entity: Index<FileEntity>,
+        info: Index<FileInfo>,
+    },
+
+    /// The info of an entity belongs to a different entity
+    #[error(
+        "File entity {:#x} uses file info {:#x}, which belongs to file entity {:#x}",
+        entity.get(),
+        info.get(),
+        found.get()
+    )]
+    InfoEntityMismatch {
+        entity: Index<FileEntity>,
+        info: Index<FileInfo>,
+        found: Index<FileEntity>,
+    },
+
+    /// Following an entity to its info and then to that info's path leads to a path of a different entity
+    #[error("File entity {:#x} uses file path {:#x} through file info {:#x}, but the path points to file entity {:#x}", entity.get(), path.get(), info.get(), found.get())]
+    PathEntityMismatch {
+        entity: Index<FileEntity>,
+        info: Index<FileInfo>,
+        path: Index<FilePath>,
+        found: Index<FileEntity>,
+    },
+}
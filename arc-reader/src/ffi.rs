@@ -0,0 +1,152 @@
+//! C ABI for opening archives and reading files out of them
+//!
+//! All of the functions here are `extern "C"` and unmangled, so that a header can be generated with
+//! `cbindgen` (see `cbindgen.toml`). A shared library can be built with
+//! `cargo rustc -p arc-reader --features ffi --crate-type cdylib`.
+//!
+//! When a function fails, it returns null and the reason can be retrieved with [`arc_last_error`]
+//! from the same thread.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    fs::File,
+    io::BufReader,
+    panic::AssertUnwindSafe,
+};
+
+use crate::{archive::Archive, ArchiveError};
+
+/// An archive opened with [`arc_open`], along with the file that it was read from
+///
+/// This is opaque to C and must be released with [`arc_free`]
+pub struct ArcHandle {
+    archive: Archive,
+    reader: BufReader<File>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // Interior nul bytes would truncate the message on the C side anyways
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, storing the error as the last error if it fails or panics
+fn guard<T>(f: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(message)) => {
+            set_last_error(message);
+            None
+        }
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(format!("panicked: {message}"));
+            None
+        }
+    }
+}
+
+/// Gets the message of the last error that occurred on the calling thread, or null if nothing has failed yet
+///
+/// The returned string is owned by this library and stays valid until the next error on the same thread.
+/// It must not be freed by the caller.
+#[no_mangle]
+pub extern "C" fn arc_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Opens and reads the archive at `path`, returning null on failure
+///
+/// The file stays open for as long as the handle is alive, so that files can be read out of it
+/// with [`arc_read_file`]. The handle must be released with [`arc_free`].
+///
+/// # Safety
+/// `path` must be null or point to a nul-terminated UTF-8 string
+#[no_mangle]
+pub unsafe extern "C" fn arc_open(path: *const c_char) -> *mut ArcHandle {
+    guard(|| {
+        if path.is_null() {
+            return Err("path is null".to_string());
+        }
+
+        let path = CStr::from_ptr(path)
+            .to_str()
+            .map_err(|e| format!("path is not valid UTF-8: {e}"))?;
+
+        let mut reader = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+        let archive = Archive::read(&mut reader).map_err(|e| e.to_string())?;
+
+        Ok(Box::into_raw(Box::new(ArcHandle { archive, reader })))
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Reads and decompresses the file with the path hash `hash`, returning null on failure
+///
+/// On success, the length of the returned buffer is written to `out_len`. The buffer is owned by the caller
+/// and must be released with [`arc_free_buf`], passing the same length.
+///
+/// # Safety
+/// - `arc` must be null or a handle returned by [`arc_open`] that has not been freed
+/// - `out_len` must be null or valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn arc_read_file(
+    arc: *mut ArcHandle,
+    hash: u64,
+    out_len: *mut usize,
+) -> *mut u8 {
+    guard(|| {
+        if out_len.is_null() {
+            return Err("out_len is null".to_string());
+        }
+
+        let handle = arc.as_mut().ok_or_else(|| "archive is null".to_string())?;
+
+        let data = handle
+            .archive
+            .read_file(&mut handle.reader, hash)
+            .map_err(|e: ArchiveError| e.to_string())?;
+
+        let data = Box::into_raw(data.into_boxed_slice());
+        *out_len = data.len();
+
+        Ok(data.cast::<u8>())
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Closes an archive opened with [`arc_open`]. Passing null does nothing.
+///
+/// # Safety
+/// `arc` must be null or a handle returned by [`arc_open`] that has not already been freed
+#[no_mangle]
+pub unsafe extern "C" fn arc_free(arc: *mut ArcHandle) {
+    if !arc.is_null() {
+        drop(Box::from_raw(arc));
+    }
+}
+
+/// Releases a buffer returned by [`arc_read_file`]. Passing null does nothing.
+///
+/// # Safety
+/// `buf` must be null or a buffer returned by [`arc_read_file`] that has not already been freed,
+/// and `len` must be the length that was written to `out_len` when it was returned
+#[no_mangle]
+pub unsafe extern "C" fn arc_free_buf(buf: *mut u8, len: usize) {
+    if !buf.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(buf, len)));
+    }
+}
@@ -1,5 +1,8 @@
-use std::fmt::{Debug, Display};
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use core::fmt::Display;
 
+#[cfg(feature = "std")]
 use hash40::Hash40;
 
 use crate::BinaryRepr;
@@ -16,16 +19,31 @@ pub(crate) struct Hash {
     pub len: u8,
 }
 
-impl std::hash::Hash for Hash {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        state.write_u64(self.hash40().0)
+impl core::hash::Hash for Hash {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(self.value())
     }
 }
 
 impl Hash {
+    /// Creates a new hash value from a [`Hash40`]
+    #[cfg(feature = "std")]
+    pub const fn new(hash: Hash40) -> Self {
+        Self {
+            crc: hash.crc(),
+            len: hash.str_len(),
+        }
+    }
+
+    /// Gets the raw 40-bit hash value
+    pub const fn value(&self) -> u64 {
+        ((self.len as u64) << 32) | (self.crc as u64)
+    }
+
     /// Gets the hash value as a [`Hash40`], more useful for most operations
+    #[cfg(feature = "std")]
     pub const fn hash40(&self) -> Hash40 {
-        Hash40(((self.len as u64) << 32) | (self.crc as u64))
+        Hash40(self.value())
     }
 
     /// Gets the length of the hash value
@@ -35,38 +53,63 @@ impl Hash {
     }
 }
 
+#[cfg(feature = "std")]
 impl Debug for Hash {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         Display::fmt(&self.hash40(), f)
     }
 }
 
+#[cfg(not(feature = "std"))]
+impl Debug for Hash {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#012x}", self.value())
+    }
+}
+
 /// A 4-byte aligned Hash40 value
 ///
 /// This value is used over a [`u64`] because a [`u64`] has different alignment on different systems.
 ///
 /// For example, on x86-64, a `u64`` is aligned to the 4-byte boundary but on arm systems it is aligned on 8-bytes
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct HashWithData {
     pub crc: u32,
     len_and_data: u32,
 }
 
 impl HashWithData {
+    /// The largest value that fits in the 24 bits of data, this is also [`INVALID_INDEX`](crate::index::INVALID_INDEX)
+    pub const MAX_DATA: u32 = 0x00FF_FFFF;
+
+    #[cfg(feature = "std")]
+    #[track_caller]
     pub const fn new(hash: Hash40, data: u32) -> Self {
+        debug_assert!(
+            data <= Self::MAX_DATA,
+            "hash data does not fit in 24 bits, it would be truncated"
+        );
+
         Self {
             crc: hash.crc(),
             len_and_data: (hash.str_len() as u32) | (data << 8),
         }
     }
 
+    /// Gets the raw 40-bit hash value
+    pub const fn value(&self) -> u64 {
+        ((self.length() as u64) << 32) | (self.crc as u64)
+    }
+
     /// Gets the hash value as a [`Hash40`], more useful for most operations
+    #[cfg(feature = "std")]
     pub const fn hash40(&self) -> Hash40 {
-        Hash40(((self.length() as u64) << 32) | (self.crc as u64))
+        Hash40(self.value())
     }
 
     /// Sets the hash of the hash value
+    #[cfg(feature = "std")]
     pub fn set_hash40(&mut self, hash: Hash40) {
         self.len_and_data = (self.len_and_data & 0xFFFF_FF00) | hash.str_len() as u32;
         self.crc = hash.crc();
@@ -83,13 +126,23 @@ impl HashWithData {
     }
 
     /// Sets the data of the hash value
+    ///
+    /// In debug builds this panics if `data` does not fit in 24 bits, since it would otherwise
+    /// silently be truncated and corrupt the archive
+    #[track_caller]
     pub fn set_data(&mut self, data: u32) {
-        self.len_and_data = (self.len_and_data & 0xFF) | data << 24;
+        debug_assert!(
+            data <= Self::MAX_DATA,
+            "hash data {data:#x} does not fit in 24 bits, it would be truncated"
+        );
+
+        self.len_and_data = (self.len_and_data & 0xFF) | (data << 8);
     }
 }
 
+#[cfg(feature = "std")]
 impl Debug for HashWithData {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("HashWithData")
             .field("hash", &format!("{}", self.hash40()))
             .field("data", &self.data())
@@ -97,5 +150,33 @@ impl Debug for HashWithData {
     }
 }
 
-impl BinaryRepr for Hash {}
-impl BinaryRepr for HashWithData {}
+#[cfg(not(feature = "std"))]
+impl Debug for HashWithData {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HashWithData")
+            .field("hash", &format_args!("{:#012x}", self.value()))
+            .field("data", &self.data())
+            .finish()
+    }
+}
+
+unsafe impl BinaryRepr for Hash {}
+unsafe impl BinaryRepr for HashWithData {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::IntoHash;
+
+    #[test]
+    fn set_data_roundtrips_every_24_bit_value() {
+        let hash = "fighter/mario/model/body/c00/model.numdlb".into_hash();
+        let mut value = HashWithData::new(hash, 0);
+
+        for data in 0..=HashWithData::MAX_DATA {
+            value.set_data(data);
+            assert_eq!(value.data(), data);
+            assert_eq!(value.hash40(), hash);
+        }
+    }
+}
@@ -1,11 +1,109 @@
-use std::ops::Range;
+use core::{marker::PhantomData, ops::Range};
 
 pub const INVALID_INDEX: u32 = 0x00FF_FFFF;
 
 pub fn checked_range(start: u32, count: u32) -> Range<u32> {
-    if (start + count) >= INVALID_INDEX {
-        0..0
-    } else {
-        start..start + count
+    match start.checked_add(count) {
+        Some(end) if end < INVALID_INDEX => start..end,
+        _ => 0..0,
+    }
+}
+
+/// An index into the table of `T`
+///
+/// Every table is indexed by a plain `u32` in the archive, which makes it easy to use an index
+/// for one table with another (e.g. a [`FileGroup`](crate::archive::file_group::FileGroup) index
+/// where a [`FilePackage`](crate::archive::file_package::FilePackage) index is expected). This type
+/// tags the index with the table it belongs to so that the public API can't be given the wrong one.
+pub struct Index<T>(u32, PhantomData<fn() -> T>);
+
+impl<T> Index<T> {
+    /// The index used by the archive to mean that nothing is referenced
+    pub const INVALID: Self = Self::new(INVALID_INDEX);
+
+    /// Tags a raw index as belonging to the table of `T`
+    pub const fn new(index: u32) -> Self {
+        Self(index, PhantomData)
+    }
+
+    /// Gets the raw index
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+
+    /// Checks if this index is not [`Self::INVALID`]
+    pub const fn is_valid(self) -> bool {
+        self.0 != INVALID_INDEX
+    }
+}
+
+// These are implemented manually since deriving them would require `T` to implement them as well
+
+impl<T> Copy for Index<T> {}
+
+impl<T> Clone for Index<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> PartialEq for Index<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T> Eq for Index<T> {}
+
+impl<T> PartialOrd for Index<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Index<T> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<T> core::hash::Hash for Index<T> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<T> core::fmt::Debug for Index<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = core::any::type_name::<T>();
+        let name = name.rsplit("::").next().unwrap_or(name);
+        write!(f, "Index<{name}>({:#x})", self.0)
+    }
+}
+
+impl<T> From<Index<T>> for u32 {
+    fn from(index: Index<T>) -> Self {
+        index.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_range_rejects_overflow() {
+        assert_eq!(checked_range(INVALID_INDEX - 1, 1), 0..0);
+        assert_eq!(checked_range(INVALID_INDEX - 1, u32::MAX), 0..0);
+        assert_eq!(checked_range(u32::MAX, 2), 0..0);
+    }
+
+    #[test]
+    fn checked_range_keeps_valid_ranges() {
+        assert_eq!(checked_range(4, 3), 4..7);
+        assert_eq!(
+            checked_range(INVALID_INDEX - 2, 1),
+            INVALID_INDEX - 2..INVALID_INDEX - 1
+        );
     }
 }
@@ -1,16 +1,19 @@
 use std::io::{self, Read, Seek, Write};
-use std::ops::Range;
 
 use byteorder::{LittleEndian, ReadBytesExt};
 
-use crate::BinaryRepr;
+use crate::{ArchiveError, BinaryRepr, Decompressor};
+
+/// The size of the header that precedes every compressed data section
+pub(crate) const COMPRESSED_TABLE_SIZE: u32 = 0x10;
 
 /// Extension trait to allow easier reading of archive file data
 pub(crate) trait ReadBinExt {
     /// Reads the exact number of bytes to read a value of `T`
     ///
-    /// SAFETY: The caller must ensure that reading this data at the current location
-    ///     will result in a proper/valid value of `T`
+    /// # Safety
+    /// The caller must ensure that reading this data at the current location
+    /// will result in a proper/valid value of `T`
     unsafe fn read_binary<T: BinaryRepr>(&mut self) -> Result<T, io::Error> {
         let bytes = self.read_count_vec(std::mem::size_of::<T>())?;
         crate::single_value_sanity::<T>(&bytes);
@@ -28,47 +31,168 @@ pub(crate) trait ReadBinExt {
 
     /// Reads a compressed data section, returning it as a decompressed
     /// slice of bytes
-    fn read_compressed_data(&mut self) -> Result<Box<[u8]>, io::Error> {
-        self.read_compressed_data_vec().map(Vec::into_boxed_slice)
+    fn read_compressed_data<D: Decompressor + ?Sized>(
+        &mut self,
+        decompressor: &D,
+    ) -> Result<Box<[u8]>, io::Error> {
+        self.read_compressed_data_vec(decompressor)
+            .map(Vec::into_boxed_slice)
     }
 
     /// Reads a compressed data section, returning it as a decompressed
     /// vec of bytes
-    fn read_compressed_data_vec(&mut self) -> Result<Vec<u8>, io::Error>;
+    fn read_compressed_data_vec<D: Decompressor + ?Sized>(
+        &mut self,
+        decompressor: &D,
+    ) -> Result<Vec<u8>, io::Error>;
+
+    /// Reads a chain of compressed data sections starting at the current location, returning
+    /// all of their decompressed contents concatenated together
+    ///
+    /// Each section is read like [`ReadBinExt::read_compressed_data_vec`], and the next section starts
+    /// `offset_to_next` bytes after the start of the previous one. The chain ends after a section with an
+    /// `offset_to_next` of 0, when the next section starts at or past `end`, or when the next section has
+    /// a table size of 0 or is at the end of the data.
+    ///
+    /// Every section has an `offset_to_next` that points right past it, even the last one, so `end`
+    /// has to be where the data that follows the chain starts.
+    fn read_all_compressed_sections<D: Decompressor + ?Sized>(
+        &mut self,
+        decompressor: &D,
+        end: u64,
+    ) -> Result<Vec<u8>, io::Error>;
+
+    /// Reads `compressed_size` bytes of compressed data at the current location, returning it
+    /// decompressed with `decompressor`
+    fn read_decompressed_data_vec<D: Decompressor + ?Sized>(
+        &mut self,
+        decompressor: &D,
+        compressed_size: u64,
+        decompressed_size: usize,
+    ) -> Result<Vec<u8>, io::Error>;
+
+    /// Decompresses `compressed_size` bytes of ZSTD compressed data at the current location into `out`,
+    /// returning the number of decompressed bytes that were written
+    fn copy_zstd_data<W: Write>(
+        &mut self,
+        compressed_size: u64,
+        decompressed_size: usize,
+        out: &mut W,
+    ) -> Result<u64, io::Error>;
+}
+
+/// Writer that counts the number of bytes that pass through it
+#[cfg(not(target_os = "switch"))]
+struct CountingWriter<'a, W> {
+    inner: &'a mut W,
+    count: u64,
+}
+
+#[cfg(not(target_os = "switch"))]
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
 }
 
 pub(crate) trait WriteBinExt: Write {
     fn write_binary<T: BinaryRepr + Copy>(&mut self, value: &T) -> Result<(), io::Error> {
         self.write_all(value.cast_bytes())
     }
+
+    /// ZSTD compresses `data` and writes it as a single compressed data section, which can be
+    /// read back with [`ReadBinExt::read_compressed_data`]
+    #[cfg(not(target_os = "switch"))]
+    fn write_compressed_section(&mut self, data: &[u8]) -> Result<(), io::Error> {
+        let compressed = zstd::encode_all(data, 0)?;
+
+        for value in [
+            COMPRESSED_TABLE_SIZE,
+            data.len() as u32,
+            compressed.len() as u32,
+            COMPRESSED_TABLE_SIZE + compressed.len() as u32,
+        ] {
+            self.write_all(&value.to_le_bytes())?;
+        }
+
+        self.write_all(&compressed)
+    }
 }
 
 impl<W: Write> WriteBinExt for W {}
 
+/// Reads and decompresses the data of the compressed section whose header starts at `starting_position`,
+/// the reader must be right after the header
+///
+/// The section is checked to fit in the rest of the reader before anything is read, and any error is
+/// reported along with the location and sizes of the section, since a truncated section otherwise
+/// only shows up as an opaque error from the decompressor.
+fn read_section_data<R: Read + Seek, D: Decompressor + ?Sized>(
+    reader: &mut R,
+    decompressor: &D,
+    starting_position: u64,
+    compressed_size: u64,
+    decompressed_size: usize,
+) -> Result<Vec<u8>, io::Error> {
+    let data_start = reader.stream_position()?;
+    let end = reader.seek(io::SeekFrom::End(0))?;
+    reader.seek(io::SeekFrom::Start(data_start))?;
+
+    let remaining = end.saturating_sub(data_start);
+    if compressed_size > remaining {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "Compressed section at {starting_position:#x} needs {compressed_size:#x} bytes, but only {remaining:#x} remain"
+            ),
+        ));
+    }
+
+    reader
+        .read_decompressed_data_vec(decompressor, compressed_size, decompressed_size)
+        .map_err(|e| {
+            let message = format!(
+                "Failed to read compressed section at {starting_position:#x} ({compressed_size:#x} bytes, {decompressed_size:#x} decompressed): {e}"
+            );
+
+            match ArchiveError::from(e) {
+                ArchiveError::Decompress(_) => ArchiveError::Decompress(message).into(),
+                e => io::Error::new(io::Error::from(e).kind(), message),
+            }
+        })
+}
+
 impl<R: Read + Seek> ReadBinExt for R {
     fn read_count_vec(&mut self, count: usize) -> Result<Vec<u8>, io::Error> {
-        // SAFETY: We are initializing a vec with invalid data, but then immediately
-        //      reading into it/initializing. So long as the reader provided implements
-        //      the `read_exact` contract properly, then this is safe.
-        unsafe {
-            let mut data = Vec::with_capacity(count);
-            data.set_len(count);
-            self.read_exact(&mut data)?;
-
-            Ok(data)
+        // The buffer grows as data is read instead of being allocated up front, so a malformed size
+        // fails with an EOF error instead of trying to allocate all of it
+        let mut data = Vec::new();
+        self.take(count as u64).read_to_end(&mut data)?;
+
+        if data.len() != count {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
         }
-    }
 
-    fn read_compressed_data_vec(&mut self) -> Result<Vec<u8>, io::Error> {
-        const REQUIRED_TABLE_SIZE: u32 = 0x10;
+        Ok(data)
+    }
 
+    fn read_compressed_data_vec<D: Decompressor + ?Sized>(
+        &mut self,
+        decompressor: &D,
+    ) -> Result<Vec<u8>, io::Error> {
         let starting_position = self.stream_position()?;
 
         let table_size = self.read_u32::<LittleEndian>()?;
-        if table_size != REQUIRED_TABLE_SIZE {
+        if table_size != COMPRESSED_TABLE_SIZE {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
-                format!("Expected table size of {REQUIRED_TABLE_SIZE:#x}, found {table_size:#x}"),
+                format!("Expected table size of {COMPRESSED_TABLE_SIZE:#x}, found {table_size:#x}"),
             ));
         }
 
@@ -76,140 +200,127 @@ impl<R: Read + Seek> ReadBinExt for R {
         let compressed_size = self.read_u32::<LittleEndian>()? as u64;
         let offset_to_next = self.read_u32::<LittleEndian>()? as u64;
 
-        // SAFETY: We are initializing a vec with valid data by reading it in after creating the buffer
-        let mut data = Vec::with_capacity(decompressed_size);
+        let data = read_section_data(
+            self,
+            decompressor,
+            starting_position,
+            compressed_size,
+            decompressed_size,
+        )?;
 
-        #[cfg(not(target_os = "switch"))]
-        {
-            zstd::stream::copy_decode(self.take(compressed_size), &mut data)?;
-        }
-
-        #[cfg(target_os = "switch")]
-        {
-            #[repr(C)]
-            struct ZSTD_PtrBuffer {
-                pub ptr: *mut u8,
-                pub size: usize,
-                pub pos: usize,
-            }
-
-            #[skyline::from_offset(0x39a2fc0)]
-            fn decompress_stream(
-                thing: *mut u64,
-                output: &mut ZSTD_PtrBuffer,
-                input: &mut ZSTD_PtrBuffer,
-            ) -> usize;
-
-            #[skyline::from_offset(0x35410b0)]
-            fn initialize_decompressor(ptr: *mut u64);
+        self.seek(io::SeekFrom::Start(starting_position + offset_to_next))?;
 
-            #[skyline::from_offset(0x3541030)]
-            fn finalize_decompressor(ptr: *mut u64);
+        Ok(data)
+    }
 
-            let mut buf = Vec::with_capacity(compressed_size as usize);
-            unsafe {
-                buf.set_len(compressed_size as usize);
+    fn read_all_compressed_sections<D: Decompressor + ?Sized>(
+        &mut self,
+        decompressor: &D,
+        end: u64,
+    ) -> Result<Vec<u8>, io::Error> {
+        let mut data = Vec::new();
+
+        loop {
+            let starting_position = self.stream_position()?;
+            if !data.is_empty() && starting_position >= end {
+                break;
             }
 
-            self.read_exact(&mut buf)?;
+            let table_size = match self.read_u32::<LittleEndian>() {
+                Ok(0) => break,
+                Ok(table_size) => table_size,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !data.is_empty() => break,
+                Err(e) => return Err(e),
+            };
 
-            let mut decompressor = [0u64; 2];
-            unsafe {
-                initialize_decompressor(decompressor.as_mut_ptr());
-                data.set_len(decompressed_size);
+            if table_size != COMPRESSED_TABLE_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Expected table size of {COMPRESSED_TABLE_SIZE:#x}, found {table_size:#x}"
+                    ),
+                ));
             }
 
-            let mut input_buffer = ZSTD_PtrBuffer {
-                ptr: buf.as_ptr() as _,
-                size: buf.len(),
-                pos: 0,
-            };
+            let decompressed_size = self.read_u32::<LittleEndian>()? as usize;
+            let compressed_size = self.read_u32::<LittleEndian>()? as u64;
+            let offset_to_next = self.read_u32::<LittleEndian>()? as u64;
 
-            let mut output_buffer = ZSTD_PtrBuffer {
-                ptr: data.as_mut_ptr(),
-                size: data.len(),
-                pos: 0,
-            };
+            data.extend(read_section_data(
+                self,
+                decompressor,
+                starting_position,
+                compressed_size,
+                decompressed_size,
+            )?);
 
-            unsafe {
-                decompress_stream(decompressor[1] as _, &mut output_buffer, &mut input_buffer);
-                finalize_decompressor(decompressor.as_mut_ptr());
+            if offset_to_next == 0 {
+                break;
             }
-        }
 
-        if data.len() != decompressed_size {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!(
-                    "Expected a decompressed size of {decompressed_size:#x}, received {:#x}",
-                    data.len()
-                ),
-            ));
+            self.seek(io::SeekFrom::Start(starting_position + offset_to_next))?;
         }
 
-        self.seek(io::SeekFrom::Start(starting_position + offset_to_next))?;
-
         Ok(data)
     }
-}
 
-/// Simple reader that reads over a slice of borrowed bytes,
-/// allowing the caller to cast the data into other forms when needed
-pub(crate) struct BorrowedReader<'a> {
-    data: &'a [u8],
-    cursor: usize,
-}
+    fn read_decompressed_data_vec<D: Decompressor + ?Sized>(
+        &mut self,
+        decompressor: &D,
+        compressed_size: u64,
+        decompressed_size: usize,
+    ) -> Result<Vec<u8>, io::Error> {
+        let compressed = self.read_count_vec(compressed_size as usize)?;
+        let data = decompressor
+            .decompress(&compressed, decompressed_size)
+            .map_err(|e| io::Error::from(ArchiveError::Decompress(e.to_string())))?;
 
-#[allow(dead_code)]
-impl<'a> BorrowedReader<'a> {
-    /// Creates a new borrowed reader, with a cursor of `0`
-    pub fn new(data: &'a [u8]) -> Self {
-        Self { data, cursor: 0 }
-    }
+        if data.len() != decompressed_size {
+            return Err(ArchiveError::Decompress(format!(
+                "Expected a decompressed size of {decompressed_size:#x}, received {:#x}",
+                data.len()
+            ))
+            .into());
+        }
 
-    /// SAFETY: The caller guarantees that where we are reading is valid
-    ///     and initialized contents for a value of T
-    pub unsafe fn read_copy<T: BinaryRepr + Copy>(&mut self) -> T {
-        let data = T::cast(&self.data[self.cursor..]);
-        self.cursor += std::mem::size_of::<T>();
-        *data
+        Ok(data)
     }
 
-    /// SAFETY: The caller guarantees that where we are reading is valid
-    ///     and initialized contents for a value of T
-    pub unsafe fn read<T: BinaryRepr>(&mut self) -> &T {
-        let data = T::cast(&self.data[self.cursor..]);
-        self.cursor += std::mem::size_of::<T>();
-        data
-    }
+    fn copy_zstd_data<W: Write>(
+        &mut self,
+        compressed_size: u64,
+        decompressed_size: usize,
+        out: &mut W,
+    ) -> Result<u64, io::Error> {
+        // The switch decompressor works on whole buffers, so there is nothing to gain by streaming
+        #[cfg(target_os = "switch")]
+        let written = {
+            let data = self.read_decompressed_data_vec(
+                &crate::ZstdDecompressor,
+                compressed_size,
+                decompressed_size,
+            )?;
+            out.write_all(&data)?;
+            data.len() as u64
+        };
 
-    /// SAFETY: The caller guarantees that where we are reading is valid
-    ///     and initialized contents for `count` values of T
-    pub unsafe fn read_slice<T: BinaryRepr>(&mut self, count: usize) -> &[T] {
-        let data = T::cast_slice(
-            &self.data[self.cursor..(self.cursor + std::mem::size_of::<T>() * count)],
-        );
-        self.cursor += std::mem::size_of::<T>() * count;
-        data
-    }
+        #[cfg(not(target_os = "switch"))]
+        let written = {
+            let mut writer = CountingWriter {
+                inner: out,
+                count: 0,
+            };
+            zstd::stream::copy_decode(self.take(compressed_size), &mut writer)?;
+            writer.count
+        };
+
+        if written != decompressed_size as u64 {
+            return Err(ArchiveError::Decompress(format!(
+                "Expected a decompressed size of {decompressed_size:#x}, received {written:#x}"
+            ))
+            .into());
+        }
 
-    /// Advances the cursor by an equivalent size of `count * std::mem::size_of::<T>()` while
-    /// performing sanity checks on the byte range itself
-    ///
-    /// This is useful if you don't need the data right now, but need to know where it is
-    /// located for future operations
-    ///
-    /// The return value of this function is guaranteed to be within range of the data
-    /// provided when constructing this reader, so if you do not mutate the
-    /// data container in any way it is safe to call `get_unchecked` with the range
-    pub fn advance_byte_slice<T: Sized>(&mut self, count: usize) -> Range<usize> {
-        let range = self.cursor..self.cursor + count * std::mem::size_of::<T>();
-
-        // Check to make sure that this byte slice is appropriate for how we
-        // are going to use it
-        crate::slice_sanity::<T>(&self.data[range.clone()]);
-
-        self.cursor += count;
-        range
+        Ok(written)
     }
 }
@@ -1,5 +1,14 @@
-#![feature(pointer_is_aligned)]
+//! Reading and editing of the resource tables of `data.arc`
+//!
+//! With the default `std` feature disabled and the `core` feature enabled, this crate is `no_std` and only
+//! contains the binary layout of the tables: the table structs, [`BinaryRepr`] and the borrowed reader.
+//! Reading files, decompression and everything that edits or serializes the tables need `std`.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+// The crate-internal helpers are only used by the std parts of the crate
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+#[cfg(feature = "std")]
 use hash40::Hash40;
 
 #[cfg(not(target_endian = "little"))]
@@ -21,164 +30,101 @@ pub mod prelude {
         stream_folder::StreamFolder,
         stream_path::StreamPath,
     };
+    pub use super::index::Index;
 }
 
 pub mod archive;
 pub mod index;
+#[cfg(feature = "std")]
 pub mod refs;
 
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "std")]
+mod decompress;
+#[cfg(feature = "std")]
+mod error;
 mod hash;
+#[cfg(feature = "std")]
 mod io;
+mod reader;
+mod repr;
+
+#[cfg(feature = "std")]
+pub use decompress::{Decompressor, ZstdDecompressor};
+#[cfg(feature = "std")]
+pub use error::{
+    ArchiveError, BackRefAnomaly, DuplicatePath, FlaggedRow, RoundtripMismatch, ValidationError,
+};
+pub use reader::BorrowedReader;
+pub use repr::{BinaryRepr, CastError};
+
+#[cfg(feature = "std")]
+pub(crate) use repr::single_value_sanity;
+pub(crate) use repr::slice_sanity;
 
 mod __sealed {
     pub trait Sealed {}
 }
 
-#[cfg(feature = "cast-sanity")]
-#[inline(always)]
-#[track_caller]
-fn single_value_sanity<T: Sized>(bytes: &[u8]) {
-    let this_ptr = bytes.as_ptr().cast::<T>();
-
-    assert!(this_ptr.is_aligned());
-    assert!(bytes.len() >= std::mem::size_of::<T>());
+#[cfg(feature = "std")]
+pub trait IntoHash {
+    fn into_hash(self) -> Hash40;
 }
 
-#[cfg(not(feature = "cast-sanity"))]
-#[inline(always)]
-fn single_value_sanity<T: Sized>(_bytes: &[u8]) {}
-
-#[cfg(feature = "cast-sanity")]
-#[inline(always)]
-#[track_caller]
-fn slice_sanity<T: Sized>(bytes: &[u8]) {
-    let this_ptr = bytes.as_ptr().cast::<T>();
-
-    assert!(this_ptr.is_aligned());
-    assert_eq!(bytes.len() % std::mem::size_of::<T>(), 0x0);
+#[cfg(feature = "std")]
+/// Normalizes a path the way that paths are stored in the archive and hashes it
+///
+/// Paths in the archive are hashed exactly as written, and they are always:
+/// - ASCII lowercase
+/// - separated by forward slashes
+/// - relative to the root of the archive, without a leading slash
+///
+/// So `/Fighter\Mario\model.nutexb` becomes `fighter/mario/model.nutexb` before it is hashed.
+pub fn normalize_and_hash(path: &str) -> Hash40 {
+    let path = path.replace('\\', "/").to_ascii_lowercase();
+    Hash40::new(path.trim_start_matches('/'))
 }
 
-#[cfg(not(feature = "cast-sanity"))]
-#[inline(always)]
-fn slice_sanity<T: Sized>(_bytes: &[u8]) {}
-
-/// Trait that enables zero-copy reading of archive tables
-pub trait BinaryRepr: Sized {
-    /// Casts a slice of bytes to a reference of this type
-    /// SAFETY: The caller must ensure that the bytes provided contain a valid representation of this type
-    #[track_caller]
-    unsafe fn cast(bytes: &[u8]) -> &Self {
-        single_value_sanity::<Self>(bytes);
-
-        &*bytes.as_ptr().cast::<Self>()
-    }
-
-    fn cast_bytes(&self) -> &[u8]
-    where
-        Self: Copy,
-    {
-        let ptr = (self as *const Self).cast::<u8>();
-        // SAFETY: This slice is the exact size as this value
-        unsafe { std::slice::from_raw_parts(ptr, std::mem::size_of::<Self>()) }
-    }
-
-    /// Casts a slice of bytes to a slice of this type
-    ///
-    /// When the `cast-sanity` feature is disabled, this has the same functionality as [`BinaryRepr::cast_slice_trailing`]
-    /// SAFETY: The caller must ensure that the bytes provided contain a valid representation of at least one
-    ///         value of this type
-    #[track_caller]
-    unsafe fn cast_slice(bytes: &[u8]) -> &[Self] {
-        slice_sanity::<Self>(bytes);
-
-        std::slice::from_raw_parts(
-            bytes.as_ptr().cast::<Self>(),
-            bytes.len() / std::mem::size_of::<Self>(),
-        )
-    }
-
-    fn cast_slice_bytes(slice: &[Self]) -> &[u8]
-    where
-        Self: Copy,
-    {
-        let ptr = slice.as_ptr().cast::<u8>();
-        // SAFETY: This slice is the exact size as this value
-        unsafe { std::slice::from_raw_parts(ptr, std::mem::size_of::<Self>() * slice.len()) }
-    }
-
-    /// Casts a slice of bytes to a slice of this type, ignoring trailing bytes
-    /// SAFETY: The caller must ensure that the bytes provided contain a valid representation of at least one
-    ///         value of this type
-    #[track_caller]
-    unsafe fn cast_slice_trailing(bytes: &[u8]) -> &[Self] {
-        single_value_sanity::<Self>(bytes);
-
-        std::slice::from_raw_parts(
-            bytes.as_ptr().cast::<Self>(),
-            bytes.len() / std::mem::size_of::<Self>(),
-        )
-    }
-
-    /// Casts a slice of bytes to a mutable reference of this type
-    /// SAFETY: The caller must ensure that the bytes provided contain a valid representation of this type
-    #[track_caller]
-    unsafe fn cast_mut(bytes: &mut [u8]) -> &mut Self {
-        single_value_sanity::<Self>(bytes);
-
-        &mut *bytes.as_mut_ptr().cast::<Self>()
-    }
-
-    /// Casts a slice of bytes to a mutable slice of this type
-    ///
-    /// When the `cast-sanity` feature is disabled, this has the same functionality as [`BinaryRepr::cast_slice_trailing_mut`]
-    /// SAFETY: The caller must ensure that the bytes provided contain a valid representation of at least one
-    ///         value of this type
-    #[track_caller]
-    unsafe fn cast_slice_mut(bytes: &mut [u8]) -> &mut [Self] {
-        slice_sanity::<Self>(bytes);
-
-        std::slice::from_raw_parts_mut(
-            bytes.as_mut_ptr().cast::<Self>(),
-            bytes.len() / std::mem::size_of::<Self>(),
-        )
-    }
-
-    /// Casts a slice of bytes to a mutable slice of this type, ignoring trailing bytes
-    /// SAFETY: The caller must ensure that the bytes provided contain a valid representation of at least one
-    ///         value of this type
-    #[track_caller]
-    unsafe fn cast_slice_trailing_mut(bytes: &mut [u8]) -> &mut [Self] {
-        single_value_sanity::<Self>(bytes);
-
-        std::slice::from_raw_parts_mut(
-            bytes.as_mut_ptr().cast::<Self>(),
-            bytes.len() / std::mem::size_of::<Self>(),
-        )
-    }
-}
-
-pub trait IntoHash {
-    fn into_hash(self) -> Hash40;
+#[cfg(feature = "std")]
+/// Normalizes a stream path the same way as [`normalize_and_hash`] and hashes it
+///
+/// Stream paths are stored with a `stream:/` prefix, which is added if it is missing. So both
+/// `Sound\BGM\bgm.nus3audio` and `stream:/sound/bgm/bgm.nus3audio` hash to the same value.
+pub fn normalize_stream_and_hash(path: &str) -> Hash40 {
+    const PREFIX: &str = "stream:";
+
+    let path = path.replace('\\', "/").to_ascii_lowercase();
+    let path = path.strip_prefix(PREFIX).unwrap_or(&path);
+    Hash40::new(&format!("{PREFIX}/{}", path.trim_start_matches('/')))
 }
 
+#[cfg(feature = "std")]
 impl IntoHash for &str {
     fn into_hash(self) -> Hash40 {
         Hash40::new(self)
     }
 }
 
+#[cfg(feature = "std")]
 impl IntoHash for String {
     fn into_hash(self) -> Hash40 {
         Hash40::new(self.as_str())
     }
 }
 
+#[cfg(feature = "std")]
 impl IntoHash for Hash40 {
     fn into_hash(self) -> Hash40 {
         self
     }
 }
 
+#[cfg(feature = "std")]
 impl IntoHash for u64 {
     fn into_hash(self) -> Hash40 {
         Hash40(self)
@@ -208,6 +154,24 @@ pub enum Locale {
 
 impl Locale {
     pub const COUNT: usize = 14;
+
+    /// Every valid locale, in the order of their ordinals
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::Japanese,
+        Self::UsEnglish,
+        Self::UsFrench,
+        Self::UsSpanish,
+        Self::EuEnglish,
+        Self::EuFrench,
+        Self::EuSpanish,
+        Self::German,
+        Self::Dutch,
+        Self::Italian,
+        Self::Russian,
+        Self::Korean,
+        Self::Chinese,
+        Self::Taiwanese,
+    ];
 }
 
 #[repr(i32)]
@@ -224,4 +188,33 @@ pub enum Region {
 
 impl Region {
     pub const COUNT: usize = 5;
+
+    /// Every valid region, in the order of their ordinals
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::Japan,
+        Self::NorthAmerica,
+        Self::Europe,
+        Self::Korea,
+        Self::China,
+    ];
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_stream_and_hash_adds_the_prefix() {
+        let expected = Hash40::new("stream:/sound/bgm/bgm_crs2_01_menu.nus3audio");
+        for path in [
+            "sound/bgm/bgm_crs2_01_menu.nus3audio",
+            "/sound/bgm/bgm_crs2_01_menu.nus3audio",
+            "sound\\bgm\\bgm_crs2_01_menu.nus3audio",
+            "Sound/BGM/BGM_CRS2_01_MENU.nus3audio",
+            "stream:/sound/bgm/bgm_crs2_01_menu.nus3audio",
+            "STREAM:\\sound\\bgm\\bgm_crs2_01_menu.nus3audio",
+        ] {
+            assert_eq!(normalize_stream_and_hash(path), expected, "{path}");
+        }
+    }
 }
@@ -0,0 +1,92 @@
+//! Python bindings for reading archives, enabled with the `python` feature
+//!
+//! The extension module itself is built by the `arc-reader-py` crate, which can be built and installed
+//! with `maturin develop` from its directory, see its `pyproject.toml`.
+
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use pyo3::{
+    exceptions::{PyIOError, PyKeyError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+
+use crate::{archive::Archive, ArchiveError, IntoHash};
+
+fn to_py_err(error: ArchiveError) -> PyErr {
+    match error {
+        ArchiveError::Io(error) => PyIOError::new_err(error.to_string()),
+        ArchiveError::MissingPath(_) => PyKeyError::new_err(error.to_string()),
+        error => PyValueError::new_err(error.to_string()),
+    }
+}
+
+/// An archive, along with the file that it was read from
+#[pyclass(name = "Archive", unsendable)]
+pub struct PyArchive {
+    archive: Archive,
+    reader: BufReader<File>,
+}
+
+#[pymethods]
+impl PyArchive {
+    /// Opens and reads the archive at `path`
+    #[staticmethod]
+    fn open(path: PathBuf) -> PyResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let archive = Archive::read(&mut reader).map_err(to_py_err)?;
+
+        Ok(Self { archive, reader })
+    }
+
+    /// Loads a newline separated list of labels, which are used to turn hashes back into strings
+    #[staticmethod]
+    fn load_labels(path: PathBuf) -> PyResult<()> {
+        Archive::load_labels(path)?;
+
+        Ok(())
+    }
+
+    /// Reads and decompresses the file at `path`
+    fn read_file<'py>(&mut self, py: Python<'py>, path: &str) -> PyResult<&'py PyBytes> {
+        let data = self
+            .archive
+            .read_file(&mut self.reader, path)
+            .map_err(to_py_err)?;
+
+        Ok(PyBytes::new(py, &data))
+    }
+
+    /// Lists the path of every file in the archive
+    ///
+    /// Paths without a loaded label are formatted as hexadecimal hashes
+    fn list_paths(&self) -> Vec<String> {
+        self.archive
+            .resource
+            .file_path
+            .iter()
+            .map(|(_, path)| path.path().to_string())
+            .collect()
+    }
+
+    /// Gets the decompressed size of the file at `path`, in bytes
+    fn file_size(&self, path: &str) -> PyResult<u32> {
+        let (_, data) = self
+            .archive
+            .resolve_file_data(path.into_hash())
+            .map_err(to_py_err)?;
+
+        let data = self.archive.get_file_data(data).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "The file desc of {path} points to file data {data:#x}, which is not in the archive"
+            ))
+        })?;
+
+        Ok(data.decompressed_size())
+    }
+}
+
+/// Adds the classes of the bindings to the extension module `m`
+pub fn register(m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyArchive>()
+}
@@ -0,0 +1,117 @@
+//! Reading of borrowed archive data
+//!
+//! Like [`crate::repr`], this only depends on `core`.
+
+use core::ops::Range;
+
+use crate::BinaryRepr;
+
+/// Simple reader that reads over a slice of borrowed bytes,
+/// allowing the caller to cast the data into other forms when needed
+pub struct BorrowedReader<'a> {
+    data: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> BorrowedReader<'a> {
+    /// Creates a new borrowed reader, with a cursor of `0`
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, cursor: 0 }
+    }
+
+    /// Reads a copy of the value of `T` at the cursor, and advances past it
+    ///
+    /// # Safety
+    /// The caller guarantees that where we are reading is valid
+    /// and initialized contents for a value of T
+    pub unsafe fn read_copy<T: BinaryRepr + Copy>(&mut self) -> T {
+        let data = T::cast(&self.data[self.cursor..]);
+        self.cursor += core::mem::size_of::<T>();
+        *data
+    }
+
+    /// Borrows the value of `T` at the cursor, and advances past it
+    ///
+    /// # Safety
+    /// The caller guarantees that where we are reading is valid
+    /// and initialized contents for a value of T
+    pub unsafe fn read<T: BinaryRepr>(&mut self) -> &T {
+        let data = T::cast(&self.data[self.cursor..]);
+        self.cursor += core::mem::size_of::<T>();
+        data
+    }
+
+    /// Borrows the `count` values of `T` at the cursor, and advances past them
+    ///
+    /// # Safety
+    /// The caller guarantees that where we are reading is valid
+    /// and initialized contents for `count` values of T
+    pub unsafe fn read_slice<T: BinaryRepr>(&mut self, count: usize) -> &[T] {
+        let data = T::cast_slice(
+            &self.data[self.cursor..(self.cursor + core::mem::size_of::<T>() * count)],
+        );
+        self.cursor += core::mem::size_of::<T>() * count;
+        data
+    }
+
+    /// Bounds-checked variant of [`BorrowedReader::read`]
+    ///
+    /// Returns `None` (without advancing the cursor) if there are not enough bytes
+    /// remaining or if the cursor is not suitably aligned for `T`
+    pub fn try_read<T: BinaryRepr>(&mut self) -> Option<&'a T> {
+        let end = self.cursor.checked_add(core::mem::size_of::<T>())?;
+        let value = T::try_cast(self.data.get(self.cursor..end)?)?;
+        self.cursor = end;
+        Some(value)
+    }
+
+    /// Bounds-checked variant of [`BorrowedReader::read_slice`]
+    ///
+    /// Returns `None` (without advancing the cursor) if there are not enough bytes
+    /// remaining for `count` values or if the cursor is not suitably aligned for `T`
+    pub fn try_read_slice<T: BinaryRepr>(&mut self, count: usize) -> Option<&'a [T]> {
+        let len = core::mem::size_of::<T>().checked_mul(count)?;
+        let end = self.cursor.checked_add(len)?;
+        let values = T::try_cast_slice(self.data.get(self.cursor..end)?)?;
+        self.cursor = end;
+        Some(values)
+    }
+
+    /// Advances the cursor by an equivalent size of `count * core::mem::size_of::<T>()` while
+    /// performing sanity checks on the byte range itself
+    ///
+    /// This is useful if you don't need the data right now, but need to know where it is
+    /// located for future operations
+    ///
+    /// The return value of this function is guaranteed to be within range of the data
+    /// provided when constructing this reader, so if you do not mutate the
+    /// data container in any way it is safe to call `get_unchecked` with the range
+    pub fn advance_byte_slice<T: Sized>(&mut self, count: usize) -> Range<usize> {
+        let range = self.cursor..self.cursor + count * core::mem::size_of::<T>();
+
+        // Check to make sure that this byte slice is appropriate for how we
+        // are going to use it
+        crate::slice_sanity::<T>(&self.data[range.clone()]);
+
+        self.cursor = range.end;
+        range
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C, align(4))]
+    struct Aligned([u8; 24]);
+
+    #[test]
+    fn advance_byte_slice_advances_by_bytes() {
+        let bytes = Aligned([0; 24]);
+        let mut reader = BorrowedReader::new(&bytes.0);
+
+        assert_eq!(reader.advance_byte_slice::<u32>(3), 0..12);
+        assert_eq!(reader.advance_byte_slice::<u32>(2), 12..20);
+        assert_eq!(reader.cursor, 20);
+    }
+}
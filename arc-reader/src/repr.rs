@@ -0,0 +1,250 @@
+//! Zero-copy casting of the binary layout of the archive
+//!
+//! Everything in this module only depends on `core`, so that the layout types can eventually be
+//! used without `std`. Keep it that way, anything that needs `std::io` belongs in [`crate::io`].
+
+#[cfg(feature = "cast-sanity")]
+#[inline(always)]
+#[track_caller]
+pub(crate) fn single_value_sanity<T: Sized>(bytes: &[u8]) {
+    let this_ptr = bytes.as_ptr().cast::<T>();
+
+    assert_eq!(this_ptr as usize % core::mem::align_of::<T>(), 0x0);
+    assert!(bytes.len() >= core::mem::size_of::<T>());
+}
+
+#[cfg(not(feature = "cast-sanity"))]
+#[inline(always)]
+pub(crate) fn single_value_sanity<T: Sized>(_bytes: &[u8]) {}
+
+#[cfg(feature = "cast-sanity")]
+#[inline(always)]
+#[track_caller]
+pub(crate) fn slice_sanity<T: Sized>(bytes: &[u8]) {
+    let this_ptr = bytes.as_ptr().cast::<T>();
+
+    assert_eq!(this_ptr as usize % core::mem::align_of::<T>(), 0x0);
+    assert!(bytes.len().is_multiple_of(core::mem::size_of::<T>()));
+}
+
+#[cfg(not(feature = "cast-sanity"))]
+#[inline(always)]
+pub(crate) fn slice_sanity<T: Sized>(_bytes: &[u8]) {}
+
+/// Why bytes could not be cast by [`BinaryRepr::cast_checked`] or [`BinaryRepr::cast_slice_checked`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CastError {
+    /// There are fewer bytes than the size of one value
+    TooShort { size: usize, len: usize },
+
+    /// The length of the bytes is not a multiple of the size of one value
+    TrailingBytes { size: usize, len: usize },
+
+    /// The bytes do not start on the alignment of the type
+    Misaligned { align: usize, address: usize },
+}
+
+impl core::fmt::Display for CastError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooShort { size, len } => {
+                write!(f, "Expected at least {size:#x} bytes, found {len:#x}")
+            }
+            Self::TrailingBytes { size, len } => {
+                write!(f, "Expected a multiple of {size:#x} bytes, found {len:#x}")
+            }
+            Self::Misaligned { align, address } => {
+                write!(
+                    f,
+                    "Expected alignment of {align:#x}, found address {address:#x}"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for CastError {}
+
+/// Checks that `bytes` start on the alignment of `T`
+fn check_alignment<T>(bytes: &[u8]) -> Result<(), CastError> {
+    let align = core::mem::align_of::<T>();
+    let address = bytes.as_ptr() as usize;
+    if !address.is_multiple_of(align) {
+        return Err(CastError::Misaligned { align, address });
+    }
+
+    Ok(())
+}
+
+/// Trait that enables zero-copy reading of archive tables
+///
+/// # Safety
+/// Implementors must be plain data that is valid for any bit pattern, since the
+/// checked casts ([`BinaryRepr::cast_checked`] and [`BinaryRepr::cast_slice_checked`]) are safe
+///
+/// This trait used to be safe to implement, so implementations outside of this crate have to be
+/// changed to `unsafe impl` after making sure that they uphold this contract. This is a breaking change.
+pub unsafe trait BinaryRepr: Sized {
+    /// Casts a slice of bytes to a reference of this type
+    ///
+    /// # Safety
+    /// The caller must ensure that the bytes provided contain a valid representation of this type
+    #[track_caller]
+    unsafe fn cast(bytes: &[u8]) -> &Self {
+        single_value_sanity::<Self>(bytes);
+
+        &*bytes.as_ptr().cast::<Self>()
+    }
+
+    fn cast_bytes(&self) -> &[u8]
+    where
+        Self: Copy,
+    {
+        let ptr = (self as *const Self).cast::<u8>();
+        // SAFETY: This slice is the exact size as this value
+        unsafe { core::slice::from_raw_parts(ptr, core::mem::size_of::<Self>()) }
+    }
+
+    /// Casts a slice of bytes to a slice of this type
+    ///
+    /// When the `cast-sanity` feature is disabled, this has the same functionality as [`BinaryRepr::cast_slice_trailing`]
+    ///
+    /// # Safety
+    /// The caller must ensure that the bytes provided contain a valid representation of at least one
+    /// value of this type
+    #[track_caller]
+    unsafe fn cast_slice(bytes: &[u8]) -> &[Self] {
+        slice_sanity::<Self>(bytes);
+
+        core::slice::from_raw_parts(
+            bytes.as_ptr().cast::<Self>(),
+            bytes.len() / core::mem::size_of::<Self>(),
+        )
+    }
+
+    fn cast_slice_bytes(slice: &[Self]) -> &[u8]
+    where
+        Self: Copy,
+    {
+        let ptr = slice.as_ptr().cast::<u8>();
+        // SAFETY: This slice is the exact size as this value
+        unsafe { core::slice::from_raw_parts(ptr, core::mem::size_of::<Self>() * slice.len()) }
+    }
+
+    /// Casts a slice of bytes to a slice of this type, ignoring trailing bytes
+    ///
+    /// # Safety
+    /// The caller must ensure that the bytes provided contain a valid representation of at least one
+    /// value of this type
+    #[track_caller]
+    unsafe fn cast_slice_trailing(bytes: &[u8]) -> &[Self] {
+        single_value_sanity::<Self>(bytes);
+
+        core::slice::from_raw_parts(
+            bytes.as_ptr().cast::<Self>(),
+            bytes.len() / core::mem::size_of::<Self>(),
+        )
+    }
+
+    /// Casts a slice of bytes to a mutable reference of this type
+    ///
+    /// # Safety
+    /// The caller must ensure that the bytes provided contain a valid representation of this type
+    #[track_caller]
+    unsafe fn cast_mut(bytes: &mut [u8]) -> &mut Self {
+        single_value_sanity::<Self>(bytes);
+
+        &mut *bytes.as_mut_ptr().cast::<Self>()
+    }
+
+    /// Casts a slice of bytes to a mutable slice of this type
+    ///
+    /// When the `cast-sanity` feature is disabled, this has the same functionality as [`BinaryRepr::cast_slice_trailing_mut`]
+    ///
+    /// # Safety
+    /// The caller must ensure that the bytes provided contain a valid representation of at least one
+    /// value of this type
+    #[track_caller]
+    unsafe fn cast_slice_mut(bytes: &mut [u8]) -> &mut [Self] {
+        slice_sanity::<Self>(bytes);
+
+        core::slice::from_raw_parts_mut(
+            bytes.as_mut_ptr().cast::<Self>(),
+            bytes.len() / core::mem::size_of::<Self>(),
+        )
+    }
+
+    /// Casts a slice of bytes to a mutable slice of this type, ignoring trailing bytes
+    ///
+    /// # Safety
+    /// The caller must ensure that the bytes provided contain a valid representation of at least one
+    /// value of this type
+    #[track_caller]
+    unsafe fn cast_slice_trailing_mut(bytes: &mut [u8]) -> &mut [Self] {
+        single_value_sanity::<Self>(bytes);
+
+        core::slice::from_raw_parts_mut(
+            bytes.as_mut_ptr().cast::<Self>(),
+            bytes.len() / core::mem::size_of::<Self>(),
+        )
+    }
+
+    /// Casts a slice of bytes to a reference of this type, returning an error if there are
+    /// not enough bytes or if they are not aligned for this type
+    ///
+    /// Unlike [`BinaryRepr::cast`], these checks are always performed regardless of
+    /// the `cast-sanity` feature, so they can be opted into per call
+    fn cast_checked(bytes: &[u8]) -> Result<&Self, CastError> {
+        let size = core::mem::size_of::<Self>();
+        if bytes.len() < size {
+            return Err(CastError::TooShort {
+                size,
+                len: bytes.len(),
+            });
+        }
+
+        check_alignment::<Self>(bytes)?;
+
+        // SAFETY: We have checked the length and alignment, and implementors of this trait
+        //  are valid for any bit pattern
+        Ok(unsafe { &*bytes.as_ptr().cast::<Self>() })
+    }
+
+    /// Casts a slice of bytes to a slice of this type, returning an error if the length is not
+    /// a multiple of the size of this type or if the bytes are not aligned for this type
+    ///
+    /// Unlike [`BinaryRepr::cast_slice`], these checks are always performed regardless of
+    /// the `cast-sanity` feature, so they can be opted into per call
+    fn cast_slice_checked(bytes: &[u8]) -> Result<&[Self], CastError> {
+        let size = core::mem::size_of::<Self>();
+        if size == 0 || !bytes.len().is_multiple_of(size) {
+            return Err(CastError::TrailingBytes {
+                size,
+                len: bytes.len(),
+            });
+        }
+
+        check_alignment::<Self>(bytes)?;
+
+        // SAFETY: See `cast_checked`
+        Ok(unsafe {
+            core::slice::from_raw_parts(bytes.as_ptr().cast::<Self>(), bytes.len() / size)
+        })
+    }
+
+    /// Casts a slice of bytes to a reference of this type, returning `None` if there are
+    /// not enough bytes or if they are not aligned for this type
+    ///
+    /// See [`BinaryRepr::cast_checked`]
+    fn try_cast(bytes: &[u8]) -> Option<&Self> {
+        Self::cast_checked(bytes).ok()
+    }
+
+    /// Casts a slice of bytes to a slice of this type, returning `None` if the length is not
+    /// a multiple of the size of this type or if the bytes are not aligned for this type
+    ///
+    /// See [`BinaryRepr::cast_slice_checked`]
+    fn try_cast_slice(bytes: &[u8]) -> Option<&[Self]> {
+        Self::cast_slice_checked(bytes).ok()
+    }
+}